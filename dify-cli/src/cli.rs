@@ -0,0 +1,238 @@
+//! The command-line arguments.
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+
+/// A command-line tool for Dify apps.
+#[derive(Debug, Parser)]
+#[command(name = "dify", version, about)]
+pub struct Cli {
+    #[command(flatten)]
+    pub global: GlobalArgs,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// The arguments shared by all commands.
+#[derive(Debug, Args)]
+pub struct GlobalArgs {
+    /// The base URL of the Dify API, overrides the base URL of the profile.
+    #[arg(long, global = true, env = "DIFY_BASE_URL")]
+    pub base_url: Option<String>,
+    /// The API key of the app, used when no profile is selected.
+    #[arg(long, global = true, env = "DIFY_API_KEY", hide_env_values = true)]
+    pub api_key: Option<String>,
+    /// The config file of the app profiles, `.toml` or `.json`.
+    /// Defaults to `$XDG_CONFIG_HOME/dify/config.toml` or `~/.config/dify/config.toml`.
+    #[arg(long, global = true, env = "DIFY_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// The app profile of the config file, the default app if not set.
+    #[arg(long, short = 'p', global = true, env = "DIFY_PROFILE")]
+    pub profile: Option<String>,
+    /// The user identifier sent with the requests.
+    #[arg(
+        long,
+        short = 'u',
+        global = true,
+        env = "DIFY_USER",
+        default_value = "dify-cli"
+    )]
+    pub user: String,
+    /// The request timeout in seconds, overrides the timeout of the profile.
+    #[arg(long, global = true, env = "DIFY_TIMEOUT")]
+    pub timeout: Option<u64>,
+    /// Prints JSON instead of human-readable output, one event per line for streams.
+    #[arg(long, global = true)]
+    pub json: bool,
+}
+
+/// The commands.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Chats with a chat app, interactively when no query is given.
+    Chat(ChatArgs),
+    /// Runs a workflow app.
+    RunWorkflow(RunWorkflowArgs),
+    /// Runs a workflow app over the rows of a CSV or JSONL file, resuming a previous run.
+    BatchWorkflow(BatchWorkflowArgs),
+    /// Sends a completion message to a completion app.
+    Complete(CompleteArgs),
+    /// Runs a YAML regression test suite against the app.
+    Test(TestArgs),
+    /// Manages the conversations of the user.
+    #[command(subcommand)]
+    Conversations(ConversationsCommand),
+    /// Lists the messages of a conversation in chronological order.
+    Messages(MessagesArgs),
+    /// Uploads a file for use in messages and workflows.
+    Upload(UploadArgs),
+    /// Converts text to speech.
+    Tts(TtsArgs),
+    /// Converts speech to text.
+    Stt(SttArgs),
+    /// Shows the parameters of the app.
+    Params,
+    /// Shows the meta information of the app.
+    Meta,
+}
+
+/// The inputs of an app.
+#[derive(Debug, Args)]
+pub struct InputArgs {
+    /// An input variable, `key=value` for a string or `key:=json` for a JSON value.
+    #[arg(long = "input", short = 'i', value_name = "KEY=VALUE")]
+    pub inputs: Vec<String>,
+    /// A file to upload, `key=path` for a file input variable
+    /// (repeat the key for a file list), or `path` for the files of the request.
+    #[arg(long = "file", short = 'f', value_name = "[KEY=]PATH")]
+    pub files: Vec<String>,
+}
+
+/// The arguments of `dify chat`.
+#[derive(Debug, Args)]
+pub struct ChatArgs {
+    /// The query, starts an interactive chat if not given.
+    pub query: Option<String>,
+    /// The ID of the conversation to continue.
+    #[arg(long, short = 'c')]
+    pub conversation: Option<String>,
+    #[command(flatten)]
+    pub inputs: InputArgs,
+    /// Waits for the complete answer instead of streaming it.
+    #[arg(long)]
+    pub no_stream: bool,
+}
+
+/// The arguments of `dify run-workflow`.
+#[derive(Debug, Args)]
+pub struct RunWorkflowArgs {
+    #[command(flatten)]
+    pub inputs: InputArgs,
+    /// The ID of the workflow version to run, the published version if not given.
+    #[arg(long)]
+    pub workflow_id: Option<String>,
+    /// Waits for the result instead of streaming the progress.
+    #[arg(long)]
+    pub no_stream: bool,
+}
+
+/// The arguments of `dify batch-workflow`.
+#[derive(Debug, Args)]
+pub struct BatchWorkflowArgs {
+    /// The input file, `.csv` with a header row, `.jsonl` or `.ndjson`.
+    pub input: PathBuf,
+    /// The JSONL results file, resumed if it exists.
+    #[arg(long, short = 'o')]
+    pub output: PathBuf,
+    /// Maps a column of the input to a variable of the workflow.
+    #[arg(long = "map", short = 'm', value_name = "COLUMN=VARIABLE")]
+    pub mappings: Vec<String>,
+    /// The number of rows run concurrently.
+    #[arg(long, short = 'c', default_value_t = 4)]
+    pub concurrency: usize,
+    /// The maximum number of retries of transient failures per row.
+    #[arg(long, default_value_t = 2)]
+    pub retries: u32,
+    /// The ID of the workflow version to run, the published version if not given.
+    #[arg(long)]
+    pub workflow_id: Option<String>,
+    /// Runs the rows without validating the inputs against the parameters.
+    #[arg(long)]
+    pub no_validate: bool,
+    /// Runs the failed and invalid rows of a previous run again.
+    #[arg(long)]
+    pub retry_failed: bool,
+}
+
+/// The arguments of `dify complete`.
+#[derive(Debug, Args)]
+pub struct CompleteArgs {
+    /// The query, sent as the `query` input variable.
+    pub query: Option<String>,
+    #[command(flatten)]
+    pub inputs: InputArgs,
+    /// Waits for the complete answer instead of streaming it.
+    #[arg(long)]
+    pub no_stream: bool,
+}
+
+/// The arguments of `dify test`.
+#[derive(Debug, Args)]
+pub struct TestArgs {
+    /// The YAML test suite.
+    pub suite: PathBuf,
+    /// Writes the report as JUnit XML.
+    #[arg(long, value_name = "PATH")]
+    pub junit: Option<PathBuf>,
+    /// Writes the report as JSON.
+    #[arg(long, value_name = "PATH")]
+    pub report: Option<PathBuf>,
+    /// Fails the cases whose answer differs from the baseline.
+    #[arg(long, conflicts_with = "update_baseline")]
+    pub fail_on_diff: bool,
+    /// Updates the baseline with the answers of the passed cases.
+    #[arg(long)]
+    pub update_baseline: bool,
+    /// The number of cases run concurrently.
+    #[arg(long, short = 'c', default_value_t = 1)]
+    pub concurrency: usize,
+}
+
+/// The commands of `dify conversations`.
+#[derive(Debug, Subcommand)]
+pub enum ConversationsCommand {
+    /// Lists the conversations, most recent first.
+    List {
+        /// The maximum number of conversations.
+        #[arg(long, short = 'n', default_value_t = 20)]
+        limit: usize,
+        /// Lists the pinned conversations only.
+        #[arg(long)]
+        pinned: bool,
+    },
+    /// Renames a conversation.
+    Rename {
+        /// The conversation ID.
+        id: String,
+        /// The new name, generated automatically if not given.
+        name: Option<String>,
+    },
+    /// Deletes a conversation.
+    Delete {
+        /// The conversation ID.
+        id: String,
+    },
+}
+
+/// The arguments of `dify messages`.
+#[derive(Debug, Args)]
+pub struct MessagesArgs {
+    /// The conversation ID.
+    pub conversation: String,
+    /// The maximum number of the most recent messages.
+    #[arg(long, short = 'n', default_value_t = 20)]
+    pub limit: usize,
+}
+
+/// The arguments of `dify upload`.
+#[derive(Debug, Args)]
+pub struct UploadArgs {
+    /// The path of the file.
+    pub path: PathBuf,
+}
+
+/// The arguments of `dify tts`.
+#[derive(Debug, Args)]
+pub struct TtsArgs {
+    /// The text to convert.
+    pub text: String,
+    /// The path of the audio file to write.
+    #[arg(long, short = 'o')]
+    pub output: PathBuf,
+}
+
+/// The arguments of `dify stt`.
+#[derive(Debug, Args)]
+pub struct SttArgs {
+    /// The path of the audio file.
+    pub path: PathBuf,
+}
@@ -0,0 +1,475 @@
+//! The implementation of the commands.
+use crate::{
+    cli::{
+        BatchWorkflowArgs, ChatArgs, Cli, Command, CompleteArgs, ConversationsCommand,
+        MessagesArgs, RunWorkflowArgs, SttArgs, TestArgs, TtsArgs, UploadArgs,
+    },
+    inputs, output, profile,
+};
+use anyhow::{bail, Context, Result as AnyResult};
+use dify_client::{
+    api::Api,
+    batch::BatchRunner,
+    conversation::Conversation,
+    harness::{BaselineMode, CaseStatus, TestRunner},
+    progress::WorkflowProgress,
+    request::{
+        AudioToTextRequest, ChatMessagesRequest, CompletionMessagesRequest, ConversationsRequest,
+        MessagesRequest, MetaRequest, ParametersRequest, ResponseMode, TextToAudioRequest,
+        WorkflowsRunRequest,
+    },
+    response::{FinishedStatus, ToolIcon},
+    trace::WorkflowTrace,
+};
+use futures::{StreamExt, TryStreamExt};
+use serde_json::Value as JsonValue;
+use std::{io::Write, time::Duration};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// Runs the command of the command line.
+///
+/// # Arguments
+/// * `cli` - The parsed command line.
+///
+/// # Returns
+/// A `Result` indicating success or an error.
+pub async fn run(cli: Cli) -> AnyResult<()> {
+    let global = cli.global;
+    let api = profile::client(&global)?.api().with_user(&global.user);
+    let json = global.json;
+    match cli.command {
+        Command::Chat(args) => chat(&api, &global.user, args, json).await,
+        Command::RunWorkflow(args) => run_workflow(&api, args, json).await,
+        Command::BatchWorkflow(args) => batch_workflow(&api, &global.user, args, json).await,
+        Command::Complete(args) => complete(&api, args, json).await,
+        Command::Test(args) => test(&api, &global.user, args, json).await,
+        Command::Conversations(command) => conversations(&api, &global.user, command, json).await,
+        Command::Messages(args) => messages(&api, &global.user, args, json).await,
+        Command::Upload(args) => upload(&api, args, json).await,
+        Command::Tts(args) => tts(&api, args, json).await,
+        Command::Stt(args) => stt(&api, args, json).await,
+        Command::Params => params(&api, json).await,
+        Command::Meta => meta(&api, json).await,
+    }
+}
+
+/// Sends a chat message, streaming the answer unless `no_stream` is set.
+async fn send_message(
+    conversation: &Conversation,
+    req_data: ChatMessagesRequest,
+    no_stream: bool,
+    json: bool,
+) -> AnyResult<()> {
+    if no_stream {
+        let resp = conversation.send_request(req_data).await?;
+        if json {
+            output::print_json(&resp)?;
+        } else {
+            println!("{}", resp.answer);
+        }
+        return Ok(());
+    }
+    let stream = conversation.send_request_stream(req_data).await?;
+    output::print_answer_stream(stream, json).await
+}
+
+async fn chat(api: &Api, user: &str, args: ChatArgs, json: bool) -> AnyResult<()> {
+    let resolved = inputs::resolve(api, &args.inputs).await?;
+    let mut conversation = api.conversation(user).with_inputs(resolved.inputs);
+    if let Some(id) = args.conversation {
+        conversation = conversation.with_id(id);
+    }
+    let mut files = resolved.files;
+    let request = |query: String, files: Vec<_>| ChatMessagesRequest {
+        query,
+        files,
+        auto_generate_name: true,
+        ..Default::default()
+    };
+
+    if let Some(query) = args.query {
+        return send_message(&conversation, request(query, files), args.no_stream, json).await;
+    }
+
+    eprintln!("Type a message, /new to start a new conversation, /exit to quit.");
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        eprint!("> ");
+        std::io::stderr().flush()?;
+        let Some(line) = lines.next_line().await? else {
+            break;
+        };
+        match line.trim() {
+            "" => continue,
+            "/exit" | "/quit" => break,
+            "/new" => {
+                conversation = api
+                    .conversation(user)
+                    .with_inputs(conversation.inputs().clone());
+                eprintln!("Started a new conversation.");
+                continue;
+            }
+            query => {
+                let req_data = request(query.into(), std::mem::take(&mut files));
+                if let Err(e) = send_message(&conversation, req_data, args.no_stream, json).await {
+                    eprintln!("error: {:#}", e);
+                }
+            }
+        }
+    }
+    if let Some(id) = conversation.id() {
+        eprintln!("Conversation: {}", id);
+    }
+    Ok(())
+}
+
+async fn run_workflow(api: &Api, args: RunWorkflowArgs, json: bool) -> AnyResult<()> {
+    let resolved = inputs::resolve(api, &args.inputs).await?;
+    let mut req_data = WorkflowsRunRequest {
+        inputs: resolved.inputs,
+        files: resolved.files,
+        ..Default::default()
+    };
+    let workflow_id = args.workflow_id.as_deref();
+
+    if args.no_stream {
+        let resp = match workflow_id {
+            Some(workflow_id) => api.workflows_run_by_id(workflow_id, req_data).await?,
+            None => api.workflows_run(req_data).await?,
+        };
+        if json {
+            output::print_json(&resp)?;
+        } else {
+            output::print_outputs(resp.data.outputs.as_ref())?;
+        }
+        if resp.data.status == FinishedStatus::Failed {
+            bail!(
+                "Workflow failed: {}",
+                resp.data.error.as_deref().unwrap_or_default()
+            );
+        }
+        return Ok(());
+    }
+
+    req_data.response_mode = ResponseMode::Streaming;
+    let mut stream = match workflow_id {
+        Some(workflow_id) => {
+            api.workflows_run_stream_by_id(workflow_id, req_data)
+                .await?
+        }
+        None => api.workflows_run_stream(req_data).await?,
+    };
+    let trace = if json {
+        let mut trace = WorkflowTrace::new();
+        while let Some(event) = stream.next().await {
+            let event = event?;
+            println!("{}", serde_json::to_string(&event)?);
+            trace.push(&event);
+        }
+        trace
+    } else {
+        let trace = WorkflowProgress::new()
+            .with_text(false)
+            .render(stream)
+            .await?;
+        output::print_outputs(trace.outputs.as_ref())?;
+        trace
+    };
+    if trace.status == Some(FinishedStatus::Failed) || trace.error.is_some() {
+        bail!(
+            "Workflow failed: {}",
+            trace.error.as_deref().unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+async fn batch_workflow(
+    api: &Api,
+    user: &str,
+    args: BatchWorkflowArgs,
+    json: bool,
+) -> AnyResult<()> {
+    let mut runner = BatchRunner::new(api.clone())
+        .with_user(user)
+        .with_concurrency(args.concurrency)
+        .with_retries(args.retries, Duration::from_secs(1))
+        .with_validation(!args.no_validate)
+        .with_retry_failed(args.retry_failed);
+    if let Some(workflow_id) = args.workflow_id {
+        runner = runner.with_workflow_id(workflow_id);
+    }
+    for mapping in &args.mappings {
+        let Some((column, variable)) = mapping.split_once('=') else {
+            bail!("Mapping {} Illegal, expected COLUMN=VARIABLE", mapping);
+        };
+        runner = runner.with_mapping(column, variable);
+    }
+    let summary = runner.run_file(&args.input, &args.output).await?;
+    if json {
+        output::print_json(&summary)?;
+    } else {
+        println!(
+            "{} rows: {} succeeded, {} failed, {} invalid, {} skipped, {} tokens",
+            summary.total,
+            summary.succeeded,
+            summary.failed,
+            summary.invalid,
+            summary.skipped,
+            summary.total_tokens
+        );
+    }
+    if summary.failed + summary.invalid > 0 {
+        bail!("Some rows did not succeed, see {}", args.output.display());
+    }
+    Ok(())
+}
+
+async fn complete(api: &Api, args: CompleteArgs, json: bool) -> AnyResult<()> {
+    let resolved = inputs::resolve(api, &args.inputs).await?;
+    let mut req_data = CompletionMessagesRequest {
+        inputs: resolved.inputs,
+        files: resolved.files,
+        ..Default::default()
+    };
+    if let Some(query) = args.query {
+        req_data
+            .inputs
+            .insert("query".into(), JsonValue::String(query));
+    }
+    if args.no_stream {
+        let resp = api.completion_messages(req_data).await?;
+        if json {
+            output::print_json(&resp)?;
+        } else {
+            println!("{}", resp.answer);
+        }
+        return Ok(());
+    }
+    let stream = api.completion_messages_stream(req_data).await?;
+    output::print_answer_stream(stream, json).await
+}
+
+async fn test(api: &Api, user: &str, args: TestArgs, json: bool) -> AnyResult<()> {
+    let baseline_mode = match (args.fail_on_diff, args.update_baseline) {
+        (_, true) => BaselineMode::Update,
+        (true, false) => BaselineMode::Fail,
+        (false, false) => BaselineMode::Report,
+    };
+    let report = TestRunner::new(api.clone())
+        .with_user(user)
+        .with_concurrency(args.concurrency)
+        .with_baseline_mode(baseline_mode)
+        .run_file(&args.suite)
+        .await?;
+    if let Some(path) = &args.junit {
+        report.write_junit(path)?;
+    }
+    if let Some(path) = &args.report {
+        report.write_json(path)?;
+    }
+    if json {
+        output::print_json(&report)?;
+    } else {
+        for case in &report.cases {
+            let status = match case.status {
+                CaseStatus::Passed => "PASS",
+                CaseStatus::Failed => "FAIL",
+                CaseStatus::Error => "ERROR",
+                CaseStatus::Skipped => "SKIP",
+            };
+            println!("{:<5} {} ({}ms)", status, case.name, case.latency_ms);
+            for failure in case.failures.iter().chain(&case.error) {
+                println!("      {}", failure);
+            }
+            if let Some(diff) = &case.diff {
+                for line in diff.lines() {
+                    println!("      {}", line);
+                }
+            }
+        }
+        println!(
+            "{}: {} passed, {} failed, {} errors, {} skipped in {:.1}s",
+            report.suite,
+            report.passed,
+            report.failed,
+            report.errors,
+            report.skipped,
+            report.elapsed_time
+        );
+    }
+    if !report.is_success() {
+        bail!(
+            "{} of {} cases did not pass",
+            report.failed + report.errors,
+            report.tests
+        );
+    }
+    Ok(())
+}
+
+async fn conversations(
+    api: &Api,
+    user: &str,
+    command: ConversationsCommand,
+    json: bool,
+) -> AnyResult<()> {
+    match command {
+        ConversationsCommand::List { limit, pinned } => {
+            let conversations: Vec<_> = api
+                .conversations_iter_with(ConversationsRequest {
+                    user: user.into(),
+                    limit: Some(limit.clamp(1, 100) as u32),
+                    pinned,
+                    ..Default::default()
+                })
+                .take(limit)
+                .try_collect()
+                .await?;
+            if json {
+                return output::print_json(&conversations);
+            }
+            for conversation in conversations {
+                println!("{}\t{}", conversation.id, conversation.name);
+            }
+        }
+        ConversationsCommand::Rename { id, name } => {
+            let conversation = api.conversation(user).with_id(id);
+            let resp = conversation.rename(name.as_deref()).await?;
+            if json {
+                output::print_json(&resp)?;
+            } else {
+                println!("Renamed {}", conversation.id().unwrap_or_default());
+            }
+        }
+        ConversationsCommand::Delete { id } => {
+            api.conversation(user).with_id(&id).delete().await?;
+            if json {
+                output::print_json(&serde_json::json!({"result": "success"}))?;
+            } else {
+                println!("Deleted {}", id);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn messages(api: &Api, user: &str, args: MessagesArgs, json: bool) -> AnyResult<()> {
+    let mut messages: Vec<_> = api
+        .messages_iter_with(MessagesRequest {
+            conversation_id: args.conversation,
+            user: user.into(),
+            limit: Some(args.limit.clamp(1, 100) as u32),
+            ..Default::default()
+        })
+        .take(args.limit)
+        .try_collect()
+        .await?;
+    messages.reverse();
+    if json {
+        return output::print_json(&messages);
+    }
+    for message in messages {
+        println!("user: {}", message.query);
+        println!("assistant: {}", message.answer);
+        println!();
+    }
+    Ok(())
+}
+
+async fn upload(api: &Api, args: UploadArgs, json: bool) -> AnyResult<()> {
+    let resp = inputs::upload(api, &args.path).await?;
+    if json {
+        output::print_json(&resp)?;
+    } else {
+        println!("{}\t{}\t{} bytes", resp.id, resp.name, resp.size);
+    }
+    Ok(())
+}
+
+async fn tts(api: &Api, args: TtsArgs, json: bool) -> AnyResult<()> {
+    let audio = api
+        .text_to_audio(TextToAudioRequest {
+            text: args.text,
+            ..Default::default()
+        })
+        .await?;
+    tokio::fs::write(&args.output, &audio)
+        .await
+        .with_context(|| format!("Failed to write {}", args.output.display()))?;
+    if json {
+        output::print_json(&serde_json::json!({
+            "path": args.output,
+            "size": audio.len(),
+        }))?;
+    } else {
+        println!("Wrote {} bytes to {}", audio.len(), args.output.display());
+    }
+    Ok(())
+}
+
+async fn stt(api: &Api, args: SttArgs, json: bool) -> AnyResult<()> {
+    let file = tokio::fs::read(&args.path)
+        .await
+        .with_context(|| format!("Failed to read {}", args.path.display()))?;
+    let resp = api
+        .audio_to_text(AudioToTextRequest {
+            file: file.into(),
+            ..Default::default()
+        })
+        .await?;
+    if json {
+        output::print_json(&resp)?;
+    } else {
+        println!("{}", resp.text);
+    }
+    Ok(())
+}
+
+async fn params(api: &Api, json: bool) -> AnyResult<()> {
+    let resp = api.parameters(ParametersRequest::default()).await?;
+    if json {
+        return output::print_json(&resp);
+    }
+    if !resp.opening_statement.is_empty() {
+        println!("Opening statement: {}", resp.opening_statement);
+    }
+    println!("Inputs:");
+    for item in &resp.user_input_form {
+        // each item is an object keyed by the form item type
+        let value = serde_json::to_value(item)?;
+        let Some((type_, field)) = value.as_object().and_then(|item| item.iter().next()) else {
+            continue;
+        };
+        let text = |name: &str| field.get(name).and_then(JsonValue::as_str).unwrap_or("");
+        let required = field
+            .get("required")
+            .and_then(JsonValue::as_bool)
+            .unwrap_or_default();
+        println!(
+            "  {} ({}{}) {}",
+            text("variable"),
+            type_,
+            if required { ", required" } else { "" },
+            text("label")
+        );
+    }
+    Ok(())
+}
+
+async fn meta(api: &Api, json: bool) -> AnyResult<()> {
+    let resp = api.meta(MetaRequest::default()).await?;
+    if json {
+        return output::print_json(&resp);
+    }
+    let mut tools: Vec<_> = resp.tool_icons.iter().collect();
+    tools.sort_by_key(|(name, _)| name.as_str());
+    println!("Tools:");
+    for (name, icon) in tools {
+        match icon {
+            ToolIcon::Url(url) => println!("  {}\t{}", name, url),
+            ToolIcon::Emoji { content, .. } => println!("  {}\t{}", name, content),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,118 @@
+//! The parsing of input variables and the upload of files.
+use crate::cli::InputArgs;
+use anyhow::{anyhow, Context, Result as AnyResult};
+use dify_client::{
+    api::Api,
+    request::{FileInput, FileType, FilesUploadRequest},
+    response::FilesUploadResponse,
+};
+use serde_json::Value as JsonValue;
+use std::{collections::HashMap, path::Path};
+
+/// The inputs and the request files of an app.
+#[derive(Debug, Default)]
+pub struct Inputs {
+    /// The input variables.
+    pub inputs: HashMap<String, JsonValue>,
+    /// The files of the request.
+    pub files: Vec<FileInput>,
+}
+
+/// Parses an input variable, `key=value` for a string or `key:=json` for a JSON value.
+///
+/// # Arguments
+/// * `input` - The input argument.
+///
+/// # Returns
+/// A `Result` containing the key and the value or an error.
+pub fn parse_input(input: &str) -> AnyResult<(String, JsonValue)> {
+    let (key, value) = input
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Input {} Illegal, expected KEY=VALUE", input))?;
+    let (key, value) = match key.strip_suffix(':') {
+        Some(key) => (
+            key,
+            serde_json::from_str(value).with_context(|| format!("Input {} Illegal", key))?,
+        ),
+        None => (key, JsonValue::String(value.into())),
+    };
+    if key.is_empty() {
+        return Err(anyhow!("Input {} Illegal, expected KEY=VALUE", input));
+    }
+    Ok((key.into(), value))
+}
+
+/// Uploads a file.
+///
+/// # Arguments
+/// * `api` - The API of the app.
+/// * `path` - The path of the file.
+///
+/// # Returns
+/// A `Result` containing the upload response or an error.
+pub async fn upload(api: &Api, path: &Path) -> AnyResult<FilesUploadResponse> {
+    let file = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let req_data = FilesUploadRequest {
+        file: file.into(),
+        ..Default::default()
+    };
+    let resp = match path.file_name() {
+        Some(file_name) => {
+            api.files_upload_with_name(&file_name.to_string_lossy(), req_data)
+                .await
+        }
+        None => api.files_upload(req_data).await,
+    };
+    resp.with_context(|| format!("Failed to upload {}", path.display()))
+}
+
+/// Uploads a file, and returns it as a file input.
+async fn upload_input(api: &Api, path: &Path) -> AnyResult<FileInput> {
+    let resp = upload(api, path).await?;
+    Ok(FileInput::LocalFile {
+        type_: FileType::from_extension(&resp.extension),
+        upload_file_id: resp.id,
+    })
+}
+
+/// Parses the input variables and uploads the files.
+/// A key given for multiple files makes a file list variable.
+///
+/// # Arguments
+/// * `api` - The API of the app.
+/// * `args` - The input arguments.
+///
+/// # Returns
+/// A `Result` containing the inputs or an error.
+pub async fn resolve(api: &Api, args: &InputArgs) -> AnyResult<Inputs> {
+    let mut resolved = Inputs::default();
+    for input in &args.inputs {
+        let (key, value) = parse_input(input)?;
+        resolved.inputs.insert(key, value);
+    }
+    let mut file_vars: Vec<(String, Vec<JsonValue>)> = Vec::new();
+    for file in &args.files {
+        match file.split_once('=') {
+            Some((key, path)) => {
+                let input = upload_input(api, Path::new(path)).await?.into();
+                match file_vars.iter_mut().find(|(k, _)| k == key) {
+                    Some((_, files)) => files.push(input),
+                    None => file_vars.push((key.into(), vec![input])),
+                }
+            }
+            None => resolved
+                .files
+                .push(upload_input(api, Path::new(file)).await?),
+        }
+    }
+    for (key, mut files) in file_vars {
+        let value = match files.len() {
+            1 => files.remove(0),
+            _ => JsonValue::Array(files),
+        };
+        resolved.inputs.insert(key, value);
+    }
+    Ok(resolved)
+}
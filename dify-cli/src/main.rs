@@ -0,0 +1,32 @@
+//! `dify`, a command-line tool for Dify apps built on `dify-client`.
+//!
+//! The credentials are read from `--api-key` or `DIFY_API_KEY`, or from a profile of a config
+//! file (`--config` and `--profile`), in the format of [`dify_client::config::ConfigFile`].
+//!
+//! ```sh
+//! export DIFY_BASE_URL=https://api.dify.ai DIFY_API_KEY=app-xxxxxxxx
+//! dify chat "What are the specs of the iPhone 13 Pro Max?"
+//! dify chat --conversation 45701982-8118-4bc5-8e9b-64562b4555f2
+//! dify --profile summary-workflow run-workflow --input topic=rust --file doc=notes.md
+//! dify --json conversations list
+//! ```
+mod cli;
+mod commands;
+mod inputs;
+mod output;
+mod profile;
+
+use clap::Parser;
+use std::process::ExitCode;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = cli::Cli::parse();
+    match commands::run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
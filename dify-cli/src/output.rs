@@ -0,0 +1,71 @@
+//! The printing of responses and streams, as JSON or human-readable text.
+use anyhow::{bail, Result as AnyResult};
+use dify_client::response::SseMessageEvent;
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::io::Write;
+
+/// Prints a value as pretty JSON.
+pub fn print_json<T: Serialize + ?Sized>(value: &T) -> AnyResult<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Prints the outputs of a workflow, strings as they are and other values as JSON.
+pub fn print_outputs(outputs: Option<&JsonValue>) -> AnyResult<()> {
+    match outputs {
+        Some(JsonValue::Object(outputs)) if outputs.len() == 1 => match outputs.values().next() {
+            Some(JsonValue::String(text)) => println!("{}", text),
+            Some(value) => print_json(value)?,
+            None => {}
+        },
+        Some(JsonValue::Null) | None => {}
+        Some(outputs) => print_json(outputs)?,
+    }
+    Ok(())
+}
+
+/// Prints an answer stream, the answer chunks as they arrive or the events as JSON lines.
+///
+/// # Arguments
+/// * `stream` - The stream of events.
+/// * `json` - Whether the events are printed as JSON lines.
+///
+/// # Returns
+/// A `Result` indicating success or an error.
+pub async fn print_answer_stream<S>(stream: S, json: bool) -> AnyResult<()>
+where
+    S: Stream<Item = AnyResult<SseMessageEvent>>,
+{
+    let mut stream = std::pin::pin!(stream);
+    let mut stdout = std::io::stdout();
+    while let Some(event) = stream.next().await {
+        let event = event?;
+        if json {
+            println!("{}", serde_json::to_string(&event)?);
+            continue;
+        }
+        match event {
+            SseMessageEvent::Message { answer, .. }
+            | SseMessageEvent::AgentMessage { answer, .. } => {
+                write!(stdout, "{}", answer)?;
+                stdout.flush()?;
+            }
+            SseMessageEvent::MessageReplace { answer, .. } => {
+                writeln!(stdout)?;
+                write!(stdout, "{}", answer)?;
+                stdout.flush()?;
+            }
+            SseMessageEvent::Error { code, message, .. } => {
+                writeln!(stdout)?;
+                bail!("{}: {}", code, message);
+            }
+            _ => {}
+        }
+    }
+    if !json {
+        writeln!(stdout)?;
+    }
+    Ok(())
+}
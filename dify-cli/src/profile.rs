@@ -0,0 +1,61 @@
+//! The resolution of the client configuration from the arguments, the environment and profiles.
+use crate::cli::GlobalArgs;
+use anyhow::{bail, Result as AnyResult};
+use dify_client::{Client, Config};
+use std::{env, path::PathBuf, time::Duration};
+
+/// Returns the default path of the config file, if it exists.
+fn default_config_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("dify").join("config.toml")).filter(|path| path.is_file())
+}
+
+/// Resolves the client configuration.
+///
+/// A selected profile is read from the config file. Otherwise the API key of the arguments
+/// or the environment is used, and the default app of the config file as the last resort.
+/// The base URL and the timeout of the arguments override those of the profile.
+///
+/// # Arguments
+/// * `global` - The global arguments.
+///
+/// # Returns
+/// A `Result` containing the configuration or an error.
+pub fn resolve_config(global: &GlobalArgs) -> AnyResult<Config> {
+    let config_path = global
+        .config
+        .clone()
+        .filter(|path| !path.as_os_str().is_empty())
+        .or_else(default_config_path);
+    let non_empty = |value: &Option<String>| value.clone().filter(|value| !value.is_empty());
+    let mut config = match (non_empty(&global.profile), non_empty(&global.api_key)) {
+        (None, Some(api_key)) => Config {
+            api_key,
+            ..Default::default()
+        },
+        (profile, _) => match config_path {
+            Some(path) => Config::from_file(path, profile.as_deref())?,
+            None => bail!("No credentials, set DIFY_API_KEY or select a profile of a config file"),
+        },
+    };
+    if let Some(base_url) = non_empty(&global.base_url) {
+        config.base_url = base_url;
+    }
+    if let Some(timeout) = global.timeout {
+        config.timeout = Duration::from_secs(timeout);
+    }
+    Ok(config)
+}
+
+/// Creates the client of the resolved configuration.
+///
+/// # Arguments
+/// * `global` - The global arguments.
+///
+/// # Returns
+/// A `Result` containing the client or an error.
+pub fn client(global: &GlobalArgs) -> AnyResult<Client> {
+    Ok(Client::new_with_config(resolve_config(global)?))
+}
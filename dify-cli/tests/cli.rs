@@ -0,0 +1,251 @@
+use serde_json::json;
+use std::{
+    path::Path,
+    process::{Command, Output},
+};
+use wiremock::{
+    matchers::{body_partial_json, header, method, path, query_param},
+    Mock, MockServer, ResponseTemplate,
+};
+
+/// Runs `dify` with the arguments, isolated from the `DIFY_*` variables of the environment.
+async fn dify(args: &[&str], config_home: &Path) -> Output {
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    let config_home = config_home.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_dify"));
+        for (key, _) in std::env::vars().filter(|(key, _)| key.starts_with("DIFY_")) {
+            command.env_remove(key);
+        }
+        command
+            .env("XDG_CONFIG_HOME", config_home)
+            .args(args)
+            .output()
+            .expect("run dify failed")
+    })
+    .await
+    .expect("join failed")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[tokio::test]
+async fn test_chat_stream() {
+    let server = MockServer::start().await;
+    let sse = format!(
+        "data: {}\n\ndata: {}\n\n",
+        json!({"event": "message", "message_id": "m1", "conversation_id": "conv-1",
+            "created_at": 1705395332, "id": "m1", "task_id": "t1", "answer": "hi "}),
+        json!({"event": "message", "message_id": "m1", "conversation_id": "conv-1",
+            "created_at": 1705395332, "id": "m1", "task_id": "t1", "answer": "there"})
+    );
+    Mock::given(method("POST"))
+        .and(path("/v1/chat-messages"))
+        .and(header("authorization", "Bearer API_KEY"))
+        .and(body_partial_json(json!({
+            "query": "hello",
+            "user": "afa",
+            "conversation_id": "conv-1",
+            "inputs": {"name": "Alice", "age": 30},
+            "response_mode": "streaming"
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(sse, "text/event-stream"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let home = tempfile::tempdir().unwrap();
+    let uri = server.uri();
+    let output = dify(
+        &[
+            "--base-url",
+            &uri,
+            "--api-key",
+            "API_KEY",
+            "--user",
+            "afa",
+            "chat",
+            "hello",
+            "--conversation",
+            "conv-1",
+            "--input",
+            "name=Alice",
+            "--input",
+            "age:=30",
+        ],
+        home.path(),
+    )
+    .await;
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(stdout(&output), "hi there\n");
+}
+
+#[tokio::test]
+async fn test_run_workflow_with_files() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/files/upload"))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+            "id": "file-1", "name": "notes.md", "size": 7, "extension": "md",
+            "mime_type": "text/markdown", "created_by": "u1", "created_at": 1705395332
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/workflows/run"))
+        .and(body_partial_json(json!({
+            "inputs": {
+                "topic": "rust",
+                "doc": {"transfer_method": "local_file", "type": "document", "upload_file_id": "file-1"}
+            },
+            "response_mode": "blocking"
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "workflow_run_id": "run-1", "task_id": "t1",
+            "data": {"id": "run-1", "workflow_id": "wf-1", "status": "succeeded",
+                "outputs": {"summary": "done"}, "error": null, "elapsed_time": 1.0,
+                "total_tokens": 1, "total_steps": 2, "created_at": 1705395332,
+                "finished_at": 1705395333}
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let home = tempfile::tempdir().unwrap();
+    let doc = home.path().join("notes.md");
+    std::fs::write(&doc, "# notes").unwrap();
+    let file = format!("doc={}", doc.display());
+    let uri = server.uri();
+    let output = dify(
+        &[
+            "--base-url",
+            &uri,
+            "--api-key",
+            "API_KEY",
+            "run-workflow",
+            "--no-stream",
+            "--input",
+            "topic=rust",
+            "--file",
+            &file,
+        ],
+        home.path(),
+    )
+    .await;
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(stdout(&output), "done\n");
+}
+
+#[tokio::test]
+async fn test_profile_and_conversations() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/conversations"))
+        .and(header("authorization", "Bearer app-profile"))
+        .and(query_param("user", "dify-cli"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "limit": 2, "has_more": false,
+            "data": [
+                {"id": "conv-1", "name": "First", "inputs": {}, "introduction": "", "created_at": 1705395332},
+                {"id": "conv-2", "name": "Second", "inputs": {}, "introduction": "", "created_at": 1705395332}
+            ]
+        })))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let home = tempfile::tempdir().unwrap();
+    std::fs::create_dir(home.path().join("dify")).unwrap();
+    std::fs::write(
+        home.path().join("dify").join("config.toml"),
+        format!(
+            "base_url = \"{}\"\n[apps.bot]\napi_key = \"app-profile\"\n",
+            server.uri()
+        ),
+    )
+    .unwrap();
+
+    let output = dify(&["conversations", "list", "--limit", "2"], home.path()).await;
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(stdout(&output), "conv-1\tFirst\nconv-2\tSecond\n");
+
+    let output = dify(
+        &["--profile", "bot", "--json", "conversations", "list"],
+        home.path(),
+    )
+    .await;
+    assert!(output.status.success(), "{:?}", output);
+    let conversations: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(conversations[1]["id"], "conv-2");
+
+    let output = dify(&["--profile", "missing", "meta"], home.path()).await;
+    assert!(!output.status.success());
+}
+
+#[tokio::test]
+async fn test_missing_credentials() {
+    let home = tempfile::tempdir().unwrap();
+    let output = dify(&["params"], home.path()).await;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("No credentials"), "{}", stderr);
+
+    let output = dify(
+        &["--api-key", "API_KEY", "chat", "--input", "novalue"],
+        home.path(),
+    )
+    .await;
+    assert!(!output.status.success());
+}
+
+#[tokio::test]
+async fn test_regression_suite() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/completion-messages"))
+        .and(body_partial_json(json!({"inputs": {"query": "2+2"}})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "message_id": "m1", "created_at": 1705395332, "task_id": "t1",
+            "event": "message", "mode": "completion", "answer": "4", "metadata": {}
+        })))
+        .mount(&server)
+        .await;
+
+    let home = tempfile::tempdir().unwrap();
+    let suite = home.path().join("math.yaml");
+    std::fs::write(
+        &suite,
+        "app: completion\ncases:\n  - name: add\n    query: 2+2\n    assert:\n      - equals: '4'\n  - name: wrong\n    query: 2+2\n    assert:\n      - equals: '5'\n",
+    )
+    .unwrap();
+    let junit = home.path().join("junit.xml");
+    let uri = server.uri();
+    let output = dify(
+        &[
+            "--base-url",
+            &uri,
+            "--api-key",
+            "API_KEY",
+            "test",
+            suite.to_str().unwrap(),
+            "--junit",
+            junit.to_str().unwrap(),
+        ],
+        home.path(),
+    )
+    .await;
+    assert!(!output.status.success(), "{:?}", output);
+    let out = stdout(&output);
+    assert!(out.contains("PASS  add"), "{}", out);
+    assert!(out.contains("FAIL  wrong"), "{}", out);
+    assert!(
+        out.contains("math: 1 passed, 1 failed, 0 errors, 0 skipped"),
+        "{}",
+        out
+    );
+    let xml = std::fs::read_to_string(&junit).unwrap();
+    assert!(xml.contains(r#"<testsuite name="math" tests="2" failures="1""#));
+}
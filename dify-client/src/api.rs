@@ -15,13 +15,12 @@
 //!     // Create a client for interacting with the Dify API
 //!     let client = dify_client::Client::new("https://api.dify.ai", "API_KEY");
 //!
-//!     // Create an API instance using the client
-//!     let api = Api::new(&client);
+//!     // Create an API instance using the client, with a default user for all requests
+//!     let api = Api::new(&client).with_user("user123");
 //!
 //!     // Create a chat message request
 //!     let request = ChatMessagesRequest {
 //!         query: "What is the weather today?".to_string(),
-//!         user: "user123".to_string(),
 //!         ..Default::default()  
 //!     };
 //!
@@ -44,29 +43,44 @@
 //! This module provides a client for interacting with the Dify API.
 //!
 use super::{
-    client::Client,
-    http::{multipart, Method, Request},
+    cache::ResponseCache,
+    client::{bearer_auth, Client, Config},
+    http::{header, multipart, Method, Request, StatusCode},
+    pagination::{Paginator, DEFAULT_PAGE_SIZE},
     request::{
-        AudioToTextRequest, Bytes, ChatMessagesRequest, CompletionMessagesRequest,
+        AppFeedbacksRequest, AudioToTextRequest, Bytes, ChatMessagesRequest,
+        CompletionMessagesRequest, ConversationVariableUpdateRequest, ConversationVariablesRequest,
         ConversationsDeleteRequest, ConversationsRenameRequest, ConversationsRequest,
-        FilesUploadRequest, MessagesFeedbacksRequest, MessagesRequest, MessagesSuggestedRequest,
-        MetaRequest, ParametersRequest, ResponseMode, StreamTaskStopRequest, TextToAudioRequest,
-        WorkflowsRunRequest,
+        FilesUploadRequest, InfoRequest, MessagesFeedbacksRequest, MessagesRequest,
+        MessagesSuggestedRequest, MetaRequest, ParametersRequest, ResponseMode, SiteRequest,
+        StreamTaskStopRequest, TextToAudioRequest, WorkflowLogsRequest, WorkflowsRunRequest,
     },
     response::{
-        parse_error_response, parse_response, AudioToTextResponse, ChatMessagesResponse,
-        CompletionMessagesResponse, ConversationsResponse, FilesUploadResponse, MessagesResponse,
-        MessagesSuggestedResponse, MetaResponse, ParametersResponse, ResultResponse,
-        SseMessageEventStream, WorkflowsRunResponse,
+        parse_error_response, parse_response, parse_response_with_status, AppFeedback,
+        AppFeedbacksResponse, AudioToTextResponse, ChatMessagesResponse,
+        CompletionMessagesResponse, ConversationData, ConversationVariable,
+        ConversationVariablesResponse, ConversationsResponse, FilesUploadResponse, InfoResponse,
+        MessageData, MessagesResponse, MessagesSuggestedResponse, MetaResponse, ParametersResponse,
+        ResultResponse, SiteResponse, SseMessageEventStream, WorkflowLog, WorkflowLogsResponse,
+        WorkflowsRunResponse, WorkflowsRunTypedResponse,
     },
+    validation::InputsValidator,
 };
 use anyhow::{bail, Result as AnyResult};
 use eventsource_stream::Eventsource;
-use futures::stream::Stream;
-use std::fmt::{Display, Formatter, Result as FmtResult};
+use futures::stream::{BoxStream, StreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value as JsonValue;
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Arc,
+    time::Duration,
+};
+pub use tokio_util::sync::CancellationToken;
 
 /// API 路径
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ApiPath {
     /// 发送对话消息, 创建会话消息。
     ChatMessages,
@@ -89,6 +103,10 @@ pub enum ApiPath {
     ConversationsDelete,
     /// 会话重命名, 对会话进行重命名，会话名称用于显示在支持多会话的客户端上。
     ConversationsRename,
+    /// 获取对话变量, 从特定会话中检索变量。
+    ConversationVariables,
+    /// 更新对话变量, 更新特定会话中变量的值。
+    ConversationVariableUpdate,
     /// 语音转文字
     AudioToText,
     /// 文字转语音
@@ -97,12 +115,22 @@ pub enum ApiPath {
     Parameters,
     /// 获取应用Meta信息, 用于获取工具icon
     Meta,
+    /// 获取应用基本信息, 名称、描述、标签及类型
+    Info,
+    /// 获取应用 WebApp 设置, 标题、图标、主题、版权及默认语言等
+    Site,
+    /// 获取应用反馈列表, 终端用户及管理员对消息的点赞点踩反馈
+    AppFeedbacks,
 
     /// workflow
     /// 执行 workflow
     WorkflowsRun,
+    /// 执行指定版本的 workflow
+    WorkflowsRunById,
     /// 停止响应, 仅支持流式模式。
     WorkflowsStop,
+    /// 获取 workflow 日志, 倒序返回 workflow 执行日志。
+    WorkflowsLogs,
 
     /// completion 文本生成
     /// 发送请求给文本生成型应用
@@ -131,18 +159,125 @@ impl ApiPath {
             ApiPath::Conversations => "/v1/conversations",
             ApiPath::ConversationsDelete => "/v1/conversations/{conversation_id}",
             ApiPath::ConversationsRename => "/v1/conversations/{conversation_id}/name",
+            ApiPath::ConversationVariables => "/v1/conversations/{conversation_id}/variables",
+            ApiPath::ConversationVariableUpdate => {
+                "/v1/conversations/{conversation_id}/variables/{variable_id}"
+            }
             ApiPath::AudioToText => "/v1/audio-to-text",
             ApiPath::TextToAudio => "/v1/text-to-audio",
             ApiPath::Parameters => "/v1/parameters",
             ApiPath::Meta => "/v1/meta",
+            ApiPath::Info => "/v1/info",
+            ApiPath::Site => "/v1/site",
+            ApiPath::AppFeedbacks => "/v1/app/feedbacks",
             ApiPath::WorkflowsRun => "/v1/workflows/run",
+            ApiPath::WorkflowsRunById => "/v1/workflows/{workflow_id}/run",
             ApiPath::WorkflowsStop => "/v1/workflows/{task_id}/stop",
+            ApiPath::WorkflowsLogs => "/v1/workflows/logs",
             ApiPath::CompletionMessages => "/v1/completion-messages",
             ApiPath::CompletionMessagesStop => "/v1/completion-messages/{task_id}/stop",
         }
     }
 }
 
+impl ApiPath {
+    /// 所有 API 路径，按声明顺序排列
+    pub(crate) const ALL: [ApiPath; 24] = [
+        ApiPath::ChatMessages,
+        ApiPath::FilesUpload,
+        ApiPath::ChatMessagesStop,
+        ApiPath::MessagesFeedbacks,
+        ApiPath::MessagesSuggested,
+        ApiPath::Messages,
+        ApiPath::Conversations,
+        ApiPath::ConversationsDelete,
+        ApiPath::ConversationsRename,
+        ApiPath::ConversationVariables,
+        ApiPath::ConversationVariableUpdate,
+        ApiPath::AudioToText,
+        ApiPath::TextToAudio,
+        ApiPath::Parameters,
+        ApiPath::Meta,
+        ApiPath::Info,
+        ApiPath::Site,
+        ApiPath::AppFeedbacks,
+        ApiPath::WorkflowsRun,
+        ApiPath::WorkflowsRunById,
+        ApiPath::WorkflowsStop,
+        ApiPath::WorkflowsLogs,
+        ApiPath::CompletionMessages,
+        ApiPath::CompletionMessagesStop,
+    ];
+
+    /// 根据请求 URL 的路径获取 API 路径，路径参数匹配任意值，base URL 的路径前缀被忽略
+    /// # Example
+    /// ```
+    /// use dify_client::api::ApiPath;
+    /// let path = ApiPath::from_path("/api/v1/chat-messages/task-1/stop");
+    /// assert_eq!(path, Some(ApiPath::ChatMessagesStop));
+    /// ```
+    pub fn from_path(path: &str) -> Option<Self> {
+        let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+        ApiPath::ALL
+            .into_iter()
+            .filter(|api_path| {
+                let template: Vec<&str> = api_path
+                    .as_str()
+                    .trim_start_matches('/')
+                    .split('/')
+                    .collect();
+                segments.len() >= template.len()
+                    && segments[segments.len() - template.len()..]
+                        .iter()
+                        .zip(&template)
+                        .all(|(segment, part)| {
+                            segment == part || (part.starts_with('{') && !segment.is_empty())
+                        })
+            })
+            // 优先匹配路径参数少的 API 路径
+            .min_by_key(|api_path| api_path.as_str().matches('{').count())
+    }
+}
+
+// 编译期检查 `ApiPath::ALL` 按声明顺序列出每个 API 路径，
+// 新增 API 路径时下方的 match 不完整而编译失败，提醒补充到 `ApiPath::ALL`
+const _: () = {
+    match ApiPath::ChatMessages {
+        ApiPath::ChatMessages
+        | ApiPath::FilesUpload
+        | ApiPath::ChatMessagesStop
+        | ApiPath::MessagesFeedbacks
+        | ApiPath::MessagesSuggested
+        | ApiPath::Messages
+        | ApiPath::Conversations
+        | ApiPath::ConversationsDelete
+        | ApiPath::ConversationsRename
+        | ApiPath::ConversationVariables
+        | ApiPath::ConversationVariableUpdate
+        | ApiPath::AudioToText
+        | ApiPath::TextToAudio
+        | ApiPath::Parameters
+        | ApiPath::Meta
+        | ApiPath::Info
+        | ApiPath::Site
+        | ApiPath::AppFeedbacks
+        | ApiPath::WorkflowsRun
+        | ApiPath::WorkflowsRunById
+        | ApiPath::WorkflowsStop
+        | ApiPath::WorkflowsLogs
+        | ApiPath::CompletionMessages
+        | ApiPath::CompletionMessagesStop => {}
+    }
+    let mut i = 0;
+    while i < ApiPath::ALL.len() {
+        assert!(
+            ApiPath::ALL[i] as usize == i,
+            "ApiPath::ALL misses an API path"
+        );
+        i += 1;
+    }
+};
+
 impl Display for ApiPath {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         write!(f, "{}", self.as_str())
@@ -150,27 +285,241 @@ impl Display for ApiPath {
 }
 
 /// 发送请求前的钩子函数
-type BeforeSend = Option<Box<dyn Fn(Request) -> Request + Send + Sync>>;
+type BeforeSend = Option<Arc<dyn Fn(Request) -> Request + Send + Sync>>;
+
+/// The byte stream of a streaming response body.
+pub type BytesStream = BoxStream<'static, Result<Bytes, reqwest::Error>>;
+
+/// The `Idempotency-Key` header name.
+pub const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+/// Per-request options, applied to every request sent by an [`Api`] instance.
+///
+/// # Example
+///
+/// ```no_run
+/// use dify_client::{api::RequestOptions, request, Client};
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new("https://api.dify.ai", "API_KEY");
+///     // a long-running blocking workflow needs a longer timeout
+///     let options = RequestOptions {
+///         timeout: Some(Duration::from_secs(600)),
+///         idempotency_key: Some("order-42".into()),
+///         ..Default::default()
+///     };
+///     let data = request::WorkflowsRunRequest {
+///         user: "afa".into(),
+///         ..Default::default()
+///     };
+///     let result = client.api().with_options(options).workflows_run(data).await;
+///     println!("{:?}", result);
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RequestOptions {
+    /// The timeout of the request, overrides the client timeout.
+    pub timeout: Option<Duration>,
+    /// The extra headers of the request.
+    pub headers: header::HeaderMap,
+    /// The value of the `Idempotency-Key` header.
+    pub idempotency_key: Option<String>,
+    /// The API key of the request, overrides the client API key.
+    pub api_key: Option<String>,
+    /// The token for cancelling the request, a cancelled token also ends the response stream.
+    pub cancellation_token: Option<CancellationToken>,
+}
+
+impl RequestOptions {
+    /// Applies the options to the request.
+    ///
+    /// # Arguments
+    /// * `req` - The request to apply the options to.
+    ///
+    /// # Returns
+    /// A `Result` containing the request or an error.
+    ///
+    /// # Errors
+    /// Returns an error if the API key or the idempotency key is not a valid header value.
+    fn apply(&self, mut req: Request) -> AnyResult<Request> {
+        if let Some(timeout) = self.timeout {
+            *req.timeout_mut() = Some(timeout);
+        }
+        let headers = req.headers_mut();
+        for (name, value) in self.headers.iter() {
+            headers.insert(name.clone(), value.clone());
+        }
+        if let Some(key) = self.idempotency_key.as_deref() {
+            let value = header::HeaderValue::from_str(key)
+                .map_err(|_| anyhow::anyhow!("RequestOptions.IdempotencyKey Illegal"))?;
+            headers.insert(IDEMPOTENCY_KEY, value);
+        }
+        if let Some(api_key) = self.api_key.as_deref() {
+            headers.insert(header::AUTHORIZATION, bearer_auth(api_key)?);
+        }
+        Ok(req)
+    }
+}
 
 /// Dify API
-pub struct Api<'a> {
+///
+/// The `Api` owns a clone of the client, so it is `'static` and can be stored in structs,
+/// returned from functions or moved into spawned tasks.
+#[derive(Clone)]
+pub struct Api {
     before_send_hook: BeforeSend,
-    pub(crate) client: &'a Client,
+    /// The default user for requests without a user.
+    default_user: Option<String>,
+    /// The request options.
+    options: RequestOptions,
+    /// The validator of the request inputs.
+    validator: Option<InputsValidator>,
+    /// The cache of the responses of read-only endpoints.
+    cache: Option<ResponseCache>,
+    pub(crate) client: Client,
 }
 
 /// Dify API
-impl<'a> Api<'a> {
+impl Api {
     /// Creates a new `Api` instance with the specified client.
     ///
     /// # Arguments
     /// * `client` - The client for interacting with the Dify API.
-    pub fn new(client: &'a Client) -> Self {
+    pub fn new(client: &Client) -> Self {
         Self {
             before_send_hook: None,
-            client,
+            default_user: None,
+            options: RequestOptions::default(),
+            validator: None,
+            cache: None,
+            client: client.clone(),
         }
     }
 
+    /// Returns the client of the API.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Overrides the API key of this API instance.
+    /// The new API key shares the HTTP connection pool of the client.
+    ///
+    /// # Arguments
+    /// * `api_key` - The API key for authentication.
+    ///
+    /// # Returns
+    /// A `Result` containing the API or an error if the API key is illegal.
+    pub fn with_api_key(mut self, api_key: &str) -> AnyResult<Self> {
+        let config = Config {
+            api_key: api_key.into(),
+            ..self.client.config.as_ref().clone()
+        };
+        self.client = self.client.with_config(config)?;
+        Ok(self)
+    }
+
+    /// Sets the default user of this API instance.
+    /// The default user is used for requests sent with an empty `user`.
+    ///
+    /// # Arguments
+    /// * `user` - The default user.
+    pub fn with_user(mut self, user: impl Into<String>) -> Self {
+        self.default_user = Some(user.into());
+        self
+    }
+
+    /// Returns the default user of this API instance.
+    pub fn default_user(&self) -> Option<&str> {
+        self.default_user.as_deref()
+    }
+
+    /// Fills the user with the default user if it is empty.
+    ///
+    /// # Arguments
+    /// * `user` - The user of the request.
+    fn fill_user(&self, user: &mut String) {
+        if user.is_empty() {
+            if let Some(default_user) = self.default_user.as_ref() {
+                user.clone_from(default_user);
+            }
+        }
+    }
+
+    /// Sets the request options of this API instance.
+    /// The options apply to every request sent by this instance,
+    /// create a dedicated instance for per-call options.
+    ///
+    /// # Arguments
+    /// * `options` - The request options.
+    pub fn with_options(mut self, options: RequestOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Returns the request options of this API instance.
+    pub fn options(&self) -> &RequestOptions {
+        &self.options
+    }
+
+    /// Sets the validator of the request inputs.
+    /// The inputs of chat, completion and workflow requests are validated against
+    /// the app parameters before sending.
+    ///
+    /// # Arguments
+    /// * `validator` - The inputs validator.
+    pub fn with_validator(mut self, validator: InputsValidator) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    /// Sets the cache of the responses of read-only endpoints:
+    /// `parameters`, `meta`, `info` and `site`.
+    /// See the [`cache`](crate::cache) module.
+    ///
+    /// # Arguments
+    /// * `cache` - The response cache.
+    pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Returns the cache of the responses of read-only endpoints, if any.
+    pub fn cache(&self) -> Option<&ResponseCache> {
+        self.cache.as_ref()
+    }
+
+    /// Validates the inputs with the validator, if any.
+    ///
+    /// # Arguments
+    /// * `inputs` - The inputs of the request.
+    /// * `user` - The user of the request.
+    ///
+    /// # Returns
+    /// A `Result` indicating success or an error.
+    async fn validate_inputs(
+        &self,
+        inputs: &HashMap<String, JsonValue>,
+        user: &str,
+    ) -> AnyResult<()> {
+        match self.validator.as_ref() {
+            Some(validator) => validator.validate(self, inputs, user).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the key identifying the app of this API instance,
+    /// made of the base URL and the effective API key.
+    pub(crate) fn app_key(&self) -> String {
+        let api_key = self
+            .options
+            .api_key
+            .as_deref()
+            .unwrap_or(&self.client.config.api_key);
+        format!("{} {}", self.client.config.base_url, api_key)
+    }
+
     /// Sets a hook function to be called before sending a request.
     /// The hook function is called with the request before it is sent.
     /// The hook function can be used to modify the request before it is sent.
@@ -183,7 +532,7 @@ impl<'a> Api<'a> {
     where
         F: Fn(Request) -> Request + Send + Sync + 'static,
     {
-        self.before_send_hook = Some(Box::new(hook));
+        self.before_send_hook = Some(Arc::new(hook));
     }
 
     /// Sends a request to the Dify API and returns the response.
@@ -194,10 +543,133 @@ impl<'a> Api<'a> {
     /// # Returns
     /// A `Result` containing the response or an error.
     async fn send(&self, mut req: Request) -> AnyResult<reqwest::Response> {
+        req = self.options.apply(req)?;
         if let Some(hook) = self.before_send_hook.as_ref() {
             req = hook(req);
         }
-        self.client.execute(req).await
+        self.cancellable(self.client.execute(req)).await
+    }
+
+    /// Sends a request to the Dify API and returns the response body,
+    /// reading the body under the cancellation token of the options too.
+    ///
+    /// # Arguments
+    /// * `req` - The request to send.
+    ///
+    /// # Returns
+    /// A `Result` containing the response body or an error.
+    async fn send_text(&self, req: Request) -> AnyResult<String> {
+        let resp = self.send(req).await?;
+        self.read_text(resp).await
+    }
+
+    /// Reads the body of a response as text, until the cancellation token of the options is cancelled.
+    ///
+    /// # Arguments
+    /// * `resp` - The response to read.
+    ///
+    /// # Returns
+    /// A `Result` containing the response body or an error.
+    async fn read_text(&self, resp: reqwest::Response) -> AnyResult<String> {
+        self.cancellable(async { Ok(resp.text().await?) }).await
+    }
+
+    /// Reads the body of a response as bytes, until the cancellation token of the options is cancelled.
+    ///
+    /// # Arguments
+    /// * `resp` - The response to read.
+    ///
+    /// # Returns
+    /// A `Result` containing the response body or an error.
+    async fn read_bytes(&self, resp: reqwest::Response) -> AnyResult<Bytes> {
+        self.cancellable(async { Ok(resp.bytes().await?) }).await
+    }
+
+    /// Runs a future until the cancellation token of the options is cancelled.
+    ///
+    /// # Arguments
+    /// * `future` - The future to run.
+    ///
+    /// # Returns
+    /// A `Result` containing the output of the future, or an error if cancelled.
+    async fn cancellable<T>(
+        &self,
+        future: impl std::future::Future<Output = AnyResult<T>>,
+    ) -> AnyResult<T> {
+        match self.options.cancellation_token.as_ref() {
+            Some(token) => token
+                .run_until_cancelled(future)
+                .await
+                .unwrap_or_else(|| bail!("Request cancelled")),
+            None => future.await,
+        }
+    }
+
+    /// Sends a request of a read-only endpoint and returns the response body,
+    /// from the response cache if any.
+    /// An expired response with an `ETag` is revalidated with `If-None-Match`.
+    ///
+    /// # Arguments
+    /// * `api_path` - The API path of the endpoint.
+    /// * `req` - The request to send.
+    ///
+    /// # Returns
+    /// A `Result` containing the response body or an error.
+    async fn send_cached(&self, api_path: ApiPath, mut req: Request) -> AnyResult<String> {
+        let Some(cache) = self
+            .cache
+            .as_ref()
+            .filter(|_| ResponseCache::is_cacheable(api_path))
+        else {
+            return self.send_text(req).await;
+        };
+        let app_key = self.app_key();
+        let cached = cache.get(&app_key, api_path);
+        if let Some(cached) = cached.as_ref() {
+            if cached.fresh {
+                return Ok(cached.body.clone());
+            }
+            if let Some(etag) = cached.etag.as_deref() {
+                req.headers_mut()
+                    .insert(header::IF_NONE_MATCH, header::HeaderValue::from_str(etag)?);
+            }
+        }
+
+        let resp = self.send(req).await?;
+        if let (StatusCode::NOT_MODIFIED, Some(cached)) = (resp.status(), cached) {
+            cache.insert(&app_key, api_path, cached.body.clone(), cached.etag);
+            return Ok(cached.body);
+        }
+        let success = resp.status().is_success();
+        let etag = resp
+            .headers()
+            .get(header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string);
+        let text = self.read_text(resp).await?;
+        if success {
+            cache.insert(&app_key, api_path, text.clone(), etag);
+        }
+        Ok(text)
+    }
+
+    /// Converts a streaming response into a SSE message event stream.
+    /// The stream ends when the cancellation token of the options is cancelled.
+    ///
+    /// # Arguments
+    /// * `resp` - The streaming response.
+    ///
+    /// # Returns
+    /// The SSE message event stream.
+    fn event_stream(&self, resp: reqwest::Response) -> SseMessageEventStream<BytesStream> {
+        let stream = match self.options.cancellation_token.clone() {
+            Some(token) => resp
+                .bytes_stream()
+                .take_until(token.cancelled_owned())
+                .boxed(),
+            None => resp.bytes_stream().boxed(),
+        };
+        SseMessageEventStream::new(stream.eventsource())
     }
 
     /// Builds the API request URL.
@@ -237,11 +709,13 @@ impl<'a> Api<'a> {
         &self,
         mut req_data: ChatMessagesRequest,
     ) -> AnyResult<ChatMessagesResponse> {
+        self.fill_user(&mut req_data.user);
+        self.validate_inputs(&req_data.inputs, &req_data.user)
+            .await?;
         req_data.response_mode = ResponseMode::Blocking;
 
         let req = self.create_chat_messages_request(req_data)?;
-        let resp = self.send(req).await?;
-        let text = resp.text().await?;
+        let text = self.send_text(req).await?;
         parse_response::<ChatMessagesResponse>(&text)
     }
 
@@ -272,20 +746,20 @@ impl<'a> Api<'a> {
     pub async fn chat_messages_stream(
         &self,
         mut req_data: ChatMessagesRequest,
-    ) -> AnyResult<SseMessageEventStream<impl Stream<Item = Result<Bytes, reqwest::Error>>>> {
+    ) -> AnyResult<SseMessageEventStream<BytesStream>> {
+        self.fill_user(&mut req_data.user);
+        self.validate_inputs(&req_data.inputs, &req_data.user)
+            .await?;
         req_data.response_mode = ResponseMode::Streaming;
 
         let req = self.create_chat_messages_request(req_data)?;
         let resp = self.send(req).await?;
-        let stream = resp.bytes_stream().eventsource();
-        let s = SseMessageEventStream::new(stream);
-
-        Ok(s)
+        Ok(self.event_stream(resp))
     }
 
     /// Sends a request to upload files to the Dify API and returns the response.  
-    /// 上传文件（目前仅支持图片）并在发送消息时使用，可实现图文多模态理解。  
-    /// 支持 png, jpg, jpeg, webp, gif 格式。  
+    /// 上传文件并在发送消息或执行 workflow 时使用，可实现图文多模态理解及文档理解。  
+    /// 文件类型由文件内容推断，无法推断的文档（如 txt、md、csv 等）需使用 [`Api::files_upload_with_name`]。  
     /// 上传的文件仅供当前终端用户使用。  
     ///
     /// # Arguments
@@ -297,21 +771,59 @@ impl<'a> Api<'a> {
         &self,
         req_data: FilesUploadRequest,
     ) -> AnyResult<FilesUploadResponse> {
-        if !infer::is_image(&req_data.file) {
+        self.upload_file(req_data, None).await
+    }
+
+    /// Sends a request to upload a named file to the Dify API and returns the response.  
+    /// 文件类型由文件内容推断，无法推断时（如 txt、md、csv 等文档）由文件名后缀确定。  
+    ///
+    /// # Arguments
+    /// * `file_name` - The name of the file, e.g. `notes.md`.
+    /// * `req_data` - The files upload request data.
+    ///
+    /// # Returns
+    /// A `Result` containing the files upload response or an error.
+    pub async fn files_upload_with_name(
+        &self,
+        file_name: &str,
+        req_data: FilesUploadRequest,
+    ) -> AnyResult<FilesUploadResponse> {
+        if file_name.is_empty() {
+            bail!("FilesUploadRequest.FileName Illegal");
+        }
+        self.upload_file(req_data, Some(file_name)).await
+    }
+
+    /// Uploads a file, named after its inferred type unless a file name is given.
+    async fn upload_file(
+        &self,
+        mut req_data: FilesUploadRequest,
+        file_name: Option<&str>,
+    ) -> AnyResult<FilesUploadResponse> {
+        self.fill_user(&mut req_data.user);
+        if req_data.file.is_empty() {
             bail!("FilesUploadRequest.File Illegal");
         }
-        let kind = infer::get(&req_data.file).expect("Failed to get file type");
+        let kind = infer::get(&req_data.file);
+        let file_name = match (file_name, kind) {
+            (Some(file_name), _) => file_name.to_string(),
+            (_, Some(kind)) if infer::is_image(&req_data.file) => {
+                format!("image_file.{}", kind.extension())
+            }
+            (_, Some(kind)) => format!("file.{}", kind.extension()),
+            _ => bail!("FilesUploadRequest.FileName Illegal"),
+        };
+        let mime_type = kind.map_or("application/octet-stream", |kind| kind.mime_type());
         let file_part = multipart::Part::stream(req_data.file)
-            .file_name(format!("image_file.{}", kind.extension()))
-            .mime_str(kind.mime_type())?;
+            .file_name(file_name)
+            .mime_str(mime_type)?;
         let form = multipart::Form::new()
             .text("user", req_data.user)
             .part("file", file_part);
 
         let url = self.build_request_api(ApiPath::FilesUpload);
         let req = self.client.create_multipart_request(url, form)?;
-        let resp = self.send(req).await?;
-        let text = resp.text().await?;
+        let text = self.send_text(req).await?;
         parse_response::<FilesUploadResponse>(&text)
     }
 
@@ -329,6 +841,7 @@ impl<'a> Api<'a> {
         mut req_data: StreamTaskStopRequest,
         api_path: ApiPath,
     ) -> AnyResult<ResultResponse> {
+        self.fill_user(&mut req_data.user);
         if req_data.task_id.is_empty() {
             bail!("StreamTaskStopRequest.TaskId Illegal");
         }
@@ -338,8 +851,7 @@ impl<'a> Api<'a> {
 
         req_data.task_id = String::new();
         let req = self.client.create_request(url, Method::POST, req_data)?;
-        let resp = self.send(req).await?;
-        let text = resp.text().await?;
+        let text = self.send_text(req).await?;
         parse_response::<ResultResponse>(&text)
     }
 
@@ -378,8 +890,7 @@ impl<'a> Api<'a> {
 
         req_data.message_id = String::new();
         let req = self.client.create_request(url, Method::GET, req_data)?;
-        let resp = self.send(req).await?;
-        let text = resp.text().await?;
+        let text = self.send_text(req).await?;
         parse_response::<MessagesSuggestedResponse>(&text)
     }
 
@@ -394,6 +905,7 @@ impl<'a> Api<'a> {
         &self,
         mut req_data: MessagesFeedbacksRequest,
     ) -> AnyResult<ResultResponse> {
+        self.fill_user(&mut req_data.user);
         if req_data.message_id.is_empty() {
             bail!("MessagesFeedbacksRequest.MessageID Illegal");
         }
@@ -403,8 +915,7 @@ impl<'a> Api<'a> {
 
         req_data.message_id = String::new();
         let req = self.client.create_request(url, Method::POST, req_data)?;
-        let resp = self.send(req).await?;
-        let text = resp.text().await?;
+        let text = self.send_text(req).await?;
         parse_response::<ResultResponse>(&text)
     }
 
@@ -417,16 +928,16 @@ impl<'a> Api<'a> {
     /// A `Result` containing the conversations response or an error.
     pub async fn conversations(
         &self,
-        req_data: ConversationsRequest,
+        mut req_data: ConversationsRequest,
     ) -> AnyResult<ConversationsResponse> {
+        self.fill_user(&mut req_data.user);
         if req_data.user.is_empty() {
             bail!("ConversationsRequest.User Illegal");
         }
 
         let url = self.build_request_api(ApiPath::Conversations);
         let req = self.client.create_request(url, Method::GET, req_data)?;
-        let resp = self.send(req).await?;
-        let text = resp.text().await?;
+        let text = self.send_text(req).await?;
         parse_response::<ConversationsResponse>(&text)
     }
 
@@ -437,18 +948,112 @@ impl<'a> Api<'a> {
     ///
     /// # Returns
     /// A `Result` containing the messages response or an error.
-    pub async fn messages(&self, req_data: MessagesRequest) -> AnyResult<MessagesResponse> {
+    pub async fn messages(&self, mut req_data: MessagesRequest) -> AnyResult<MessagesResponse> {
+        self.fill_user(&mut req_data.user);
         if req_data.conversation_id.is_empty() {
             bail!("MessagesRequest.ConversationID Illegal");
         }
 
         let url = self.build_request_api(ApiPath::Messages);
         let req = self.client.create_request(url, Method::GET, req_data)?;
-        let resp = self.send(req).await?;
-        let text = resp.text().await?;
+        let text = self.send_text(req).await?;
         parse_response::<MessagesResponse>(&text)
     }
 
+    /// Returns a stream of all conversations of the user, paging through the conversations list.
+    /// The conversations are yielded from the most recently updated.
+    ///
+    /// # Arguments
+    /// * `user` - The user of the conversations.
+    ///
+    /// # Returns
+    /// A `Paginator` yielding the conversations.
+    pub fn conversations_iter(&self, user: &str) -> Paginator<ConversationData> {
+        self.conversations_iter_with(ConversationsRequest {
+            user: user.into(),
+            limit: Some(DEFAULT_PAGE_SIZE),
+            ..Default::default()
+        })
+    }
+
+    /// Returns a stream of all conversations matching the request, paging through the conversations list.
+    /// The `limit` of the request is the page size, and the `last_id` the cursor to start after.
+    ///
+    /// # Arguments
+    /// * `req_data` - The conversations request data of the first page.
+    ///
+    /// # Returns
+    /// A `Paginator` yielding the conversations.
+    pub fn conversations_iter_with(
+        &self,
+        req_data: ConversationsRequest,
+    ) -> Paginator<ConversationData> {
+        let api = self.clone();
+        let cursor = req_data.last_id.clone();
+        Paginator::new(cursor, move |last_id| {
+            let api = api.clone();
+            let req_data = ConversationsRequest {
+                last_id,
+                ..req_data.clone()
+            };
+            async move {
+                let resp = api.conversations(req_data).await?;
+                let next = resp
+                    .has_more
+                    .then(|| resp.data.last().map(|c| c.id.clone()));
+                Ok((resp.data, next))
+            }
+        })
+    }
+
+    /// Returns a stream of all messages of a conversation, paging through the history messages.
+    /// The messages are yielded from the latest to the earliest.
+    ///
+    /// # Arguments
+    /// * `conversation_id` - The conversation ID.
+    /// * `user` - The user of the conversation.
+    ///
+    /// # Returns
+    /// A `Paginator` yielding the messages.
+    pub fn messages_iter(&self, conversation_id: &str, user: &str) -> Paginator<MessageData> {
+        self.messages_iter_with(MessagesRequest {
+            conversation_id: conversation_id.into(),
+            user: user.into(),
+            limit: Some(DEFAULT_PAGE_SIZE),
+            ..Default::default()
+        })
+    }
+
+    /// Returns a stream of all messages matching the request, paging through the history messages.
+    /// The `limit` of the request is the page size, and the `first_id` the cursor to start before.
+    /// The messages are yielded from the latest to the earliest.
+    ///
+    /// # Arguments
+    /// * `req_data` - The messages request data of the first page.
+    ///
+    /// # Returns
+    /// A `Paginator` yielding the messages.
+    pub fn messages_iter_with(&self, req_data: MessagesRequest) -> Paginator<MessageData> {
+        let api = self.clone();
+        let cursor = req_data.first_id.clone();
+        Paginator::new(cursor, move |first_id| {
+            let api = api.clone();
+            let req_data = MessagesRequest {
+                first_id,
+                ..req_data.clone()
+            };
+            async move {
+                let mut resp = api.messages(req_data).await?;
+                // each page is in chronological order, and the earliest message is the cursor
+                let next = resp
+                    .has_more
+                    .then(|| resp.data.first().map(|m| m.id.clone()));
+                resp.data.reverse();
+                Ok((resp.data, next))
+            }
+        })
+    }
+
     /// Sends a request to rename a conversation in the Dify API and returns the response.
     ///
     /// # Arguments
@@ -460,10 +1065,11 @@ impl<'a> Api<'a> {
         &self,
         mut req_data: ConversationsRenameRequest,
     ) -> AnyResult<ResultResponse> {
+        self.fill_user(&mut req_data.user);
         if req_data.conversation_id.is_empty() {
             bail!("ConversationsRenameRequest.ConversationID Illegal");
         }
-        if req_data.auto_generate && req_data.name.is_none() {
+        if !req_data.auto_generate && req_data.name.as_deref().unwrap_or_default().is_empty() {
             bail!("ConversationsRenameRequest.Name Illegal");
         }
 
@@ -472,11 +1078,97 @@ impl<'a> Api<'a> {
 
         req_data.conversation_id = String::new();
         let req = self.client.create_request(url, Method::POST, req_data)?;
-        let resp = self.send(req).await?;
-        let text = resp.text().await?;
+        let text = self.send_text(req).await?;
         parse_response::<ResultResponse>(&text)
     }
 
+    /// Sends a request to retrieve the variables of a conversation from the Dify API and returns the response.
+    ///
+    /// # Arguments
+    /// * `req_data` - The conversation variables request data.
+    ///
+    /// # Returns
+    /// A `Result` containing the conversation variables response or an error.
+    pub async fn conversation_variables(
+        &self,
+        mut req_data: ConversationVariablesRequest,
+    ) -> AnyResult<ConversationVariablesResponse> {
+        self.fill_user(&mut req_data.user);
+        if req_data.conversation_id.is_empty() {
+            bail!("ConversationVariablesRequest.ConversationID Illegal");
+        }
+
+        let url = self.build_request_api(ApiPath::ConversationVariables);
+        let url = url.replace("{conversation_id}", &req_data.conversation_id);
+
+        req_data.conversation_id = String::new();
+        let req = self.client.create_request(url, Method::GET, req_data)?;
+        let text = self.send_text(req).await?;
+        parse_response::<ConversationVariablesResponse>(&text)
+    }
+
+    /// Returns a stream of all variables of a conversation matching the request,
+    /// paging through the conversation variables.
+    /// The `limit` of the request is the page size, and the `last_id` the cursor to start after.
+    ///
+    /// # Arguments
+    /// * `req_data` - The conversation variables request data of the first page.
+    ///
+    /// # Returns
+    /// A `Paginator` yielding the conversation variables.
+    pub fn conversation_variables_iter(
+        &self,
+        req_data: ConversationVariablesRequest,
+    ) -> Paginator<ConversationVariable> {
+        let api = self.clone();
+        let cursor = req_data.last_id.clone();
+        Paginator::new(cursor, move |last_id| {
+            let api = api.clone();
+            let req_data = ConversationVariablesRequest {
+                last_id,
+                ..req_data.clone()
+            };
+            async move {
+                let resp = api.conversation_variables(req_data).await?;
+                let next = resp
+                    .has_more
+                    .then(|| resp.data.last().map(|v| v.id.clone()));
+                Ok((resp.data, next))
+            }
+        })
+    }
+
+    /// Sends a request to update the value of a conversation variable in the Dify API and returns the response.
+    ///
+    /// # Arguments
+    /// * `req_data` - The conversation variable update request data.
+    ///
+    /// # Returns
+    /// A `Result` containing the updated conversation variable or an error.
+    pub async fn conversation_variable_update(
+        &self,
+        mut req_data: ConversationVariableUpdateRequest,
+    ) -> AnyResult<ConversationVariable> {
+        self.fill_user(&mut req_data.user);
+        if req_data.conversation_id.is_empty() {
+            bail!("ConversationVariableUpdateRequest.ConversationID Illegal");
+        }
+        if req_data.variable_id.is_empty() {
+            bail!("ConversationVariableUpdateRequest.VariableID Illegal");
+        }
+
+        let url = self.build_request_api(ApiPath::ConversationVariableUpdate);
+        let url = url
+            .replace("{conversation_id}", &req_data.conversation_id)
+            .replace("{variable_id}", &req_data.variable_id);
+
+        req_data.conversation_id = String::new();
+        req_data.variable_id = String::new();
+        let req = self.client.create_request(url, Method::PUT, req_data)?;
+        let text = self.send_text(req).await?;
+        parse_response::<ConversationVariable>(&text)
+    }
+
     /// Sends a request to delete a conversation in the Dify API and returns the response.
     ///
     /// # Arguments
@@ -488,6 +1180,7 @@ impl<'a> Api<'a> {
         &self,
         mut req_data: ConversationsDeleteRequest,
     ) -> AnyResult<()> {
+        self.fill_user(&mut req_data.user);
         if req_data.conversation_id.is_empty() {
             bail!("ConversationsDeleteRequest.ConversationID Illegal");
         }
@@ -503,7 +1196,7 @@ impl<'a> Api<'a> {
             Ok(())
         } else {
             // parse message type
-            let text = resp.text().await?;
+            let text = self.read_text(resp).await?;
             parse_error_response(&text)
         }
     }
@@ -515,7 +1208,8 @@ impl<'a> Api<'a> {
     ///
     /// # Returns
     /// A `Result` containing the audio to text response or an error.
-    pub async fn text_to_audio(&self, req_data: TextToAudioRequest) -> AnyResult<Bytes> {
+    pub async fn text_to_audio(&self, mut req_data: TextToAudioRequest) -> AnyResult<Bytes> {
+        self.fill_user(&mut req_data.user);
         if req_data.text.is_empty() {
             bail!("TextToAudioRequest.Text Illegal");
         }
@@ -530,10 +1224,10 @@ impl<'a> Api<'a> {
             .to_str()?;
         // check if content_type is audio
         if content_type.starts_with("audio/") {
-            let bytes = resp.bytes().await?;
+            let bytes = self.read_bytes(resp).await?;
             return Ok(bytes);
         }
-        let text = resp.text().await?;
+        let text = self.read_text(resp).await?;
         parse_error_response(&text)
     }
 
@@ -546,8 +1240,9 @@ impl<'a> Api<'a> {
     /// A `Result` containing the audio to text response or an error.
     pub async fn audio_to_text(
         &self,
-        req_data: AudioToTextRequest,
+        mut req_data: AudioToTextRequest,
     ) -> AnyResult<AudioToTextResponse> {
+        self.fill_user(&mut req_data.user);
         if !infer::is_audio(&req_data.file) {
             bail!("AudioToTextRequest.File Illegal");
         }
@@ -561,8 +1256,7 @@ impl<'a> Api<'a> {
 
         let url = self.build_request_api(ApiPath::AudioToText);
         let req = self.client.create_multipart_request(url, form)?;
-        let resp = self.send(req).await?;
-        let text = resp.text().await?;
+        let text = self.send_text(req).await?;
         parse_response::<AudioToTextResponse>(&text)
     }
 
@@ -573,15 +1267,18 @@ impl<'a> Api<'a> {
     ///
     /// # Returns
     /// A `Result` containing the parameters response or an error.
-    pub async fn parameters(&self, req_data: ParametersRequest) -> AnyResult<ParametersResponse> {
+    pub async fn parameters(
+        &self,
+        mut req_data: ParametersRequest,
+    ) -> AnyResult<ParametersResponse> {
+        self.fill_user(&mut req_data.user);
         if req_data.user.is_empty() {
             bail!("ParametersRequest.User Illegal");
         }
 
         let url = self.build_request_api(ApiPath::Parameters);
         let req = self.client.create_request(url, Method::GET, req_data)?;
-        let resp = self.send(req).await?;
-        let text = resp.text().await?;
+        let text = self.send_cached(ApiPath::Parameters, req).await?;
         parse_response::<ParametersResponse>(&text)
     }
 
@@ -592,27 +1289,117 @@ impl<'a> Api<'a> {
     ///
     /// # Returns
     /// A `Result` containing the meta response or an error.
-    pub async fn meta(&self, req_data: MetaRequest) -> AnyResult<MetaResponse> {
+    pub async fn meta(&self, mut req_data: MetaRequest) -> AnyResult<MetaResponse> {
+        self.fill_user(&mut req_data.user);
         if req_data.user.is_empty() {
             bail!("MetaRequest.User Illegal");
         }
 
         let url = self.build_request_api(ApiPath::Meta);
         let req = self.client.create_request(url, Method::GET, req_data)?;
-        let resp = self.send(req).await?;
-        let text = resp.text().await?;
+        let text = self.send_cached(ApiPath::Meta, req).await?;
         parse_response::<MetaResponse>(&text)
     }
 
+    /// Sends a request to retrieve the basic information of the app from the Dify API and returns the response.
+    ///
+    /// # Arguments
+    /// * `req_data` - The info request data.
+    ///
+    /// # Returns
+    /// A `Result` containing the info response or an error.
+    pub async fn info(&self, mut req_data: InfoRequest) -> AnyResult<InfoResponse> {
+        self.fill_user(&mut req_data.user);
+
+        let url = self.build_request_api(ApiPath::Info);
+        let req = self.client.create_request(url, Method::GET, req_data)?;
+        let text = self.send_cached(ApiPath::Info, req).await?;
+        parse_response::<InfoResponse>(&text)
+    }
+
+    /// Sends a request to retrieve the WebApp settings of the app from the Dify API and returns the response.
+    ///
+    /// # Arguments
+    /// * `req_data` - The site request data.
+    ///
+    /// # Returns
+    /// A `Result` containing the site response or an error.
+    pub async fn site(&self, mut req_data: SiteRequest) -> AnyResult<SiteResponse> {
+        self.fill_user(&mut req_data.user);
+
+        let url = self.build_request_api(ApiPath::Site);
+        let req = self.client.create_request(url, Method::GET, req_data)?;
+        let text = self.send_cached(ApiPath::Site, req).await?;
+        parse_response::<SiteResponse>(&text)
+    }
+
+    /// Sends a request to retrieve a page of the feedbacks of the app from the Dify API and returns the response.
+    ///
+    /// # Arguments
+    /// * `req_data` - The app feedbacks request data.
+    ///
+    /// # Returns
+    /// A `Result` containing the app feedbacks response or an error.
+    pub async fn app_feedbacks(
+        &self,
+        req_data: AppFeedbacksRequest,
+    ) -> AnyResult<AppFeedbacksResponse> {
+        if req_data.page == Some(0) {
+            bail!("AppFeedbacksRequest.Page Illegal");
+        }
+
+        let url = self.build_request_api(ApiPath::AppFeedbacks);
+        let req = self.client.create_request(url, Method::GET, req_data)?;
+        let text = self.send_text(req).await?;
+        parse_response::<AppFeedbacksResponse>(&text)
+    }
+
+    /// Returns a stream of all feedbacks of the app, paging through the app feedbacks.
+    /// The `limit` of the request is the page size, and the `page` the page to start from.
+    /// Paging stops at the first page with fewer feedbacks than the page size.
+    ///
+    /// # Arguments
+    /// * `req_data` - The app feedbacks request data of the first page.
+    ///
+    /// # Returns
+    /// A `Paginator` yielding the feedbacks.
+    pub fn app_feedbacks_iter(&self, req_data: AppFeedbacksRequest) -> Paginator<AppFeedback> {
+        let api = self.clone();
+        let limit = req_data.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        let page = req_data.page.unwrap_or(1);
+        Paginator::new(page, move |page| {
+            let api = api.clone();
+            let req_data = AppFeedbacksRequest {
+                page: Some(page),
+                limit: Some(limit),
+            };
+            async move {
+                let resp = api.app_feedbacks(req_data).await?;
+                let next = (resp.data.len() >= limit as usize).then_some(page + 1);
+                Ok((resp.data, next))
+            }
+        })
+    }
+
     /// Creates a request to run workflows from the Dify API.
     ///
     /// # Arguments
     /// * `req` - The workflows run request data.
+    /// * `workflow_id` - The ID of the workflow version to run, the published version if `None`.
     ///     
     /// # Returns
     /// A `Result` containing the request or an error.
-    fn create_workflows_run_request(&self, req: WorkflowsRunRequest) -> AnyResult<Request> {
-        let url = self.build_request_api(ApiPath::WorkflowsRun);
+    fn create_workflows_run_request(
+        &self,
+        req: WorkflowsRunRequest,
+        workflow_id: Option<&str>,
+    ) -> AnyResult<Request> {
+        let url = match workflow_id {
+            Some(workflow_id) => self
+                .build_request_api(ApiPath::WorkflowsRunById)
+                .replace("{workflow_id}", workflow_id),
+            None => self.build_request_api(ApiPath::WorkflowsRun),
+        };
         self.client.create_request(url, Method::POST, req)
     }
 
@@ -624,15 +1411,110 @@ impl<'a> Api<'a> {
     /// # Returns
     /// A `Result` containing the workflows run response or an error.
     pub async fn workflows_run(
+        &self,
+        req_data: WorkflowsRunRequest,
+    ) -> AnyResult<WorkflowsRunResponse> {
+        self.run_workflow(req_data, None).await
+    }
+
+    /// Sends a request to run a specific version of the workflow from the Dify API and returns the response.
+    ///
+    /// # Arguments
+    /// * `workflow_id` - The ID of the workflow version to run.
+    /// * `req_data` - The workflows run request data.
+    ///
+    /// # Returns
+    /// A `Result` containing the workflows run response or an error.
+    pub async fn workflows_run_by_id(
+        &self,
+        workflow_id: &str,
+        req_data: WorkflowsRunRequest,
+    ) -> AnyResult<WorkflowsRunResponse> {
+        if workflow_id.is_empty() {
+            bail!("WorkflowsRunRequest.WorkflowID Illegal");
+        }
+        self.run_workflow(req_data, Some(workflow_id)).await
+    }
+
+    /// Runs the workflow in blocking mode.
+    async fn run_workflow(
         &self,
         mut req_data: WorkflowsRunRequest,
+        workflow_id: Option<&str>,
     ) -> AnyResult<WorkflowsRunResponse> {
+        self.fill_user(&mut req_data.user);
+        self.validate_inputs(&req_data.inputs, &req_data.user)
+            .await?;
         req_data.response_mode = ResponseMode::Blocking;
 
-        let req = self.create_workflows_run_request(req_data)?;
+        let req = self.create_workflows_run_request(req_data, workflow_id)?;
         let resp = self.send(req).await?;
-        let text = resp.text().await?;
-        parse_response::<WorkflowsRunResponse>(&text)
+        let status = resp.status().as_u16();
+        let text = self.read_text(resp).await?;
+        parse_response_with_status::<WorkflowsRunResponse>(status, &text)
+    }
+
+    /// Sends a request to run workflows with typed inputs and returns the typed outputs.
+    ///
+    /// # Arguments
+    /// * `inputs` - The inputs, serialized into `req_data.inputs`.
+    /// * `req_data` - The workflows run request data.
+    ///
+    /// # Returns
+    /// A `Result` containing the typed workflows run response or an error.
+    ///
+    /// # Errors
+    /// Returns an error if the inputs cannot be serialized, the workflow did not succeed,
+    /// or the outputs cannot be deserialized.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dify_client::{request::WorkflowsRunRequest, Client};
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Serialize)]
+    /// struct SummaryInputs {
+    ///     input: String,
+    ///     max_words: u32,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct SummaryOutputs {
+    ///     output: String,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let client = Client::new("https://api.dify.ai", "API_KEY");
+    ///     let inputs = SummaryInputs {
+    ///         input: "Dify is an LLM app development platform.".into(),
+    ///         max_words: 20,
+    ///     };
+    ///     let req = WorkflowsRunRequest {
+    ///         user: "afa".into(),
+    ///         ..Default::default()
+    ///     };
+    ///     let result = client
+    ///         .api()
+    ///         .workflows_run_typed::<_, SummaryOutputs>(&inputs, req)
+    ///         .await?;
+    ///     println!("{}", result.outputs.output);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn workflows_run_typed<I, O>(
+        &self,
+        inputs: &I,
+        req_data: WorkflowsRunRequest,
+    ) -> AnyResult<WorkflowsRunTypedResponse<O>>
+    where
+        I: Serialize + ?Sized,
+        O: DeserializeOwned,
+    {
+        let req_data = req_data.with_inputs(inputs)?;
+        let response = self.workflows_run(req_data).await?;
+        let outputs = response.outputs_as()?;
+        Ok(WorkflowsRunTypedResponse { response, outputs })
     }
 
     /// Sends a request to run workflows from the Dify API and returns the response as a stream.
@@ -646,16 +1528,48 @@ impl<'a> Api<'a> {
     /// # Errors
     /// Returns an error if the request cannot be created or the stream fails.
     pub async fn workflows_run_stream(
+        &self,
+        req_data: WorkflowsRunRequest,
+    ) -> AnyResult<SseMessageEventStream<BytesStream>> {
+        self.run_workflow_stream(req_data, None).await
+    }
+
+    /// Sends a request to run a specific version of the workflow from the Dify API and returns the response as a stream.
+    ///
+    /// # Arguments
+    /// * `workflow_id` - The ID of the workflow version to run.
+    /// * `req_data` - The workflows run request data.
+    ///
+    /// # Returns
+    /// A `Result` containing SSE message event stream or an error.
+    ///
+    /// # Errors
+    /// Returns an error if the request cannot be created or the stream fails.
+    pub async fn workflows_run_stream_by_id(
+        &self,
+        workflow_id: &str,
+        req_data: WorkflowsRunRequest,
+    ) -> AnyResult<SseMessageEventStream<BytesStream>> {
+        if workflow_id.is_empty() {
+            bail!("WorkflowsRunRequest.WorkflowID Illegal");
+        }
+        self.run_workflow_stream(req_data, Some(workflow_id)).await
+    }
+
+    /// Runs the workflow in streaming mode.
+    async fn run_workflow_stream(
         &self,
         mut req_data: WorkflowsRunRequest,
-    ) -> AnyResult<SseMessageEventStream<impl Stream<Item = Result<Bytes, reqwest::Error>>>> {
+        workflow_id: Option<&str>,
+    ) -> AnyResult<SseMessageEventStream<BytesStream>> {
+        self.fill_user(&mut req_data.user);
+        self.validate_inputs(&req_data.inputs, &req_data.user)
+            .await?;
         req_data.response_mode = ResponseMode::Streaming;
 
-        let req = self.create_workflows_run_request(req_data)?;
+        let req = self.create_workflows_run_request(req_data, workflow_id)?;
         let resp = self.send(req).await?;
-        let stream = resp.bytes_stream().eventsource();
-        let s = SseMessageEventStream::new(stream);
-        Ok(s)
+        Ok(self.event_stream(resp))
     }
 
     /// Sends a request to stop stream workflows from the Dify API and returns the response.
@@ -673,6 +1587,52 @@ impl<'a> Api<'a> {
             .await
     }
 
+    /// Sends a request to retrieve a page of the workflow logs from the Dify API and returns the response.
+    ///
+    /// # Arguments
+    /// * `req_data` - The workflow logs request data.
+    ///
+    /// # Returns
+    /// A `Result` containing the workflow logs response or an error.
+    pub async fn workflows_logs(
+        &self,
+        req_data: WorkflowLogsRequest,
+    ) -> AnyResult<WorkflowLogsResponse> {
+        if req_data.page == Some(0) {
+            bail!("WorkflowLogsRequest.Page Illegal");
+        }
+
+        let url = self.build_request_api(ApiPath::WorkflowsLogs);
+        let req = self.client.create_request(url, Method::GET, req_data)?;
+        let text = self.send_text(req).await?;
+        parse_response::<WorkflowLogsResponse>(&text)
+    }
+
+    /// Returns a stream of all workflow logs matching the request, paging through the workflow logs.
+    /// The `limit` of the request is the page size, and the `page` the page to start from.
+    ///
+    /// # Arguments
+    /// * `req_data` - The workflow logs request data of the first page.
+    ///
+    /// # Returns
+    /// A `Paginator` yielding the workflow logs.
+    pub fn workflows_logs_iter(&self, req_data: WorkflowLogsRequest) -> Paginator<WorkflowLog> {
+        let api = self.clone();
+        let page = req_data.page.unwrap_or(1);
+        Paginator::new(page, move |page| {
+            let api = api.clone();
+            let req_data = WorkflowLogsRequest {
+                page: Some(page),
+                ..req_data.clone()
+            };
+            async move {
+                let resp = api.workflows_logs(req_data).await?;
+                let next = resp.has_more.then_some(page + 1);
+                Ok((resp.data, next))
+            }
+        })
+    }
+
     /// Creates a request to create completion messages from the Dify API.
     ///
     /// # Arguments
@@ -700,11 +1660,13 @@ impl<'a> Api<'a> {
         &self,
         mut req_data: CompletionMessagesRequest,
     ) -> AnyResult<CompletionMessagesResponse> {
+        self.fill_user(&mut req_data.user);
+        self.validate_inputs(&req_data.inputs, &req_data.user)
+            .await?;
         req_data.response_mode = ResponseMode::Blocking;
 
         let req = self.create_completion_messages_request(req_data)?;
-        let resp = self.send(req).await?;
-        let text = resp.text().await?;
+        let text = self.send_text(req).await?;
         parse_response::<CompletionMessagesResponse>(&text)
     }
 
@@ -721,14 +1683,15 @@ impl<'a> Api<'a> {
     pub async fn completion_messages_stream(
         &self,
         mut req_data: CompletionMessagesRequest,
-    ) -> AnyResult<SseMessageEventStream<impl Stream<Item = Result<Bytes, reqwest::Error>>>> {
+    ) -> AnyResult<SseMessageEventStream<BytesStream>> {
+        self.fill_user(&mut req_data.user);
+        self.validate_inputs(&req_data.inputs, &req_data.user)
+            .await?;
         req_data.response_mode = ResponseMode::Streaming;
 
         let req = self.create_completion_messages_request(req_data)?;
         let resp = self.send(req).await?;
-        let stream = resp.bytes_stream().eventsource();
-        let s = SseMessageEventStream::new(stream);
-        Ok(s)
+        Ok(self.event_stream(resp))
     }
 
     /// Sends a request to stop stream completion messages from the Dify API and returns the response.
@@ -0,0 +1,595 @@
+//! This module provides running a workflow over the rows of a CSV or JSONL file.
+//!
+//! The [`BatchRunner`] reads the rows, maps the columns to the variables of the user input form
+//! of the app (by name, or with explicit mappings), converts the CSV cells to the types of the
+//! form items, validates the inputs against `parameters()`, and runs `workflows_run` for each row
+//! with bounded concurrency, retrying transient failures (network errors, `429` and `5xx`).
+//!
+//! One [`BatchResult`] per row is appended to a JSONL results file as soon as the row completes,
+//! with the outputs or the error, the tokens and the number of attempts.
+//! The results file is also the checkpoint: an interrupted batch resumes by skipping the rows
+//! already in the results file with the same index and the same values, so rows edited in the
+//! input are run again, and a line torn by a crash is dropped.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use dify_client::{batch::BatchRunner, Client};
+//! use std::time::Duration;
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let client = Client::new("https://api.dify.ai", "API_KEY");
+//!     let summary = BatchRunner::new(client.api())
+//!         .with_concurrency(8)
+//!         .with_retries(3, Duration::from_secs(1))
+//!         .with_mapping("article_text", "text")
+//!         .run_file("articles.csv", "results.jsonl")
+//!         .await?;
+//!     println!("{:?}", summary);
+//!     Ok(())
+//! }
+//! ```
+use super::{
+    api::Api,
+    request::WorkflowsRunRequest,
+    response::{
+        ErrorResponse, FinishedStatus, ParameterUserInputFormItem, ParametersResponse,
+        WorkflowsRunResponse,
+    },
+    util::stable_hash,
+    validation::{InputsValidator, ValidationError},
+};
+use anyhow::{anyhow, bail, Error, Result as AnyResult};
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    io::{BufRead, BufReader, ErrorKind},
+    path::Path,
+    time::Duration,
+};
+use tokio::io::AsyncWriteExt;
+
+/// The default number of rows run concurrently.
+pub const DEFAULT_CONCURRENCY: usize = 4;
+/// The default user of the batch requests.
+pub const DEFAULT_BATCH_USER: &str = "dify-batch";
+
+/// The format of a batch input file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BatchFormat {
+    /// Comma separated values with a header row.
+    Csv,
+    /// One JSON object per line.
+    Jsonl,
+}
+
+impl BatchFormat {
+    /// Returns the batch format for the file extension of the path.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the input file, `.csv`, `.jsonl` or `.ndjson`.
+    ///
+    /// # Returns
+    /// A `Result` containing the batch format or an error.
+    pub fn from_path(path: &Path) -> AnyResult<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => Ok(BatchFormat::Csv),
+            Some("jsonl" | "ndjson") => Ok(BatchFormat::Jsonl),
+            _ => bail!("BatchFile.Format Illegal: {}", path.display()),
+        }
+    }
+}
+
+/// A row of a batch input file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BatchRow {
+    /// The index of the row in the file, from 0, excluding the header and blank lines.
+    pub index: usize,
+    /// The values by column, strings for CSV.
+    pub values: BTreeMap<String, JsonValue>,
+}
+
+impl BatchRow {
+    /// Returns the hash of the values of the row, recorded in its result to detect edited rows.
+    pub fn hash(&self) -> String {
+        let values = serde_json::to_string(&self.values).unwrap_or_default();
+        format!("{:016x}", stable_hash(values.as_bytes()))
+    }
+}
+
+/// The status of a row.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    /// The workflow succeeded.
+    Succeeded,
+    /// The workflow failed, or the request failed after all retries.
+    Failed,
+    /// The inputs of the row are invalid, the workflow was not run.
+    Invalid,
+}
+
+/// The result of a row, one line of the results file.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BatchResult {
+    /// The index of the row.
+    pub index: usize,
+    /// The hash of the values of the row, see [`BatchRow::hash`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub row_hash: Option<String>,
+    /// The status of the row.
+    pub status: BatchStatus,
+    /// The workflow run ID.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workflow_run_id: Option<String>,
+    /// The outputs of the workflow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<JsonValue>,
+    /// The error of the workflow, the request or the inputs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The total tokens used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_tokens: Option<u32>,
+    /// The elapsed time of the workflow in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed_time: Option<f64>,
+    /// The number of requests sent.
+    pub attempts: u32,
+}
+
+impl BatchResult {
+    fn new(index: usize, status: BatchStatus, attempts: u32) -> Self {
+        Self {
+            index,
+            row_hash: None,
+            status,
+            workflow_run_id: None,
+            outputs: None,
+            error: None,
+            total_tokens: None,
+            elapsed_time: None,
+            attempts,
+        }
+    }
+
+    fn error(index: usize, status: BatchStatus, attempts: u32, error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::new(index, status, attempts)
+        }
+    }
+
+    fn from_response(index: usize, attempts: u32, resp: WorkflowsRunResponse) -> Self {
+        let data = resp.data;
+        let status = match data.status {
+            FinishedStatus::Succeeded => BatchStatus::Succeeded,
+            _ => BatchStatus::Failed,
+        };
+        Self {
+            workflow_run_id: Some(resp.workflow_run_id),
+            outputs: data.outputs,
+            error: data.error.filter(|e| !e.is_empty()),
+            total_tokens: data.total_tokens,
+            elapsed_time: data.elapsed_time,
+            ..Self::new(index, status, attempts)
+        }
+    }
+}
+
+/// The summary of a batch.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BatchSummary {
+    /// The number of rows in the input.
+    pub total: usize,
+    /// The number of rows succeeded in this run.
+    pub succeeded: usize,
+    /// The number of rows failed in this run.
+    pub failed: usize,
+    /// The number of rows with invalid inputs in this run.
+    pub invalid: usize,
+    /// The number of rows skipped, completed by a previous run.
+    pub skipped: usize,
+    /// The total tokens used in this run.
+    pub total_tokens: u64,
+}
+
+/// Runs a workflow over the rows of a batch.
+#[derive(Clone)]
+pub struct BatchRunner {
+    api: Api,
+    user: String,
+    workflow_id: Option<String>,
+    concurrency: usize,
+    max_retries: u32,
+    retry_delay: Duration,
+    mapping: HashMap<String, String>,
+    validate: bool,
+    retry_failed: bool,
+}
+
+impl BatchRunner {
+    /// Creates a new batch runner with the API of the workflow app.
+    ///
+    /// # Arguments
+    /// * `api` - The API of the workflow app.
+    pub fn new(api: Api) -> Self {
+        let user = api.default_user().unwrap_or(DEFAULT_BATCH_USER).to_string();
+        Self {
+            api,
+            user,
+            workflow_id: None,
+            concurrency: DEFAULT_CONCURRENCY,
+            max_retries: 2,
+            retry_delay: Duration::from_secs(1),
+            mapping: HashMap::new(),
+            validate: true,
+            retry_failed: false,
+        }
+    }
+
+    /// Sets the user of the requests, the default user of the API or `dify-batch` by default.
+    ///
+    /// # Arguments
+    /// * `user` - The user.
+    pub fn with_user(mut self, user: impl Into<String>) -> Self {
+        self.user = user.into();
+        self
+    }
+
+    /// Runs a specific version of the workflow instead of the published version.
+    ///
+    /// # Arguments
+    /// * `workflow_id` - The workflow ID of the version.
+    pub fn with_workflow_id(mut self, workflow_id: impl Into<String>) -> Self {
+        self.workflow_id = Some(workflow_id.into());
+        self
+    }
+
+    /// Sets the number of rows run concurrently, at least 1.
+    ///
+    /// # Arguments
+    /// * `concurrency` - The number of rows run concurrently.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets the retries of transient failures, with an exponential backoff.
+    ///
+    /// # Arguments
+    /// * `max_retries` - The maximum number of retries per row.
+    /// * `delay` - The delay before the first retry, doubled for each following retry.
+    pub fn with_retries(mut self, max_retries: u32, delay: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Maps a column of the input to a variable of the workflow.
+    /// Columns without a mapping are used for the variable of the same name, if any.
+    ///
+    /// # Arguments
+    /// * `column` - The column of the input.
+    /// * `variable` - The variable of the workflow.
+    pub fn with_mapping(mut self, column: impl Into<String>, variable: impl Into<String>) -> Self {
+        self.mapping.insert(column.into(), variable.into());
+        self
+    }
+
+    /// Sets whether the inputs are validated against the parameters, `true` by default.
+    /// Rows with invalid inputs are recorded as invalid without running the workflow.
+    ///
+    /// # Arguments
+    /// * `validate` - Whether the inputs are validated.
+    pub fn with_validation(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// Sets whether the failed and invalid rows of a previous run are run again when resuming,
+    /// `false` by default.
+    ///
+    /// # Arguments
+    /// * `retry_failed` - Whether the failed rows are run again.
+    pub fn with_retry_failed(mut self, retry_failed: bool) -> Self {
+        self.retry_failed = retry_failed;
+        self
+    }
+
+    /// Runs the workflow over the rows of an input file, appending the results to a JSONL file.
+    ///
+    /// # Arguments
+    /// * `input` - The path of the input file, `.csv`, `.jsonl` or `.ndjson`.
+    /// * `output` - The path of the results file, resumed if it exists.
+    ///
+    /// # Returns
+    /// A `Result` containing the summary of the batch or an error.
+    pub async fn run_file(
+        &self,
+        input: impl AsRef<Path>,
+        output: impl AsRef<Path>,
+    ) -> AnyResult<BatchSummary> {
+        let input = input.as_ref().to_path_buf();
+        let rows = tokio::task::spawn_blocking(move || read_rows(input)).await??;
+        self.run_rows(rows, output).await
+    }
+
+    /// Runs the workflow over the rows, appending the results to a JSONL file.
+    ///
+    /// # Arguments
+    /// * `rows` - The rows.
+    /// * `output` - The path of the results file, resumed if it exists.
+    ///
+    /// # Returns
+    /// A `Result` containing the summary of the batch or an error.
+    ///
+    /// # Errors
+    /// Returns an error if the parameters cannot be fetched or the results file cannot be written.
+    /// The failures of the rows are recorded in the results file.
+    pub async fn run_rows(
+        &self,
+        rows: Vec<BatchRow>,
+        output: impl AsRef<Path>,
+    ) -> AnyResult<BatchSummary> {
+        let output = output.as_ref();
+        let mut summary = BatchSummary {
+            total: rows.len(),
+            ..Default::default()
+        };
+        let completed: HashSet<(usize, String)> = resume_results(output)
+            .await?
+            .into_iter()
+            .filter(|result| !self.retry_failed || result.status == BatchStatus::Succeeded)
+            .filter_map(|result| Some((result.index, result.row_hash?)))
+            .collect();
+        let pending: Vec<(BatchRow, String)> = rows
+            .into_iter()
+            .map(|row| {
+                let hash = row.hash();
+                (row, hash)
+            })
+            .filter(|(row, hash)| !completed.contains(&(row.index, hash.clone())))
+            .collect();
+        summary.skipped = summary.total - pending.len();
+        if pending.is_empty() {
+            return Ok(summary);
+        }
+
+        let params = InputsValidator::new()
+            .parameters(&self.api, &self.user)
+            .await?;
+        if let Some(dir) = output.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(output)
+            .await?;
+        let mut results = stream::iter(pending)
+            .map(|(row, hash)| {
+                let params = params.clone();
+                async move {
+                    BatchResult {
+                        row_hash: Some(hash),
+                        ..self.run_row(&params, row).await
+                    }
+                }
+            })
+            .buffer_unordered(self.concurrency);
+        while let Some(result) = results.next().await {
+            file.write_all((serde_json::to_string(&result)? + "\n").as_bytes())
+                .await?;
+            file.sync_data().await?;
+            match result.status {
+                BatchStatus::Succeeded => summary.succeeded += 1,
+                BatchStatus::Failed => summary.failed += 1,
+                BatchStatus::Invalid => summary.invalid += 1,
+            }
+            summary.total_tokens += result.total_tokens.unwrap_or_default() as u64;
+        }
+        Ok(summary)
+    }
+
+    /// Maps the values of a row to the inputs of the workflow.
+    fn inputs(
+        &self,
+        params: &ParametersResponse,
+        row: BatchRow,
+    ) -> AnyResult<HashMap<String, JsonValue>> {
+        let mut inputs = HashMap::new();
+        for (column, value) in row.values {
+            let mapped = self.mapping.get(&column);
+            let variable = mapped.cloned().unwrap_or(column);
+            let item = params
+                .user_input_form
+                .iter()
+                .find(|item| item.variable() == variable);
+            let value = match (item, value) {
+                (_, JsonValue::Null) => continue,
+                (_, JsonValue::String(text)) if text.is_empty() => continue,
+                (Some(item), JsonValue::String(text)) => convert(item, &variable, &text)?,
+                (Some(_), value) => value,
+                (None, value) if mapped.is_some() => value,
+                // columns which are not variables are ignored
+                (None, _) => continue,
+            };
+            inputs.insert(variable, value);
+        }
+        Ok(inputs)
+    }
+
+    /// Runs the workflow for a row, retrying transient failures.
+    async fn run_row(&self, params: &ParametersResponse, row: BatchRow) -> BatchResult {
+        let index = row.index;
+        let inputs = match self.inputs(params, row) {
+            Ok(inputs) => inputs,
+            Err(e) => return BatchResult::error(index, BatchStatus::Invalid, 0, e.to_string()),
+        };
+        if self.validate {
+            if let Err(e) = params.validate_inputs(&inputs) {
+                return BatchResult::error(index, BatchStatus::Invalid, 0, e.to_string());
+            }
+        }
+        let req_data = WorkflowsRunRequest {
+            inputs,
+            user: self.user.clone(),
+            ..Default::default()
+        };
+
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let resp = match self.workflow_id.as_deref() {
+                Some(workflow_id) => {
+                    self.api
+                        .workflows_run_by_id(workflow_id, req_data.clone())
+                        .await
+                }
+                None => self.api.workflows_run(req_data.clone()).await,
+            };
+            match resp {
+                Ok(resp) => return BatchResult::from_response(index, attempts, resp),
+                Err(e) if attempts <= self.max_retries && is_transient(&e) => {
+                    let delay = self.retry_delay * 2u32.saturating_pow(attempts - 1);
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => {
+                    return BatchResult::error(index, BatchStatus::Failed, attempts, e.to_string())
+                }
+            }
+        }
+    }
+}
+
+/// Returns whether an error is transient: a connect or timeout error, `429` or `5xx`.
+fn is_transient(error: &Error) -> bool {
+    if error.downcast_ref::<ValidationError>().is_some() {
+        return false;
+    }
+    if let Some(resp) = error.downcast_ref::<ErrorResponse>() {
+        return resp.status == 429 || resp.status >= 500;
+    }
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.is_connect() || e.is_timeout())
+}
+
+/// Converts a CSV cell to the type of the form item.
+fn convert(item: &ParameterUserInputFormItem, variable: &str, text: &str) -> AnyResult<JsonValue> {
+    let value = match item {
+        ParameterUserInputFormItem::Number { .. } => JsonValue::Number(
+            text.trim()
+                .parse()
+                .map_err(|_| anyhow!("BatchRow.{} Illegal: not a number", variable))?,
+        ),
+        ParameterUserInputFormItem::Checkbox { .. } => {
+            match text.trim().to_ascii_lowercase().as_str() {
+                "true" | "1" | "yes" | "y" => JsonValue::Bool(true),
+                "false" | "0" | "no" | "n" => JsonValue::Bool(false),
+                _ => bail!("BatchRow.{} Illegal: not a boolean", variable),
+            }
+        }
+        ParameterUserInputFormItem::File { .. } | ParameterUserInputFormItem::FileList { .. } => {
+            serde_json::from_str(text)
+                .map_err(|_| anyhow!("BatchRow.{} Illegal: not a file JSON", variable))?
+        }
+        _ => JsonValue::String(text.into()),
+    };
+    Ok(value)
+}
+
+/// Reads the rows of a CSV or JSONL file.
+///
+/// # Arguments
+/// * `path` - The path of the file, `.csv`, `.jsonl` or `.ndjson`.
+///
+/// # Returns
+/// A `Result` containing the rows or an error.
+pub fn read_rows(path: impl AsRef<Path>) -> AnyResult<Vec<BatchRow>> {
+    let path = path.as_ref();
+    let file = fs::File::open(path)
+        .map_err(|e| anyhow!("Failed to read batch file {}: {}", path.display(), e))?;
+    match BatchFormat::from_path(path)? {
+        BatchFormat::Csv => {
+            let mut reader = csv::Reader::from_reader(file);
+            let headers = reader.headers()?.clone();
+            reader
+                .records()
+                .enumerate()
+                .map(|(index, record)| {
+                    let values = headers
+                        .iter()
+                        .zip(record?.iter())
+                        .map(|(column, value)| (column.to_string(), JsonValue::from(value)))
+                        .collect();
+                    Ok(BatchRow { index, values })
+                })
+                .collect()
+        }
+        BatchFormat::Jsonl => BufReader::new(file)
+            .lines()
+            .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+            .enumerate()
+            .map(|(index, line)| {
+                let values: JsonMap<String, JsonValue> = serde_json::from_str(&line?)
+                    .map_err(|e| anyhow!("BatchRow.{} Illegal: {}", index, e))?;
+                Ok(BatchRow {
+                    index,
+                    values: values.into_iter().collect(),
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Reads the results file, the last result of each row in the order of the rows.
+///
+/// # Arguments
+/// * `path` - The path of the results file.
+///
+/// # Returns
+/// A `Result` containing the results or an error.
+pub fn read_results(path: impl AsRef<Path>) -> AnyResult<Vec<BatchResult>> {
+    let file = fs::File::open(path.as_ref())?;
+    parse_results(BufReader::new(file))
+}
+
+/// Parses the lines of a results file, the last result of each row in the order of the rows.
+fn parse_results(reader: impl BufRead) -> AnyResult<Vec<BatchResult>> {
+    let mut results = BTreeMap::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let result: BatchResult = serde_json::from_str(&line)?;
+        results.insert(result.index, result);
+    }
+    Ok(results.into_values().collect())
+}
+
+/// Reads the results of a previous run, dropping a line torn by a crash.
+/// No results are returned if the results file does not exist.
+async fn resume_results(path: &Path) -> AnyResult<Vec<BatchResult>> {
+    let bytes = match tokio::fs::read(path).await {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let complete = bytes.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+    if complete < bytes.len() {
+        tokio::fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .await?
+            .set_len(complete as u64)
+            .await?;
+    }
+    parse_results(&bytes[..complete])
+}
@@ -0,0 +1,212 @@
+//! This module provides an in-memory cache of the responses of read-only endpoints.
+//!
+//! The parameters, meta information, basic information and WebApp settings of an app change
+//! rarely, but are often fetched for every session. A [`ResponseCache`] attached to an [`Api`]
+//! with [`Api::with_cache`] keeps these responses per app (base URL and API key) for a time to
+//! live, up to a maximum number of entries with the least recently used evicted first.
+//! The responses are the same for all users of an app, so the user of the request is not part
+//! of the key.
+//!
+//! When an expired response has an `ETag`, it is revalidated with `If-None-Match`, and reused
+//! without downloading it again if the server answers `304 Not Modified`.
+//! Only successful responses are cached, and the entries can be invalidated explicitly.
+//! The parameters fetched by an [`InputsValidator`](crate::validation::InputsValidator)
+//! through an API with a cache are cached too.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use dify_client::{cache::ResponseCache, request, Client};
+//! use std::time::Duration;
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let client = Client::new("https://api.dify.ai", "API_KEY");
+//!     let cache = ResponseCache::new().with_ttl(Duration::from_secs(600));
+//!     let api = client.api().with_cache(cache.clone());
+//!     for user in ["alice", "bob"] {
+//!         // fetched once, then served from the cache
+//!         let params = api
+//!             .parameters(request::ParametersRequest { user: user.into() })
+//!             .await?;
+//!         println!("{}", params.opening_statement);
+//!     }
+//!     // after publishing a new version of the app
+//!     cache.invalidate(&api);
+//!     Ok(())
+//! }
+//! ```
+use super::api::{Api, ApiPath};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// The default time to live of the cached responses.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(300);
+/// The default maximum number of cached responses.
+pub const DEFAULT_MAX_ENTRIES: usize = 256;
+
+/// The key of a cached response, the app key and the API path.
+type CacheKey = (String, ApiPath);
+
+/// A cached response.
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    body: String,
+    etag: Option<String>,
+    fetched_at: Instant,
+    used_at: Instant,
+}
+
+/// A cached response returned by a lookup.
+#[derive(Debug, Clone)]
+pub(crate) struct CachedResponse {
+    /// The body of the response.
+    pub body: String,
+    /// The `ETag` of the response.
+    pub etag: Option<String>,
+    /// Whether the response is within its time to live.
+    pub fresh: bool,
+}
+
+/// An in-memory cache of the responses of read-only endpoints, shared by its clones.
+#[derive(Clone, Debug)]
+pub struct ResponseCache {
+    ttl: Duration,
+    max_entries: usize,
+    entries: Arc<Mutex<HashMap<CacheKey, CacheEntry>>>,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self {
+            ttl: DEFAULT_TTL,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            entries: Arc::default(),
+        }
+    }
+}
+
+impl ResponseCache {
+    /// Creates a new cache with the default time to live and maximum number of entries.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the time to live of the cached responses, 5 minutes by default.
+    /// With a zero time to live, the responses with an `ETag` are revalidated on every request.
+    ///
+    /// # Arguments
+    /// * `ttl` - The time to live.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets the maximum number of cached responses, 256 by default.
+    ///
+    /// # Arguments
+    /// * `max_entries` - The maximum number of cached responses.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Returns whether the responses of the API path are cached:
+    /// the parameters, meta, info and site endpoints.
+    ///
+    /// # Arguments
+    /// * `api_path` - The API path.
+    pub fn is_cacheable(api_path: ApiPath) -> bool {
+        matches!(
+            api_path,
+            ApiPath::Parameters | ApiPath::Meta | ApiPath::Info | ApiPath::Site
+        )
+    }
+
+    /// Removes the cached responses of the app of the API.
+    ///
+    /// # Arguments
+    /// * `api` - The API of the app.
+    pub fn invalidate(&self, api: &Api) {
+        let app_key = api.app_key();
+        self.lock().retain(|(key, _), _| *key != app_key);
+    }
+
+    /// Removes the cached response of an endpoint of the app of the API.
+    ///
+    /// # Arguments
+    /// * `api` - The API of the app.
+    /// * `api_path` - The API path of the endpoint.
+    pub fn invalidate_path(&self, api: &Api, api_path: ApiPath) {
+        self.lock().remove(&(api.app_key(), api_path));
+    }
+
+    /// Removes all the cached responses.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Returns the number of cached responses.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns whether no response is cached.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Returns the cached response of an endpoint of an app, fresh or expired.
+    pub(crate) fn get(&self, app_key: &str, api_path: ApiPath) -> Option<CachedResponse> {
+        let mut entries = self.lock();
+        let entry = entries.get_mut(&(app_key.to_string(), api_path))?;
+        entry.used_at = Instant::now();
+        Some(CachedResponse {
+            body: entry.body.clone(),
+            etag: entry.etag.clone(),
+            fresh: entry.fetched_at.elapsed() < self.ttl,
+        })
+    }
+
+    /// Caches the response of an endpoint of an app, evicting the least recently used
+    /// response if the cache is full.
+    pub(crate) fn insert(
+        &self,
+        app_key: &str,
+        api_path: ApiPath,
+        body: String,
+        etag: Option<String>,
+    ) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let mut entries = self.lock();
+        let key = (app_key.to_string(), api_path);
+        if !entries.contains_key(&key) && entries.len() >= self.max_entries {
+            let lru = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.used_at)
+                .map(|(key, _)| key.clone());
+            if let Some(lru) = lru {
+                entries.remove(&lru);
+            }
+        }
+        let now = Instant::now();
+        entries.insert(
+            key,
+            CacheEntry {
+                body,
+                etag,
+                fetched_at: now,
+                used_at: now,
+            },
+        );
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<CacheKey, CacheEntry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,645 @@
+//! This module provides recording and replaying HTTP interactions with cassette files.
+//!
+//! A [`Cassette`] plugged into a [`Client`](crate::Client) with
+//! [`ClientBuilder::cassette`](crate::ClientBuilder::cassette) sees every request the client
+//! executes. When recording, the requests are sent and the interactions are recorded, SSE streams
+//! chunk by chunk with the delay of each chunk, and multipart uploads with their body.
+//! The recorded interactions are saved to a JSON cassette file with [`Cassette::save`], or when
+//! the last clone of the cassette is dropped. When replaying, no request is sent: each request
+//! is matched against the recorded interactions and answered with the recorded response,
+//! streams with the recorded timing.
+//!
+//! The `Authorization` header is redacted from the cassette, and more headers can be redacted
+//! with [`Cassette::with_redacted_header`]. Requests are matched on the method, the [`ApiPath`]
+//! and the body by default, see [`Matcher`]. The interactions are replayed in the recorded order,
+//! the last matching interaction is repeated once all the matching interactions are used.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use dify_client::{cassette::{Cassette, CassetteMode}, request, Client};
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     // records on the first run, replays without network afterwards
+//!     let cassette = Cassette::new("tests/cassettes/chat.json", CassetteMode::Auto)?;
+//!     let client = Client::builder()
+//!         .base_url("https://api.dify.ai")
+//!         .api_key("API_KEY")
+//!         .cassette(cassette.clone())
+//!         .build()?;
+//!     let data = request::ChatMessagesRequest {
+//!         query: "hello".into(),
+//!         user: "afa".into(),
+//!         ..Default::default()
+//!     };
+//!     let resp = client.api().chat_messages(data).await?;
+//!     println!("{}", resp.answer);
+//!     cassette.save()?;
+//!     Ok(())
+//! }
+//! ```
+use super::{
+    api::ApiPath,
+    http::{header, Request, Response},
+};
+use anyhow::{anyhow, bail, Result as AnyResult};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use bytes::Bytes;
+use futures::{stream, StreamExt};
+use http_body_util::BodyExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// The value of the redacted headers.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// The boundary of the recorded multipart bodies, replacing the random boundary of a request.
+const MULTIPART_BOUNDARY: &str = "dify-cassette-boundary";
+
+/// The mode of a cassette.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CassetteMode {
+    /// Sends the requests and records the interactions, replacing the cassette file.
+    Record,
+    /// Replays the recorded interactions without sending any request.
+    Replay,
+    /// Replays if the cassette file exists, records otherwise.
+    Auto,
+}
+
+/// What a request is matched on when replaying.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Matcher {
+    /// The HTTP method.
+    Method,
+    /// The API path, ignoring the values of the path parameters and the base URL.
+    ApiPath,
+    /// The exact path of the URL.
+    Path,
+    /// The query of the URL.
+    Query,
+    /// The body, compared as JSON if both bodies are JSON.
+    Body,
+}
+
+/// The default matchers: the method, the API path and the body.
+pub const DEFAULT_MATCHERS: [Matcher; 3] = [Matcher::Method, Matcher::ApiPath, Matcher::Body];
+
+/// A recorded body.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedBody {
+    /// A JSON body.
+    Json(JsonValue),
+    /// A UTF-8 text body.
+    Text(String),
+    /// A binary body, base64 encoded.
+    Base64(String),
+}
+
+impl RecordedBody {
+    /// Records the bytes of a body, as JSON if `json` is set and the bytes are JSON.
+    fn from_bytes(bytes: &[u8], json: bool) -> Self {
+        if json {
+            if let Ok(value) = serde_json::from_slice(bytes) {
+                return RecordedBody::Json(value);
+            }
+        }
+        match std::str::from_utf8(bytes) {
+            Ok(text) => RecordedBody::Text(text.to_string()),
+            Err(_) => RecordedBody::Base64(BASE64.encode(bytes)),
+        }
+    }
+
+    /// Returns the bytes of the body.
+    fn to_bytes(&self) -> AnyResult<Bytes> {
+        let bytes = match self {
+            RecordedBody::Json(value) => serde_json::to_vec(value)?.into(),
+            RecordedBody::Text(text) => text.clone().into(),
+            RecordedBody::Base64(data) => BASE64
+                .decode(data)
+                .map_err(|e| anyhow!("RecordedBody.Base64 Illegal: {}", e))?
+                .into(),
+        };
+        Ok(bytes)
+    }
+
+    /// Returns whether the bodies are the same, comparing JSON bodies as JSON.
+    fn matches(&self, other: &RecordedBody) -> bool {
+        match (self, other) {
+            (RecordedBody::Json(a), RecordedBody::Json(b)) => a == b,
+            (a, b) => matches!((a.to_bytes(), b.to_bytes()), (Ok(a), Ok(b)) if a == b),
+        }
+    }
+}
+
+/// A recorded request.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RecordedRequest {
+    /// The HTTP method.
+    pub method: String,
+    /// The path of the URL.
+    pub path: String,
+    /// The query of the URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    /// The headers, with the redacted values replaced.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// The body.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<RecordedBody>,
+}
+
+impl RecordedRequest {
+    /// Returns the API path of the request.
+    pub fn api_path(&self) -> Option<ApiPath> {
+        ApiPath::from_path(&self.path)
+    }
+
+    /// Returns whether the request matches the recorded request on all the matchers.
+    fn matches(&self, recorded: &RecordedRequest, matchers: &[Matcher]) -> bool {
+        matchers.iter().all(|matcher| match matcher {
+            Matcher::Method => self.method.eq_ignore_ascii_case(&recorded.method),
+            Matcher::ApiPath => match (self.api_path(), recorded.api_path()) {
+                (Some(a), Some(b)) => a == b,
+                _ => self.path == recorded.path,
+            },
+            Matcher::Path => self.path == recorded.path,
+            Matcher::Query => self.query == recorded.query,
+            Matcher::Body => match (&self.body, &recorded.body) {
+                (Some(a), Some(b)) => a.matches(b),
+                (a, b) => a.is_none() && b.is_none(),
+            },
+        })
+    }
+}
+
+/// A chunk of a recorded stream.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RecordedChunk {
+    /// The delay after the previous chunk, or after the response headers, in milliseconds.
+    pub delay_ms: u64,
+    /// The data of the chunk.
+    #[serde(flatten)]
+    pub data: RecordedBody,
+}
+
+/// A recorded response.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RecordedResponse {
+    /// The HTTP status code.
+    pub status: u16,
+    /// The headers, with the redacted values replaced.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// The time until the response headers in milliseconds.
+    #[serde(default)]
+    pub latency_ms: u64,
+    /// The body of a non-streaming response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<RecordedBody>,
+    /// The chunks of a streaming (`text/event-stream`) response.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chunks: Vec<RecordedChunk>,
+}
+
+/// A recorded interaction, a request and its response.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Interaction {
+    /// The request.
+    pub request: RecordedRequest,
+    /// The response.
+    pub response: RecordedResponse,
+}
+
+/// The content of a cassette file.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+/// The interactions of a cassette, whether they were replayed, and whether some are unsaved.
+#[derive(Debug, Default)]
+struct CassetteState {
+    interactions: Vec<Interaction>,
+    replayed: Vec<bool>,
+    unsaved: bool,
+}
+
+/// The cassette file and the interactions, shared by the clones of a cassette.
+#[derive(Debug)]
+struct CassetteInner {
+    path: PathBuf,
+    mode: CassetteMode,
+    state: Mutex<CassetteState>,
+    /// Held while saving, so the saves are written in order.
+    saving: Mutex<()>,
+}
+
+impl CassetteInner {
+    /// Saves the recorded interactions to the cassette file, if some are unsaved.
+    fn save(&self) -> AnyResult<()> {
+        let _saving = self.saving.lock().unwrap_or_else(|e| e.into_inner());
+        let json = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            if !state.unsaved {
+                return Ok(());
+            }
+            let file = CassetteFile {
+                interactions: state.interactions.clone(),
+            };
+            state.unsaved = false;
+            serde_json::to_string_pretty(&file)? + "\n"
+        };
+        let result = self.write(&json);
+        if result.is_err() {
+            self.state.lock().unwrap_or_else(|e| e.into_inner()).unsaved = true;
+        }
+        result
+    }
+
+    fn write(&self, json: &str) -> AnyResult<()> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, json)
+            .map_err(|e| anyhow!("Failed to write cassette {}: {}", self.path.display(), e))
+    }
+}
+
+impl Drop for CassetteInner {
+    fn drop(&mut self) {
+        // the last clone of the cassette is dropped, a drop cannot return the error
+        let _ = self.save();
+    }
+}
+
+/// A cassette recording or replaying the HTTP interactions of a client.
+/// The clones of a cassette share the interactions, and the recorded interactions are saved
+/// when the last clone is dropped if they were not saved with [`Cassette::save`].
+/// The errors of saving on drop are ignored, call [`Cassette::save`] to handle them.
+#[derive(Clone, Debug)]
+pub struct Cassette {
+    matchers: Vec<Matcher>,
+    redacted_headers: Vec<header::HeaderName>,
+    time_scale: f64,
+    inner: Arc<CassetteInner>,
+}
+
+impl Cassette {
+    /// Creates a new cassette.
+    /// A cassette in the `Auto` mode replays if the file exists and records otherwise.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the cassette file.
+    /// * `mode` - The mode of the cassette.
+    ///
+    /// # Returns
+    /// A `Result` containing the cassette or an error.
+    ///
+    /// # Errors
+    /// Returns an error if the cassette file of a replaying cassette cannot be read.
+    pub fn new(path: impl Into<PathBuf>, mode: CassetteMode) -> AnyResult<Self> {
+        let path = path.into();
+        let mode = match mode {
+            CassetteMode::Auto if path.is_file() => CassetteMode::Replay,
+            CassetteMode::Auto => CassetteMode::Record,
+            mode => mode,
+        };
+        let mut state = CassetteState::default();
+        if mode == CassetteMode::Replay {
+            let json = fs::read_to_string(&path)
+                .map_err(|e| anyhow!("Failed to read cassette {}: {}", path.display(), e))?;
+            let file: CassetteFile = serde_json::from_str(&json)
+                .map_err(|e| anyhow!("Cassette {} Illegal: {}", path.display(), e))?;
+            state.replayed = vec![false; file.interactions.len()];
+            state.interactions = file.interactions;
+        }
+        Ok(Self {
+            matchers: DEFAULT_MATCHERS.to_vec(),
+            redacted_headers: vec![header::AUTHORIZATION],
+            time_scale: 1.0,
+            inner: Arc::new(CassetteInner {
+                path,
+                mode,
+                state: Mutex::new(state),
+                saving: Mutex::new(()),
+            }),
+        })
+    }
+
+    /// Sets what the requests are matched on when replaying, [`DEFAULT_MATCHERS`] by default.
+    ///
+    /// # Arguments
+    /// * `matchers` - The matchers.
+    pub fn with_matchers(mut self, matchers: impl Into<Vec<Matcher>>) -> Self {
+        self.matchers = matchers.into();
+        self
+    }
+
+    /// Redacts a request and response header from the cassette, in addition to `Authorization`.
+    ///
+    /// # Arguments
+    /// * `name` - The header name.
+    pub fn with_redacted_header(mut self, name: header::HeaderName) -> Self {
+        self.redacted_headers.push(name);
+        self
+    }
+
+    /// Scales the recorded timing when replaying, `1.0` by default, `0.0` replays without delay.
+    ///
+    /// # Arguments
+    /// * `time_scale` - The factor of the recorded delays.
+    pub fn with_time_scale(mut self, time_scale: f64) -> Self {
+        self.time_scale = time_scale.max(0.0);
+        self
+    }
+
+    /// Returns the path of the cassette file.
+    pub fn path(&self) -> &Path {
+        &self.inner.path
+    }
+
+    /// Returns the mode of the cassette, `Record` or `Replay`.
+    pub fn mode(&self) -> CassetteMode {
+        self.inner.mode
+    }
+
+    /// Returns the interactions recorded, or loaded for replaying.
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.lock().interactions.clone()
+    }
+
+    /// Saves the recorded interactions to the cassette file, replacing it.
+    /// Nothing is written if no interaction was recorded since the last save.
+    ///
+    /// # Returns
+    /// A `Result` indicating success or an error.
+    ///
+    /// # Errors
+    /// Returns an error if the cassette file cannot be written.
+    pub fn save(&self) -> AnyResult<()> {
+        self.inner.save()
+    }
+
+    /// Executes a request, sending and recording it, or replaying the recorded response.
+    ///
+    /// # Arguments
+    /// * `http_client` - The HTTP client sending the request when recording.
+    /// * `request` - The request.
+    ///
+    /// # Returns
+    /// A `Result` containing the response or an error.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails, or no recorded interaction matches the request
+    /// when replaying.
+    pub(crate) async fn execute(
+        &self,
+        http_client: &reqwest::Client,
+        mut request: Request,
+    ) -> AnyResult<Response> {
+        let recorded = self.record_request(&mut request).await?;
+        if self.inner.mode == CassetteMode::Replay {
+            return self.replay(&recorded).await;
+        }
+
+        let started = Instant::now();
+        let resp = http_client.execute(request).await?;
+        let mut response = RecordedResponse {
+            status: resp.status().as_u16(),
+            headers: self.record_headers(resp.headers()),
+            latency_ms: started.elapsed().as_millis() as u64,
+            body: None,
+            chunks: Vec::new(),
+        };
+        response.headers.remove(header::CONTENT_LENGTH.as_str());
+        response.headers.remove(header::TRANSFER_ENCODING.as_str());
+        let builder = response_builder(&response);
+        if !is_event_stream(resp.headers()) {
+            let bytes = resp.bytes().await?;
+            let json = response
+                .headers
+                .get("content-type")
+                .is_none_or(|t| t.contains("json"));
+            response.body = Some(RecordedBody::from_bytes(&bytes, json));
+            self.push(Interaction {
+                request: recorded,
+                response,
+            });
+            return Ok(builder.body(bytes)?.into());
+        }
+
+        // the interaction is recorded when the stream ends or is dropped
+        let mut recorder = StreamRecorder {
+            cassette: self.clone(),
+            interaction: Some(Interaction {
+                request: recorded,
+                response,
+            }),
+            last: Instant::now(),
+        };
+        let stream = resp.bytes_stream().map(move |chunk| {
+            if let Ok(bytes) = &chunk {
+                recorder.push(bytes);
+            }
+            chunk
+        });
+        Ok(builder.body(reqwest::Body::wrap_stream(stream))?.into())
+    }
+
+    /// Records a request, buffering a streaming (multipart) body to record it.
+    async fn record_request(&self, request: &mut Request) -> AnyResult<RecordedRequest> {
+        let body = match request.body_mut().take() {
+            Some(body) => {
+                let bytes = match body.as_bytes() {
+                    Some(bytes) => Bytes::copy_from_slice(bytes),
+                    None => body.collect().await?.to_bytes(),
+                };
+                *request.body_mut() = Some(bytes.clone().into());
+                Some(bytes)
+            }
+            None => None,
+        };
+        let mut headers = self.record_headers(request.headers());
+        let content_type = headers.get("content-type").cloned().unwrap_or_default();
+        let body = body.map(|bytes| match multipart_boundary(&content_type) {
+            Some(boundary) => {
+                headers.insert(
+                    "content-type".into(),
+                    content_type.replace(&boundary, MULTIPART_BOUNDARY),
+                );
+                let bytes =
+                    replace_bytes(&bytes, boundary.as_bytes(), MULTIPART_BOUNDARY.as_bytes());
+                RecordedBody::from_bytes(&bytes, false)
+            }
+            None => RecordedBody::from_bytes(&bytes, true),
+        });
+        let url = request.url();
+        Ok(RecordedRequest {
+            method: request.method().to_string(),
+            path: url.path().to_string(),
+            query: url.query().map(str::to_string),
+            headers,
+            body,
+        })
+    }
+
+    /// Records the headers, replacing the values of the redacted headers.
+    fn record_headers(&self, headers: &header::HeaderMap) -> BTreeMap<String, String> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = match self.redacted_headers.contains(name) {
+                    true => REDACTED.to_string(),
+                    false => String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                };
+                (name.to_string(), value)
+            })
+            .collect()
+    }
+
+    /// Replays the response of the first unused interaction matching the request,
+    /// or of the last matching interaction if all are used.
+    async fn replay(&self, request: &RecordedRequest) -> AnyResult<Response> {
+        let response = {
+            let mut state = self.lock();
+            let matching: Vec<usize> = (0..state.interactions.len())
+                .filter(|i| request.matches(&state.interactions[*i].request, &self.matchers))
+                .collect();
+            let Some(index) = matching
+                .iter()
+                .find(|i| !state.replayed[**i])
+                .or(matching.last())
+                .copied()
+            else {
+                bail!(
+                    "Cassette {} has no interaction for {} {}",
+                    self.inner.path.display(),
+                    request.method,
+                    request.path
+                );
+            };
+            state.replayed[index] = true;
+            state.interactions[index].response.clone()
+        };
+
+        tokio::time::sleep(self.scale(response.latency_ms)).await;
+        let builder = response_builder(&response);
+        if response.chunks.is_empty() {
+            let body = match &response.body {
+                Some(body) => body.to_bytes()?,
+                None => Bytes::new(),
+            };
+            return Ok(builder.body(body)?.into());
+        }
+        let chunks = response
+            .chunks
+            .iter()
+            .map(|chunk| Ok((self.scale(chunk.delay_ms), chunk.data.to_bytes()?)))
+            .collect::<AnyResult<Vec<_>>>()?;
+        let stream = stream::iter(chunks).then(|(delay, bytes)| async move {
+            tokio::time::sleep(delay).await;
+            Ok::<_, std::io::Error>(bytes)
+        });
+        Ok(builder.body(reqwest::Body::wrap_stream(stream))?.into())
+    }
+
+    /// Returns the recorded delay scaled by the time scale.
+    fn scale(&self, delay_ms: u64) -> Duration {
+        Duration::from_millis(delay_ms).mul_f64(self.time_scale)
+    }
+
+    /// Appends a recorded interaction, saved later.
+    fn push(&self, interaction: Interaction) {
+        let mut state = self.lock();
+        state.interactions.push(interaction);
+        state.replayed.push(true);
+        state.unsaved = true;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CassetteState> {
+        self.inner.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Records the chunks of a stream, and records the interaction when dropped.
+struct StreamRecorder {
+    cassette: Cassette,
+    interaction: Option<Interaction>,
+    last: Instant,
+}
+
+impl StreamRecorder {
+    fn push(&mut self, bytes: &Bytes) {
+        let now = Instant::now();
+        if let Some(interaction) = self.interaction.as_mut() {
+            interaction.response.chunks.push(RecordedChunk {
+                delay_ms: now.duration_since(self.last).as_millis() as u64,
+                data: RecordedBody::from_bytes(bytes, false),
+            });
+        }
+        self.last = now;
+    }
+}
+
+impl Drop for StreamRecorder {
+    fn drop(&mut self) {
+        if let Some(interaction) = self.interaction.take() {
+            self.cassette.push(interaction);
+        }
+    }
+}
+
+/// Returns a response builder with the status and the headers of a recorded response.
+fn response_builder(response: &RecordedResponse) -> http::response::Builder {
+    response.headers.iter().fold(
+        http::Response::builder().status(response.status),
+        |builder, (name, value)| builder.header(name.as_str(), value.as_str()),
+    )
+}
+
+/// Returns whether the response is a SSE stream.
+fn is_event_stream(headers: &header::HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"))
+}
+
+/// Returns the boundary of a multipart content type.
+fn multipart_boundary(content_type: &str) -> Option<String> {
+    if !content_type.starts_with("multipart/") {
+        return None;
+    }
+    content_type
+        .split(';')
+        .find_map(|param| param.trim().strip_prefix("boundary="))
+        .map(|boundary| boundary.trim_matches('"').to_string())
+        .filter(|boundary| !boundary.is_empty())
+}
+
+/// Replaces all the occurrences of a byte pattern.
+fn replace_bytes(bytes: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+    let mut replaced = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(from) {
+            replaced.extend_from_slice(to);
+            i += from.len();
+        } else {
+            replaced.push(bytes[i]);
+            i += 1;
+        }
+    }
+    replaced
+}
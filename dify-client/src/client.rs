@@ -4,6 +4,7 @@
 //! It also includes a `Config` struct that holds the configuration for the client.
 //! The client supports creating requests, executing them, and returning the response.
 //! Additionally, it provides methods for creating form requests and handling multipart data.
+//! The `ClientBuilder` struct allows customizing the underlying HTTP client.
 //!
 //! # Examples
 //!
@@ -29,9 +30,29 @@
 //!
 //! let client = Client::new_with_config(config);
 //! ```
+//!
+//! Creating a new client with the builder:
+//!
+//! ```rust
+//! use dify_client::client::Client;
+//! use std::time::Duration;
+//!
+//! let client = Client::builder()
+//!     .base_url("https://api.dify.ai")
+//!     .api_key("API_KEY")
+//!     .connect_timeout(Duration::from_secs(5))
+//!     .read_timeout(Duration::from_secs(120))
+//!     .user_agent("my-app/1.0")
+//!     .pool_max_idle_per_host(8)
+//!     .build()
+//!     .expect("Failed to create client");
+//! ```
+#[cfg(any(feature = "default-tls", feature = "rustls-tls"))]
+use super::http::Certificate;
 use super::{
     api::Api,
-    http::{header, multipart, Method, Request, Response},
+    cassette::Cassette,
+    http::{header, multipart, Method, Proxy, Request, Response},
 };
 use anyhow::{bail, Result as AnyResult};
 use std::{sync::Arc, time::Duration};
@@ -59,13 +80,244 @@ impl Default for Config {
     }
 }
 
+/// A builder for creating a `Client` with custom HTTP settings.
+///
+/// Unlike [`Client::new_with_config`], building the client never panics,
+/// invalid settings are reported through the returned `Result`.
+#[derive(Debug, Default)]
+pub struct ClientBuilder {
+    /// The configuration for the client.
+    config: Config,
+    /// The timeout for connecting to the server.
+    connect_timeout: Option<Duration>,
+    /// The timeout for reading the response.
+    read_timeout: Option<Duration>,
+    /// The proxies for the HTTP client.
+    proxies: Vec<Proxy>,
+    /// The additional root certificates for the HTTP client.
+    #[cfg(any(feature = "default-tls", feature = "rustls-tls"))]
+    root_certificates: Vec<Certificate>,
+    /// The `User-Agent` header for the requests.
+    user_agent: Option<String>,
+    /// The extra default headers for the requests.
+    default_headers: header::HeaderMap,
+    /// Whether to use HTTP/2 with prior knowledge.
+    http2_prior_knowledge: bool,
+    /// The maximum idle connections per host in the pool.
+    pool_max_idle_per_host: Option<usize>,
+    /// The timeout for idle connections in the pool.
+    pool_idle_timeout: Option<Duration>,
+    /// The prebuilt HTTP client.
+    http_client: Option<reqwest::Client>,
+    /// The cassette recording or replaying the requests.
+    cassette: Option<Cassette>,
+}
+
+impl ClientBuilder {
+    /// Creates a new `ClientBuilder` with the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new `ClientBuilder` with the specified configuration.
+    ///
+    /// # Arguments
+    /// * `config` - The configuration for the client.
+    pub fn with_config(config: Config) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the base URL of the Dify API.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.config.base_url = base_url.into();
+        self
+    }
+
+    /// Sets the API key for the Dify API.
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.config.api_key = api_key.into();
+        self
+    }
+
+    /// Sets the total timeout for the client requests, zero means no timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = timeout;
+        self
+    }
+
+    /// Sets the timeout for connecting to the server.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout for each read of the response.
+    /// The timeout is reset after each successful read, which suits streaming responses.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Adds a proxy for the HTTP client.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+
+    /// Adds a custom root certificate for the HTTP client, available with a TLS feature.
+    #[cfg(any(feature = "default-tls", feature = "rustls-tls"))]
+    pub fn add_root_certificate(mut self, cert: Certificate) -> Self {
+        self.root_certificates.push(cert);
+        self
+    }
+
+    /// Sets the `User-Agent` header for the requests.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Adds an extra default header for the requests.
+    pub fn default_header(mut self, name: header::HeaderName, value: header::HeaderValue) -> Self {
+        self.default_headers.insert(name, value);
+        self
+    }
+
+    /// Adds extra default headers for the requests.
+    pub fn default_headers(mut self, headers: header::HeaderMap) -> Self {
+        self.default_headers.extend(headers);
+        self
+    }
+
+    /// Uses HTTP/2 with prior knowledge, only for servers known to support it.
+    pub fn http2_prior_knowledge(mut self) -> Self {
+        self.http2_prior_knowledge = true;
+        self
+    }
+
+    /// Sets the maximum idle connections per host in the pool.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Sets the timeout for idle connections in the pool.
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Uses a prebuilt HTTP client.
+    /// The HTTP settings of the builder (connect and read timeouts, proxies, certificates, HTTP/2 and pool)
+    /// are ignored, the headers and the total timeout are still applied to each request.
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    /// Records the requests to a cassette, or replays them from it without network.
+    /// See the [`cassette`](crate::cassette) module.
+    pub fn cassette(mut self, cassette: Cassette) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+
+    /// Builds the `Client`.
+    ///
+    /// # Returns
+    /// A `Result` containing the client or an error.
+    ///
+    /// # Errors
+    /// Returns an error if the API key or user agent is not a valid header value,
+    /// or the HTTP client cannot be built.
+    pub fn build(self) -> AnyResult<Client> {
+        let mut c = self.config;
+        // format the base URL
+        c.base_url = c.base_url.trim_end_matches('/').into();
+
+        let mut headers = header::HeaderMap::new();
+        headers.insert(
+            header::CACHE_CONTROL,
+            header::HeaderValue::from_static("no-cache"),
+        );
+        headers.insert(
+            header::CONTENT_TYPE,
+            header::HeaderValue::from_static("application/json; charset=utf-8"),
+        );
+        if let Some(user_agent) = self.user_agent {
+            headers.insert(header::USER_AGENT, user_agent.parse()?);
+        }
+        headers.extend(self.default_headers);
+        headers.insert(header::AUTHORIZATION, bearer_auth(&c.api_key)?);
+
+        let http_client = match self.http_client {
+            Some(http_client) => http_client,
+            None => {
+                let mut builder = reqwest::ClientBuilder::new();
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(timeout) = self.read_timeout {
+                    builder = builder.read_timeout(timeout);
+                }
+                for proxy in self.proxies {
+                    builder = builder.proxy(proxy);
+                }
+                #[cfg(any(feature = "default-tls", feature = "rustls-tls"))]
+                for cert in self.root_certificates {
+                    builder = builder.add_root_certificate(cert);
+                }
+                if self.http2_prior_knowledge {
+                    builder = builder.http2_prior_knowledge();
+                }
+                if let Some(max) = self.pool_max_idle_per_host {
+                    builder = builder.pool_max_idle_per_host(max);
+                }
+                if let Some(timeout) = self.pool_idle_timeout {
+                    builder = builder.pool_idle_timeout(timeout);
+                }
+                builder.build()?
+            }
+        };
+
+        Ok(Client {
+            config: Arc::new(c),
+            headers: Arc::new(headers),
+            http_client,
+            cassette: self.cassette,
+        })
+    }
+}
+
+/// Returns the bearer authorization header value for the API key.
+///
+/// # Arguments
+/// * `api_key` - The API key for authentication.
+///
+/// # Returns
+/// A `Result` containing the header value or an error.
+pub(crate) fn bearer_auth(api_key: &str) -> AnyResult<header::HeaderValue> {
+    let auth = format!("Bearer {}", api_key);
+    let mut bearer_auth = header::HeaderValue::from_str(&auth)
+        .map_err(|_| anyhow::anyhow!("Config.ApiKey Illegal"))?;
+    bearer_auth.set_sensitive(true);
+    Ok(bearer_auth)
+}
+
 /// The `Client` struct represents a client for interacting with the Dify API.
 #[derive(Clone, Debug)]
 pub struct Client {
     /// The configuration for the client.
     pub config: Arc<Config>,
+    /// The headers sent with every request.
+    headers: Arc<header::HeaderMap>,
     /// The HTTP client for sending requests.
     http_client: reqwest::Client,
+    /// The cassette recording or replaying the requests.
+    cassette: Option<Cassette>,
 }
 
 /// The `Client` struct represents a client for interacting with the Dify API.
@@ -78,6 +330,9 @@ impl Client {
     ///
     /// # Returns
     /// A new `Client` instance.
+    ///
+    /// # Panics
+    /// Panics if the client cannot be built, use [`Client::builder`] to handle the error.
     pub fn new(base_url: &str, api_key: &str) -> Self {
         Self::new_with_config(Config {
             base_url: base_url.into(),
@@ -93,48 +348,44 @@ impl Client {
     ///
     /// # Returns
     /// A new `Client` instance.
-    pub fn new_with_config(mut c: Config) -> Self {
-        // format the base URL
-        c.base_url = c.base_url.trim_end_matches("/").into();
-        // build the http client
-        let mut builder = reqwest::ClientBuilder::new();
-        if !c.timeout.is_zero() {
-            builder = builder.timeout(c.timeout);
-        }
-        let http_client = builder
-            .default_headers(Self::default_headers(&c))
+    ///
+    /// # Panics
+    /// Panics if the client cannot be built, use [`Client::builder`] to handle the error.
+    pub fn new_with_config(c: Config) -> Self {
+        ClientBuilder::with_config(c)
             .build()
-            .expect("Failed to create http client");
+            .expect("Failed to create http client")
+    }
 
-        Self {
-            config: Arc::new(c),
-            http_client,
-        }
+    /// Returns a new `ClientBuilder` with the default configuration.
+    ///
+    /// # Returns
+    /// A new `ClientBuilder` instance.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
     }
 
-    /// Returns the default headers for the client.
+    /// Returns a new `Client` with the specified configuration,
+    /// sharing the HTTP connection pool and the headers of this client.
     ///
     /// # Arguments
-    /// * `c` - The configuration for the client.
+    /// * `config` - The configuration for the new client.
     ///
     /// # Returns
-    /// The default headers for the client.
-    fn default_headers(c: &Config) -> header::HeaderMap {
-        let mut headers = header::HeaderMap::new();
-        headers.insert(
-            header::CACHE_CONTROL,
-            header::HeaderValue::from_static("no-cache"),
-        );
-        headers.insert(
-            header::CONTENT_TYPE,
-            header::HeaderValue::from_static("application/json; charset=utf-8"),
-        );
-
-        let auth = format!("Bearer {}", c.api_key);
-        let mut bearer_auth = header::HeaderValue::from_str(&auth).unwrap();
-        bearer_auth.set_sensitive(true);
-        headers.insert(header::AUTHORIZATION, bearer_auth);
-        headers
+    /// A `Result` containing the new client or an error.
+    ///
+    /// # Errors
+    /// Returns an error if the API key is not a valid header value.
+    pub fn with_config(&self, mut config: Config) -> AnyResult<Client> {
+        config.base_url = config.base_url.trim_end_matches('/').into();
+        let mut headers = self.headers.as_ref().clone();
+        headers.insert(header::AUTHORIZATION, bearer_auth(&config.api_key)?);
+        Ok(Client {
+            config: Arc::new(config),
+            headers: Arc::new(headers),
+            http_client: self.http_client.clone(),
+            cassette: self.cassette.clone(),
+        })
     }
 
     /// Returns the API for the client.
@@ -170,21 +421,13 @@ impl Client {
     where
         T: serde::Serialize,
     {
-        match method {
-            Method::POST => {
-                let r = self.http_client.post(url).json(&data).build()?;
-                Ok(r)
-            }
-            Method::GET => {
-                let r = self.http_client.get(url).query(&data).build()?;
-                Ok(r)
-            }
-            Method::DELETE => {
-                let r = self.http_client.delete(url).json(&data).build()?;
-                Ok(r)
-            }
+        let builder = self.http_client.request(method.clone(), url);
+        let r = match method {
+            Method::POST | Method::PUT | Method::DELETE => builder.json(&data).build()?,
+            Method::GET => builder.query(&data).build()?,
             _ => bail!("Method not supported"),
-        }
+        };
+        Ok(self.prepare_request(r))
     }
 
     /// Creates a form request with the specified URL and data.
@@ -201,7 +444,26 @@ impl Client {
         form_data: multipart::Form,
     ) -> AnyResult<Request> {
         let r = self.http_client.post(url).multipart(form_data).build()?;
-        Ok(r)
+        Ok(self.prepare_request(r))
+    }
+
+    /// Applies the client headers and timeout to the request.
+    /// Headers already present in the request are kept as they are.
+    ///
+    /// # Arguments
+    /// * `request` - The request to prepare.
+    ///
+    /// # Returns
+    /// The request with the client headers and timeout applied.
+    fn prepare_request(&self, mut request: Request) -> Request {
+        let headers = request.headers_mut();
+        for (name, value) in self.headers.iter() {
+            headers.entry(name.clone()).or_insert_with(|| value.clone());
+        }
+        if !self.config.timeout.is_zero() {
+            *request.timeout_mut() = Some(self.config.timeout);
+        }
+        request
     }
 
     /// Executes the specified request and returns the response.
@@ -212,6 +474,9 @@ impl Client {
     /// # Returns
     /// A `Result` containing the response or an error.
     pub(crate) async fn execute(&self, request: Request) -> AnyResult<Response> {
-        self.http_client.execute(request).await.map_err(Into::into)
+        match &self.cassette {
+            Some(cassette) => cassette.execute(&self.http_client, request).await,
+            None => self.http_client.execute(request).await.map_err(Into::into),
+        }
     }
 }
@@ -0,0 +1,251 @@
+//! This module provides loading of the client configuration from the environment and config files.
+//!
+//! A config file describes one or more Dify apps, each with its own API key and optional base URL.
+//! The format is chosen by the file extension, `.toml` and `.json` are supported.
+//!
+//! # Examples
+//!
+//! A TOML config file with multiple apps:
+//!
+//! ```toml
+//! base_url = "https://api.dify.ai"
+//! timeout = 60
+//! default_app = "support-bot"
+//!
+//! [apps.support-bot]
+//! api_key = "app-xxxxxxxx"
+//!
+//! [apps.summary-workflow]
+//! api_key = "app-yyyyyyyy"
+//! base_url = "https://dify.example.com"
+//! timeout = 300
+//! ```
+//!
+//! Loading the configuration:
+//!
+//! ```no_run
+//! use dify_client::{config::ConfigFile, Client, Config};
+//!
+//! // DIFY_BASE_URL, DIFY_API_KEY and DIFY_TIMEOUT
+//! let config = Config::from_env().unwrap();
+//! // MY_APP_BASE_URL, MY_APP_API_KEY and MY_APP_TIMEOUT
+//! let config = Config::from_env_with_prefix("MY_APP").unwrap();
+//! // the default app of the config file
+//! let config = Config::from_file("dify.toml", None).unwrap();
+//! // a named app of the config file
+//! let config = Config::from_file("dify.toml", Some("summary-workflow")).unwrap();
+//! let client = Client::new_with_config(config);
+//!
+//! // all apps of the config file
+//! let file = ConfigFile::from_file("dify.toml").unwrap();
+//! for name in file.apps.keys() {
+//!     let config = file.app(name).unwrap();
+//!     println!("{}: {}", name, config.base_url);
+//! }
+//! ```
+use super::client::Config;
+use anyhow::{anyhow, bail, Result as AnyResult};
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DurationSeconds};
+use std::{collections::BTreeMap, env, path::Path, time::Duration};
+
+/// The default prefix of the environment variables.
+pub const DEFAULT_ENV_PREFIX: &str = "DIFY";
+
+/// A config file describing multiple Dify apps.
+#[serde_as]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ConfigFile {
+    /// The default base URL for all apps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// The default timeout for all apps, in seconds.
+    #[serde_as(as = "Option<DurationSeconds<u64>>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<Duration>,
+    /// The name of the app used when no app is specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_app: Option<String>,
+    /// The apps, keyed by app name.
+    #[serde(default)]
+    pub apps: BTreeMap<String, AppProfile>,
+}
+
+/// The profile of a Dify app in a config file.
+#[serde_as]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct AppProfile {
+    /// The API key of the app.
+    pub api_key: String,
+    /// The base URL of the app, overrides the default base URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// The timeout of the app in seconds, overrides the default timeout.
+    #[serde_as(as = "Option<DurationSeconds<u64>>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<Duration>,
+}
+
+/// The format of a config file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// Returns the config format for the file extension of the path.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the config file.
+    ///
+    /// # Returns
+    /// A `Result` containing the config format or an error.
+    pub fn from_path(path: &Path) -> AnyResult<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Ok(ConfigFormat::Toml),
+            Some("json") => Ok(ConfigFormat::Json),
+            _ => bail!("ConfigFile.Format Illegal: {}", path.display()),
+        }
+    }
+}
+
+impl ConfigFile {
+    /// Loads the config file from the specified path.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the config file, `.toml` or `.json`.
+    ///
+    /// # Returns
+    /// A `Result` containing the config file or an error.
+    pub fn from_file(path: impl AsRef<Path>) -> AnyResult<Self> {
+        let path = path.as_ref();
+        let format = ConfigFormat::from_path(path)?;
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read config file {}: {}", path.display(), e))?;
+        Self::parse(&text, format)
+    }
+
+    /// Parses the config file from a string.
+    ///
+    /// # Arguments
+    /// * `text` - The content of the config file.
+    /// * `format` - The format of the content.
+    ///
+    /// # Returns
+    /// A `Result` containing the config file or an error.
+    pub fn parse(text: &str, format: ConfigFormat) -> AnyResult<Self> {
+        let file = match format {
+            ConfigFormat::Toml => toml::from_str(text)?,
+            ConfigFormat::Json => serde_json::from_str(text)?,
+        };
+        Ok(file)
+    }
+
+    /// Returns the name of the default app.
+    /// The default app is `default_app`, or the only app if the file has exactly one.
+    ///
+    /// # Returns
+    /// A `Result` containing the app name or an error.
+    pub fn default_app_name(&self) -> AnyResult<&str> {
+        if let Some(name) = self.default_app.as_deref() {
+            return Ok(name);
+        }
+        let mut names = self.apps.keys();
+        match (names.next(), names.next()) {
+            (Some(name), None) => Ok(name),
+            _ => bail!("ConfigFile.DefaultApp Illegal"),
+        }
+    }
+
+    /// Returns the client configuration of the named app.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the app.
+    ///
+    /// # Returns
+    /// A `Result` containing the configuration or an error.
+    pub fn app(&self, name: &str) -> AnyResult<Config> {
+        let profile = self
+            .apps
+            .get(name)
+            .ok_or_else(|| anyhow!("ConfigFile.Apps {} not found", name))?;
+        let default = Config::default();
+        Ok(Config {
+            base_url: profile
+                .base_url
+                .clone()
+                .or_else(|| self.base_url.clone())
+                .unwrap_or(default.base_url),
+            api_key: profile.api_key.clone(),
+            timeout: profile.timeout.or(self.timeout).unwrap_or(default.timeout),
+        })
+    }
+
+    /// Returns the client configuration of the default app.
+    ///
+    /// # Returns
+    /// A `Result` containing the configuration or an error.
+    pub fn default_config(&self) -> AnyResult<Config> {
+        self.app(self.default_app_name()?)
+    }
+}
+
+/// Loads the client configuration from the environment and config files.
+impl Config {
+    /// Loads the configuration from the `DIFY_BASE_URL`, `DIFY_API_KEY` and `DIFY_TIMEOUT`
+    /// environment variables.
+    ///
+    /// # Returns
+    /// A `Result` containing the configuration or an error.
+    pub fn from_env() -> AnyResult<Self> {
+        Self::from_env_with_prefix(DEFAULT_ENV_PREFIX)
+    }
+
+    /// Loads the configuration from the `{prefix}_BASE_URL`, `{prefix}_API_KEY` and
+    /// `{prefix}_TIMEOUT` environment variables.
+    /// The API key is required, the base URL and the timeout (in seconds) are optional.
+    ///
+    /// # Arguments
+    /// * `prefix` - The prefix of the environment variables.
+    ///
+    /// # Returns
+    /// A `Result` containing the configuration or an error.
+    pub fn from_env_with_prefix(prefix: &str) -> AnyResult<Self> {
+        let var = |name: &str| env::var(format!("{}_{}", prefix, name)).ok();
+        let default = Config::default();
+
+        let api_key = var("API_KEY").ok_or_else(|| anyhow!("{}_API_KEY is not set", prefix))?;
+        let base_url = var("BASE_URL").unwrap_or(default.base_url);
+        let timeout = match var("TIMEOUT") {
+            Some(secs) => Duration::from_secs(
+                secs.trim()
+                    .parse()
+                    .map_err(|_| anyhow!("{}_TIMEOUT Illegal", prefix))?,
+            ),
+            None => default.timeout,
+        };
+
+        Ok(Config {
+            base_url,
+            api_key,
+            timeout,
+        })
+    }
+
+    /// Loads the configuration of an app from a config file.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the config file, `.toml` or `.json`.
+    /// * `app` - The name of the app, the default app of the file if `None`.
+    ///
+    /// # Returns
+    /// A `Result` containing the configuration or an error.
+    pub fn from_file(path: impl AsRef<Path>, app: Option<&str>) -> AnyResult<Self> {
+        let file = ConfigFile::from_file(path)?;
+        match app {
+            Some(name) => file.app(name),
+            None => file.default_config(),
+        }
+    }
+}
@@ -0,0 +1,325 @@
+//! This module provides a handle for multi-turn chats.
+//!
+//! A [`Conversation`] is bound to a user and the inputs of the app. It starts without an ID,
+//! remembers the conversation ID returned by the first message, blocking or streaming,
+//! and sends the following messages in the same conversation.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use dify_client::Client;
+//! use futures::StreamExt;
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let client = Client::new("https://api.dify.ai", "API_KEY");
+//!     let conversation = client.api().conversation("afa");
+//!
+//!     let response = conversation.send("hello").await?;
+//!     println!("{}", response.answer);
+//!
+//!     let mut stream = conversation.send_stream("tell me more").await?;
+//!     while let Some(event) = stream.next().await {
+//!         println!("{:?}", event?);
+//!     }
+//!
+//!     for message in conversation.history().await? {
+//!         println!("{}: {}", message.query, message.answer);
+//!     }
+//!     conversation.rename(Some("greetings")).await?;
+//!     Ok(())
+//! }
+//! ```
+use super::{
+    api::Api,
+    request::{
+        ChatMessagesRequest, ConversationsDeleteRequest, ConversationsRenameRequest, Feedback,
+        MessagesFeedbacksRequest, MessagesRequest, MessagesSuggestedRequest,
+    },
+    response::{ChatMessagesResponse, MessageData, ResultResponse, SseMessageEvent},
+};
+use anyhow::{anyhow, Result as AnyResult};
+use futures::stream::{BoxStream, StreamExt};
+use serde_json::Value as JsonValue;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+/// A stream of chat message events.
+pub type SseMessageEventBoxStream = BoxStream<'static, AnyResult<SseMessageEvent>>;
+
+/// A multi-turn chat bound to a user, remembering the conversation ID.
+///
+/// Clones of a conversation share the conversation ID.
+#[derive(Clone)]
+pub struct Conversation {
+    api: Api,
+    user: String,
+    inputs: HashMap<String, JsonValue>,
+    id: Arc<RwLock<Option<String>>>,
+}
+
+impl Conversation {
+    /// Creates a new conversation of the user, started by the first message.
+    ///
+    /// # Arguments
+    /// * `api` - The API of the app.
+    /// * `user` - The user of the conversation.
+    pub fn new(api: Api, user: impl Into<String>) -> Self {
+        Self {
+            api,
+            user: user.into(),
+            inputs: HashMap::new(),
+            id: Arc::default(),
+        }
+    }
+
+    /// Sets the inputs sent with the messages.
+    ///
+    /// # Arguments
+    /// * `inputs` - The inputs of the app.
+    pub fn with_inputs(mut self, inputs: HashMap<String, JsonValue>) -> Self {
+        self.inputs = inputs;
+        self
+    }
+
+    /// Continues an existing conversation.
+    ///
+    /// # Arguments
+    /// * `id` - The conversation ID.
+    pub fn with_id(self, id: impl Into<String>) -> Self {
+        self.set_id(id.into());
+        self
+    }
+
+    /// Returns the conversation ID, `None` before the first message.
+    pub fn id(&self) -> Option<String> {
+        self.id.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Returns the user of the conversation.
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    /// Returns the inputs of the conversation.
+    pub fn inputs(&self) -> &HashMap<String, JsonValue> {
+        &self.inputs
+    }
+
+    /// Sets the conversation ID.
+    fn set_id(&self, id: String) {
+        *self.id.write().unwrap_or_else(|e| e.into_inner()) = Some(id);
+    }
+
+    /// Returns the conversation ID or an error before the first message.
+    fn require_id(&self) -> AnyResult<String> {
+        self.id().ok_or_else(|| anyhow!("Conversation.ID Illegal"))
+    }
+
+    /// Fills the user, the inputs and the conversation ID of the request.
+    fn prepare(&self, mut req_data: ChatMessagesRequest) -> ChatMessagesRequest {
+        req_data.user.clone_from(&self.user);
+        for (key, value) in &self.inputs {
+            req_data
+                .inputs
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+        if let Some(id) = self.id() {
+            req_data.conversation_id = id;
+        }
+        req_data
+    }
+
+    /// Sends a message in the conversation.
+    ///
+    /// # Arguments
+    /// * `query` - The user query.
+    ///
+    /// # Returns
+    /// A `Result` containing the chat message response or an error.
+    pub async fn send(&self, query: &str) -> AnyResult<ChatMessagesResponse> {
+        self.send_request(ChatMessagesRequest {
+            query: query.into(),
+            auto_generate_name: true,
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Sends a message request in the conversation.
+    /// The user, the conversation ID and the inputs missing from the request are filled.
+    ///
+    /// # Arguments
+    /// * `req_data` - The chat message request data.
+    ///
+    /// # Returns
+    /// A `Result` containing the chat message response or an error.
+    pub async fn send_request(
+        &self,
+        req_data: ChatMessagesRequest,
+    ) -> AnyResult<ChatMessagesResponse> {
+        let resp = self.api.chat_messages(self.prepare(req_data)).await?;
+        if let Some(id) = resp.base.conversation_id.clone() {
+            self.set_id(id);
+        }
+        Ok(resp)
+    }
+
+    /// Sends a message in the conversation and returns the response as a stream.
+    /// The conversation ID is remembered from the first event carrying it.
+    ///
+    /// # Arguments
+    /// * `query` - The user query.
+    ///
+    /// # Returns
+    /// A `Result` containing the message event stream or an error.
+    pub async fn send_stream(&self, query: &str) -> AnyResult<SseMessageEventBoxStream> {
+        self.send_request_stream(ChatMessagesRequest {
+            query: query.into(),
+            auto_generate_name: true,
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Sends a message request in the conversation and returns the response as a stream.
+    /// The user, the conversation ID and the inputs missing from the request are filled.
+    ///
+    /// # Arguments
+    /// * `req_data` - The chat message request data.
+    ///
+    /// # Returns
+    /// A `Result` containing the message event stream or an error.
+    pub async fn send_request_stream(
+        &self,
+        req_data: ChatMessagesRequest,
+    ) -> AnyResult<SseMessageEventBoxStream> {
+        let stream = self
+            .api
+            .chat_messages_stream(self.prepare(req_data))
+            .await?;
+        let conversation = self.clone();
+        let stream = stream.inspect(move |event| {
+            let id = event
+                .as_ref()
+                .ok()
+                .and_then(SseMessageEvent::base)
+                .and_then(|base| base.conversation_id.as_ref());
+            if let Some(id) = id.filter(|id| !id.is_empty()) {
+                if conversation.id().as_ref() != Some(id) {
+                    conversation.set_id(id.clone());
+                }
+            }
+        });
+        Ok(stream.boxed())
+    }
+
+    /// Returns all messages of the conversation in chronological order,
+    /// empty before the first message.
+    ///
+    /// # Returns
+    /// A `Result` containing the messages or an error.
+    pub async fn history(&self) -> AnyResult<Vec<MessageData>> {
+        let Some(id) = self.id() else {
+            return Ok(Vec::new());
+        };
+        let mut messages = self
+            .api
+            .messages_iter_with(MessagesRequest {
+                conversation_id: id,
+                user: self.user.clone(),
+                ..Default::default()
+            })
+            .collect_all()
+            .await?;
+        messages.reverse();
+        Ok(messages)
+    }
+
+    /// Renames the conversation.
+    ///
+    /// # Arguments
+    /// * `name` - The new name, generated automatically if `None`.
+    ///
+    /// # Returns
+    /// A `Result` containing the result response or an error.
+    pub async fn rename(&self, name: Option<&str>) -> AnyResult<ResultResponse> {
+        self.api
+            .conversations_renaming(ConversationsRenameRequest {
+                conversation_id: self.require_id()?,
+                name: name.map(Into::into),
+                auto_generate: name.is_none(),
+                user: self.user.clone(),
+            })
+            .await
+    }
+
+    /// Deletes the conversation.
+    /// The next message starts a new conversation.
+    ///
+    /// # Returns
+    /// A `Result` indicating success or an error.
+    pub async fn delete(&self) -> AnyResult<()> {
+        self.api
+            .conversations_delete(ConversationsDeleteRequest {
+                conversation_id: self.require_id()?,
+                user: self.user.clone(),
+            })
+            .await?;
+        *self.id.write().unwrap_or_else(|e| e.into_inner()) = None;
+        Ok(())
+    }
+
+    /// Returns the suggested questions after a message of the conversation.
+    ///
+    /// # Arguments
+    /// * `message_id` - The message ID.
+    ///
+    /// # Returns
+    /// A `Result` containing the suggested questions or an error.
+    pub async fn suggested(&self, message_id: &str) -> AnyResult<Vec<String>> {
+        let resp = self
+            .api
+            .messages_suggested(MessagesSuggestedRequest {
+                message_id: message_id.into(),
+            })
+            .await?;
+        Ok(resp.data)
+    }
+
+    /// Sends the feedback of a message of the conversation.
+    ///
+    /// # Arguments
+    /// * `message_id` - The message ID.
+    /// * `rating` - The rating, `None` to revoke the rating.
+    ///
+    /// # Returns
+    /// A `Result` containing the result response or an error.
+    pub async fn feedback(
+        &self,
+        message_id: &str,
+        rating: Option<Feedback>,
+    ) -> AnyResult<ResultResponse> {
+        self.api
+            .messages_feedbacks(MessagesFeedbacksRequest {
+                message_id: message_id.into(),
+                rating,
+                user: self.user.clone(),
+            })
+            .await
+    }
+}
+
+/// Conversations
+impl Api {
+    /// Creates a new conversation of the user with this API.
+    ///
+    /// # Arguments
+    /// * `user` - The user of the conversation.
+    pub fn conversation(&self, user: impl Into<String>) -> Conversation {
+        Conversation::new(self.clone(), user)
+    }
+}
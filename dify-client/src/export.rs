@@ -0,0 +1,435 @@
+//! This module provides archiving of the chat histories of users.
+//!
+//! The [`ConversationExporter`] walks all conversations of each user and all messages of each
+//! conversation through the `conversations` and `messages` endpoints, and appends them to one
+//! transcript file per user, `<user>-<hash>.jsonl` or `<user>-<hash>.md`, in the output directory.
+//! The transcripts include the feedback, the message files and the retriever resources.
+//!
+//! With a checkpoint file, the progress is saved after each conversation, and an interrupted
+//! export resumes where it stopped: exported conversations are skipped and a partially written
+//! transcript is truncated to the last completed conversation.
+//!
+//! The JSONL transcripts can be read back with [`read_jsonl`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use dify_client::{
+//!     export::{ConversationExporter, ExportFormat},
+//!     Client,
+//! };
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let client = Client::new("https://api.dify.ai", "API_KEY");
+//!     let exporter = ConversationExporter::new(client.api(), "archive", ExportFormat::Jsonl)
+//!         .with_checkpoint("archive/checkpoint.json");
+//!     let summary = exporter.export_users(&["afa", "bob"]).await?;
+//!     println!("{:?}", summary);
+//!     Ok(())
+//! }
+//! ```
+use super::{
+    api::Api,
+    pagination::DEFAULT_PAGE_SIZE,
+    request::{ConversationsRequest, MessagesRequest},
+    response::{ConversationData, MessageData},
+    util::stable_hash,
+};
+use anyhow::{anyhow, Result as AnyResult};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as FmtWrite,
+    fs::{self, File},
+    io::{BufRead, BufReader, SeekFrom},
+    path::{Path, PathBuf},
+};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+/// The format of the exported transcripts.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ExportFormat {
+    /// One JSON line per conversation, an [`ExportedConversation`].
+    #[default]
+    Jsonl,
+    /// A human readable Markdown transcript.
+    Markdown,
+}
+
+impl ExportFormat {
+    /// Returns the file extension of the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// An exported conversation with all its messages.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ExportedConversation {
+    /// The user of the conversation.
+    pub user: String,
+    /// The conversation.
+    pub conversation: ConversationData,
+    /// The messages of the conversation, in chronological order.
+    pub messages: Vec<MessageData>,
+}
+
+/// The export progress of a user.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct UserProgress {
+    /// Whether all conversations of the user are exported.
+    pub completed: bool,
+    /// The IDs of the exported conversations.
+    pub conversations: BTreeSet<String>,
+    /// The length of the transcript after the last exported conversation.
+    pub offset: u64,
+}
+
+/// The checkpoint of an export, keyed by user.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ExportCheckpoint {
+    /// The progress of each user.
+    pub users: BTreeMap<String, UserProgress>,
+}
+
+impl ExportCheckpoint {
+    /// Loads the checkpoint from a file, an empty checkpoint if the file does not exist.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the checkpoint file.
+    ///
+    /// # Returns
+    /// A `Result` containing the checkpoint or an error.
+    pub fn load(path: impl AsRef<Path>) -> AnyResult<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path)?;
+        serde_json::from_str(&text)
+            .map_err(|e| anyhow!("Checkpoint {} Illegal: {}", path.display(), e))
+    }
+
+    /// Saves the checkpoint to a file, atomically replacing the previous checkpoint.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the checkpoint file.
+    ///
+    /// # Returns
+    /// A `Result` indicating success or an error.
+    pub fn save(&self, path: impl AsRef<Path>) -> AnyResult<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Loads the checkpoint from a file without blocking, like [`ExportCheckpoint::load`].
+    async fn load_async(path: &Path) -> AnyResult<Self> {
+        let text = match tokio::fs::read_to_string(path).await {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&text)
+            .map_err(|e| anyhow!("Checkpoint {} Illegal: {}", path.display(), e))
+    }
+
+    /// Saves the checkpoint to a file without blocking, like [`ExportCheckpoint::save`].
+    async fn save_async(&self, path: &Path) -> AnyResult<()> {
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(self)?).await?;
+        tokio::fs::rename(&tmp, path).await?;
+        Ok(())
+    }
+}
+
+/// The summary of an export.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportSummary {
+    /// The number of exported users.
+    pub users: usize,
+    /// The number of exported conversations.
+    pub conversations: usize,
+    /// The number of exported messages.
+    pub messages: usize,
+    /// The number of conversations skipped because they were exported before.
+    pub skipped: usize,
+}
+
+/// Exports the chat histories of users to transcript files.
+#[derive(Clone)]
+pub struct ConversationExporter {
+    api: Api,
+    dir: PathBuf,
+    format: ExportFormat,
+    checkpoint: Option<PathBuf>,
+    page_size: u32,
+}
+
+impl ConversationExporter {
+    /// Creates a new `ConversationExporter`.
+    ///
+    /// # Arguments
+    /// * `api` - The API of the app.
+    /// * `dir` - The output directory of the transcripts.
+    /// * `format` - The format of the transcripts.
+    pub fn new(api: Api, dir: impl Into<PathBuf>, format: ExportFormat) -> Self {
+        Self {
+            api,
+            dir: dir.into(),
+            format,
+            checkpoint: None,
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    /// Sets the checkpoint file, making the export resumable.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the checkpoint file.
+    pub fn with_checkpoint(mut self, path: impl Into<PathBuf>) -> Self {
+        self.checkpoint = Some(path.into());
+        self
+    }
+
+    /// Sets the number of conversations and messages fetched per page.
+    ///
+    /// # Arguments
+    /// * `page_size` - The page size.
+    pub fn with_page_size(mut self, page_size: u32) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Returns the transcript path of a user.
+    /// The file name is the user with unsafe characters replaced by `_`, followed by a short
+    /// hash of the user, so users differing only in those characters get different files.
+    ///
+    /// # Arguments
+    /// * `user` - The user.
+    pub fn transcript_path(&self, user: &str) -> PathBuf {
+        let name = user
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' | '@' => c,
+                _ => '_',
+            })
+            .collect::<String>();
+        let hash = stable_hash(user.as_bytes()) as u32;
+        self.dir
+            .join(format!("{}-{:08x}.{}", name, hash, self.format.extension()))
+    }
+
+    /// Exports the chat histories of the users.
+    ///
+    /// # Arguments
+    /// * `users` - The users.
+    ///
+    /// # Returns
+    /// A `Result` containing the summary of the export or an error.
+    pub async fn export_users<S: AsRef<str>>(&self, users: &[S]) -> AnyResult<ExportSummary> {
+        let mut summary = ExportSummary::default();
+        for user in users {
+            let user_summary = self.export_user(user.as_ref()).await?;
+            summary.users += user_summary.users;
+            summary.conversations += user_summary.conversations;
+            summary.messages += user_summary.messages;
+            summary.skipped += user_summary.skipped;
+        }
+        Ok(summary)
+    }
+
+    /// Exports the chat history of a user.
+    /// Without a checkpoint, the transcript of the user is overwritten.
+    ///
+    /// # Arguments
+    /// * `user` - The user.
+    ///
+    /// # Returns
+    /// A `Result` containing the summary of the export or an error.
+    pub async fn export_user(&self, user: &str) -> AnyResult<ExportSummary> {
+        let mut checkpoint = match self.checkpoint.as_ref() {
+            Some(path) => ExportCheckpoint::load_async(path).await?,
+            None => ExportCheckpoint::default(),
+        };
+        let mut progress = checkpoint.users.get(user).cloned().unwrap_or_default();
+        let mut summary = ExportSummary::default();
+        if progress.completed {
+            summary.skipped = progress.conversations.len();
+            return Ok(summary);
+        }
+
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(self.transcript_path(user))
+            .await?;
+        // drop what was written after the last checkpoint
+        file.set_len(progress.offset).await?;
+        file.seek(SeekFrom::End(0)).await?;
+
+        let mut conversations = self.api.conversations_iter_with(ConversationsRequest {
+            user: user.into(),
+            limit: Some(self.page_size),
+            ..Default::default()
+        });
+        while let Some(conversation) = conversations.next().await {
+            let conversation = conversation?;
+            if progress.conversations.contains(&conversation.id) {
+                summary.skipped += 1;
+                continue;
+            }
+            let mut messages = self
+                .api
+                .messages_iter_with(MessagesRequest {
+                    conversation_id: conversation.id.clone(),
+                    user: user.into(),
+                    limit: Some(self.page_size),
+                    ..Default::default()
+                })
+                .collect_all()
+                .await?;
+            messages.reverse();
+
+            summary.conversations += 1;
+            summary.messages += messages.len();
+            let exported = ExportedConversation {
+                user: user.into(),
+                conversation,
+                messages,
+            };
+            file.write_all(self.render(&exported)?.as_bytes()).await?;
+            file.sync_data().await?;
+
+            progress.offset = file.stream_position().await?;
+            progress.conversations.insert(exported.conversation.id);
+            self.save_progress(&mut checkpoint, user, &progress).await?;
+        }
+
+        progress.completed = true;
+        self.save_progress(&mut checkpoint, user, &progress).await?;
+        summary.users = 1;
+        Ok(summary)
+    }
+
+    /// Saves the progress of a user to the checkpoint file, if any.
+    async fn save_progress(
+        &self,
+        checkpoint: &mut ExportCheckpoint,
+        user: &str,
+        progress: &UserProgress,
+    ) -> AnyResult<()> {
+        let Some(path) = self.checkpoint.as_ref() else {
+            return Ok(());
+        };
+        checkpoint.users.insert(user.into(), progress.clone());
+        checkpoint.save_async(path).await
+    }
+
+    /// Renders an exported conversation in the format of the exporter.
+    fn render(&self, exported: &ExportedConversation) -> AnyResult<String> {
+        match self.format {
+            ExportFormat::Jsonl => Ok(serde_json::to_string(exported)? + "\n"),
+            ExportFormat::Markdown => Ok(render_markdown(exported)),
+        }
+    }
+}
+
+/// Renders an exported conversation as a Markdown transcript.
+///
+/// # Arguments
+/// * `exported` - The exported conversation.
+///
+/// # Returns
+/// The Markdown transcript.
+pub fn render_markdown(exported: &ExportedConversation) -> String {
+    let conversation = &exported.conversation;
+    let mut md = String::new();
+    let _ = writeln!(md, "# {}\n", conversation.name);
+    let _ = writeln!(md, "- Conversation ID: `{}`", conversation.id);
+    let _ = writeln!(md, "- User: `{}`", exported.user);
+    let _ = writeln!(md, "- Created at: {}", conversation.created_at);
+    if !conversation.inputs.is_empty() {
+        let inputs = serde_json::to_string(&conversation.inputs).unwrap_or_default();
+        let _ = writeln!(md, "- Inputs: `{}`", inputs);
+    }
+    if !conversation.introduction.is_empty() {
+        let _ = writeln!(md, "\n> {}", conversation.introduction);
+    }
+
+    for message in &exported.messages {
+        let _ = writeln!(md, "\n## Message `{}`\n", message.id);
+        let _ = writeln!(md, "- Created at: {}", message.created_at);
+        if let Some(feedback) = message.feedback.as_ref() {
+            let rating = serde_json::to_value(&feedback.rating).unwrap_or_default();
+            let _ = writeln!(md, "- Feedback: {}", rating.as_str().unwrap_or_default());
+        }
+        let _ = writeln!(md, "\n**User:**\n\n{}\n", message.query);
+        let _ = writeln!(md, "**Assistant:**\n\n{}", message.answer);
+        if !message.message_files.is_empty() {
+            let _ = writeln!(md, "\n**Files:**\n");
+            for file in &message.message_files {
+                let type_ = serde_json::to_value(&file.type_).unwrap_or_default();
+                let belongs_to = serde_json::to_value(&file.belongs_to).unwrap_or_default();
+                let _ = writeln!(
+                    md,
+                    "- [{} ({})]({})",
+                    type_.as_str().unwrap_or_default(),
+                    belongs_to.as_str().unwrap_or_default(),
+                    file.url
+                );
+            }
+        }
+        if !message.retriever_resources.is_empty() {
+            let _ = writeln!(md, "\n**Sources:**\n");
+            for resource in &message.retriever_resources {
+                let _ = writeln!(md, "- {}", resource_title(resource));
+            }
+        }
+    }
+    md.push_str("\n---\n\n");
+    md
+}
+
+/// Returns the title of a retriever resource, the dataset and document names if available.
+fn resource_title(resource: &JsonValue) -> String {
+    let field = |name: &str| resource.get(name).and_then(JsonValue::as_str);
+    match (field("dataset_name"), field("document_name")) {
+        (Some(dataset), Some(document)) => format!("{} / {}", dataset, document),
+        (None, Some(document)) => document.to_string(),
+        _ => resource.to_string(),
+    }
+}
+
+/// Reads the exported conversations from a JSONL transcript.
+///
+/// # Arguments
+/// * `path` - The path of the transcript.
+///
+/// # Returns
+/// A `Result` containing the exported conversations or an error.
+pub fn read_jsonl(path: impl AsRef<Path>) -> AnyResult<Vec<ExportedConversation>> {
+    let path = path.as_ref();
+    let reader = BufReader::new(File::open(path)?);
+    let mut conversations = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let conversation = serde_json::from_str(&line)
+            .map_err(|e| anyhow!("{}:{} Illegal: {}", path.display(), i + 1, e))?;
+        conversations.push(conversation);
+    }
+    Ok(conversations)
+}
@@ -0,0 +1,932 @@
+//! This module provides a regression test harness for the prompts of an app.
+//!
+//! A [`TestSuite`] is declared in YAML: the app mode, shared inputs and assertions, and the test
+//! cases with their query, inputs and assertions. The [`TestRunner`] runs each case against a
+//! chat, completion or workflow app through the [`Api`], checks the assertions, diffs the answers
+//! against a stored baseline, and produces a [`TestReport`] which can be written as JUnit XML
+//! for CI systems or as JSON.
+//!
+//! # Suite file
+//!
+//! ```yaml
+//! name: support-bot
+//! # completion, workflow or one of the chat modes, detected with `info()` if omitted
+//! app: advanced-chat
+//! # the answers of the previous run, relative to the suite file
+//! baseline: support-bot.baseline.json
+//! inputs:
+//!   language: en
+//! assert:
+//!   - max_latency_ms: 20000
+//! cases:
+//!   - name: greeting
+//!     query: Hello
+//!     assert:
+//!       - contains: Hello
+//!       - not_contains: sorry
+//!       - regex: "(?i)how can i help"
+//!   - name: classify ticket
+//!     query: My card was charged twice
+//!     inputs:
+//!       output_format: json
+//!     assert:
+//!       - json_path: { path: "$.category", equals: billing }
+//!       - max_tokens: 800
+//! ```
+//!
+//! Text assertions check the answer, or the text output of a workflow (the outputs as JSON if the
+//! workflow has several outputs). JSON paths are evaluated against the outputs of a workflow, or
+//! against the answer parsed as JSON.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use dify_client::{harness::{BaselineMode, TestRunner}, Client};
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let client = Client::new("https://api.dify.ai", "API_KEY");
+//!     let report = TestRunner::new(client.api())
+//!         .with_baseline_mode(BaselineMode::Report)
+//!         .run_file("tests/support-bot.yaml")
+//!         .await?;
+//!     report.write_junit("target/support-bot.xml")?;
+//!     assert!(report.is_success(), "{} cases failed", report.failed + report.errors);
+//!     Ok(())
+//! }
+//! ```
+use super::{
+    api::Api,
+    request::{ChatMessagesRequest, CompletionMessagesRequest, InfoRequest, WorkflowsRunRequest},
+    response::{AppMode, FinishedStatus, SseMessageEvent},
+};
+use anyhow::{anyhow, bail, Result as AnyResult};
+use futures::{stream, StreamExt};
+use regex::Regex;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value as JsonValue;
+use similar::TextDiff;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+/// The default user of the test requests.
+pub const DEFAULT_TEST_USER: &str = "dify-test";
+
+/// An assertion on the result of a test case.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Assertion {
+    /// The answer contains the text.
+    Contains(String),
+    /// The answer does not contain the text.
+    NotContains(String),
+    /// The answer is the text, ignoring the leading and trailing whitespace.
+    Equals(String),
+    /// The answer matches the regular expression.
+    Regex(Pattern),
+    /// The value at the JSON path equals the expected value.
+    JsonPath {
+        /// The JSON path, such as `$.items[0].name`.
+        path: String,
+        /// The expected value.
+        equals: JsonValue,
+    },
+    /// The latency of the request is at most the milliseconds.
+    MaxLatencyMs(u64),
+    /// The total tokens used are at most the number.
+    MaxTokens(u64),
+}
+
+/// A regular expression, compiled once when the suite is loaded.
+#[derive(Debug, Clone)]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    /// Compiles a regular expression.
+    ///
+    /// # Arguments
+    /// * `pattern` - The regular expression.
+    ///
+    /// # Returns
+    /// A `Result` containing the pattern or an error if the regular expression is illegal.
+    pub fn new(pattern: &str) -> AnyResult<Self> {
+        Regex::new(pattern)
+            .map(Self)
+            .map_err(|e| anyhow!("Assertion.Regex Illegal: {}", e))
+    }
+
+    /// Returns the regular expression.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Self::new(&pattern).map_err(D::Error::custom)
+    }
+}
+
+impl Assertion {
+    /// Checks that the JSON path of the assertion is legal,
+    /// the regular expressions are checked when parsed.
+    fn validate(&self) -> AnyResult<()> {
+        if let Assertion::JsonPath { path, .. } = self {
+            parse_path(path)?;
+        }
+        Ok(())
+    }
+
+    /// Checks the assertion, returning the failure message if it does not hold.
+    fn check(&self, output: &CaseOutput) -> Option<String> {
+        match self {
+            Assertion::Contains(text) => (!output.answer.contains(text.as_str()))
+                .then(|| format!("expected the answer to contain {:?}", text)),
+            Assertion::NotContains(text) => output
+                .answer
+                .contains(text.as_str())
+                .then(|| format!("expected the answer not to contain {:?}", text)),
+            Assertion::Equals(text) => (output.answer.trim() != text.trim())
+                .then(|| format!("expected the answer to be {:?}", text)),
+            Assertion::Regex(pattern) => (!pattern.0.is_match(&output.answer))
+                .then(|| format!("expected the answer to match /{}/", pattern.as_str())),
+            Assertion::JsonPath { path, equals } => match json_path(&output.document(), path) {
+                Ok(Some(value)) if value == *equals => None,
+                Ok(Some(value)) => Some(format!(
+                    "expected {} to equal {}, got {}",
+                    path, equals, value
+                )),
+                Ok(None) => Some(format!(
+                    "expected {} to equal {}, got nothing",
+                    path, equals
+                )),
+                Err(e) => Some(e.to_string()),
+            },
+            Assertion::MaxLatencyMs(max) => (output.latency_ms > *max).then(|| {
+                format!(
+                    "expected a latency of at most {}ms, got {}ms",
+                    max, output.latency_ms
+                )
+            }),
+            Assertion::MaxTokens(max) => match output.total_tokens {
+                Some(tokens) if tokens > *max => {
+                    Some(format!("expected at most {} tokens, got {}", max, tokens))
+                }
+                Some(_) => None,
+                None => Some(format!("expected at most {} tokens, got no usage", max)),
+            },
+        }
+    }
+}
+
+/// A test case of a suite.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TestCase {
+    /// The name of the case, unique in the suite.
+    pub name: String,
+    /// The query, required by chat and sent as the `query` input of completion apps.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub query: String,
+    /// The inputs of the case, merged over the inputs of the suite.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub inputs: HashMap<String, JsonValue>,
+    /// The assertions of the case, checked after the assertions of the suite.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assert: Vec<Assertion>,
+    /// Whether the case is skipped.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip: bool,
+}
+
+/// A test suite, the test cases of an app.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TestSuite {
+    /// The name of the suite, the file name of the suite file by default.
+    #[serde(default)]
+    pub name: String,
+    /// The mode of the app, detected with `info()` if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<AppMode>,
+    /// The ID of the workflow version to run, the published version if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workflow_id: Option<String>,
+    /// The path of the baseline file, relative to the suite file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<PathBuf>,
+    /// The inputs shared by the cases.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub inputs: HashMap<String, JsonValue>,
+    /// The assertions checked for every case.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assert: Vec<Assertion>,
+    /// The test cases.
+    pub cases: Vec<TestCase>,
+}
+
+impl TestSuite {
+    /// Parses a test suite from YAML.
+    ///
+    /// # Arguments
+    /// * `yaml` - The YAML of the suite.
+    ///
+    /// # Returns
+    /// A `Result` containing the suite or an error.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML is invalid, a case name is empty or duplicated,
+    /// or a regular expression or a JSON path is illegal.
+    pub fn from_yaml(yaml: &str) -> AnyResult<Self> {
+        // serde_yaml_ng reads enums from YAML tags only, the assertions are single key maps
+        let suite: TestSuite = serde_yaml_ng::from_str::<JsonValue>(yaml)
+            .map_err(anyhow::Error::from)
+            .and_then(|value| Ok(serde_json::from_value(value)?))
+            .map_err(|e| anyhow!("TestSuite Illegal: {}", e))?;
+        let mut names = HashSet::new();
+        for case in &suite.cases {
+            if case.name.trim().is_empty() {
+                bail!("TestCase.Name Illegal: empty");
+            }
+            if !names.insert(case.name.as_str()) {
+                bail!("TestCase.Name Illegal: duplicate {}", case.name);
+            }
+        }
+        for assertion in suite
+            .assert
+            .iter()
+            .chain(suite.cases.iter().flat_map(|c| &c.assert))
+        {
+            assertion.validate()?;
+        }
+        Ok(suite)
+    }
+
+    /// Reads a test suite from a YAML file.
+    /// The name defaults to the file name, and the baseline path is resolved against the
+    /// directory of the file.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the suite file.
+    ///
+    /// # Returns
+    /// A `Result` containing the suite or an error.
+    pub fn from_file(path: impl AsRef<Path>) -> AnyResult<Self> {
+        let path = path.as_ref();
+        let yaml = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read test suite {}: {}", path.display(), e))?;
+        let mut suite = Self::from_yaml(&yaml)?;
+        if suite.name.is_empty() {
+            suite.name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+        }
+        if let (Some(baseline), Some(dir)) = (&suite.baseline, path.parent()) {
+            suite.baseline = Some(dir.join(baseline));
+        }
+        Ok(suite)
+    }
+}
+
+/// How the answers are compared with the baseline.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum BaselineMode {
+    /// The differences are reported, the cases do not fail.
+    #[default]
+    Report,
+    /// The cases with an answer different from the baseline fail.
+    Fail,
+    /// The baseline is updated with the answers of the passed cases,
+    /// the baseline answers of the other cases are kept and their differences reported.
+    Update,
+}
+
+/// The status of a test case.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseStatus {
+    /// All assertions hold.
+    Passed,
+    /// An assertion does not hold.
+    Failed,
+    /// The request failed, or the workflow did not succeed.
+    Error,
+    /// The case was skipped.
+    Skipped,
+}
+
+/// The result of a test case.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CaseResult {
+    /// The name of the case.
+    pub name: String,
+    /// The status of the case.
+    pub status: CaseStatus,
+    /// The answer, or the text output of the workflow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answer: Option<String>,
+    /// The outputs of the workflow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<JsonValue>,
+    /// The latency of the request in milliseconds.
+    pub latency_ms: u64,
+    /// The total tokens used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_tokens: Option<u64>,
+    /// The messages of the failed assertions.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<String>,
+    /// The error of the request or the workflow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The unified diff of the baseline answer and the answer, if they differ.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+}
+
+impl CaseResult {
+    fn new(name: &str, status: CaseStatus) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            answer: None,
+            outputs: None,
+            latency_ms: 0,
+            total_tokens: None,
+            failures: Vec::new(),
+            error: None,
+            diff: None,
+        }
+    }
+}
+
+/// The report of a test suite.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TestReport {
+    /// The name of the suite.
+    pub suite: String,
+    /// The number of cases.
+    pub tests: usize,
+    /// The number of passed cases.
+    pub passed: usize,
+    /// The number of failed cases.
+    pub failed: usize,
+    /// The number of cases with an error.
+    pub errors: usize,
+    /// The number of skipped cases.
+    pub skipped: usize,
+    /// The elapsed time of the suite in seconds.
+    pub elapsed_time: f64,
+    /// The results of the cases, in the order of the suite.
+    pub cases: Vec<CaseResult>,
+}
+
+impl TestReport {
+    fn new(suite: &str, cases: Vec<CaseResult>, elapsed_time: f64) -> Self {
+        let count = |status| cases.iter().filter(|c| c.status == status).count();
+        Self {
+            suite: suite.to_string(),
+            tests: cases.len(),
+            passed: count(CaseStatus::Passed),
+            failed: count(CaseStatus::Failed),
+            errors: count(CaseStatus::Error),
+            skipped: count(CaseStatus::Skipped),
+            elapsed_time,
+            cases,
+        }
+    }
+
+    /// Returns whether no case failed or had an error.
+    pub fn is_success(&self) -> bool {
+        self.failed == 0 && self.errors == 0
+    }
+
+    /// Returns the report as JUnit XML.
+    pub fn to_junit(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let counts = format!(
+            "tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{:.3}\"",
+            self.tests, self.failed, self.errors, self.skipped, self.elapsed_time
+        );
+        let _ = writeln!(xml, "<testsuites {}>", counts);
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" {}>",
+            escape(&self.suite),
+            counts
+        );
+        for case in &self.cases {
+            let _ = write!(
+                xml,
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                escape(&case.name),
+                escape(&self.suite),
+                case.latency_ms as f64 / 1000.0
+            );
+            if case.status == CaseStatus::Passed && case.answer.is_none() && case.diff.is_none() {
+                xml.push_str("/>\n");
+                continue;
+            }
+            xml.push_str(">\n");
+            match case.status {
+                CaseStatus::Failed => {
+                    let message = case
+                        .failures
+                        .first()
+                        .map(String::as_str)
+                        .unwrap_or_default();
+                    let _ = writeln!(
+                        xml,
+                        "      <failure message=\"{}\">{}</failure>",
+                        escape(message),
+                        escape(&case.failures.join("\n"))
+                    );
+                }
+                CaseStatus::Error => {
+                    let error = case.error.as_deref().unwrap_or_default();
+                    let _ = writeln!(
+                        xml,
+                        "      <error message=\"{}\">{}</error>",
+                        escape(error),
+                        escape(error)
+                    );
+                }
+                CaseStatus::Skipped => xml.push_str("      <skipped/>\n"),
+                CaseStatus::Passed => {}
+            }
+            if let Some(answer) = &case.answer {
+                let _ = writeln!(xml, "      <system-out>{}</system-out>", escape(answer));
+            }
+            if let Some(diff) = &case.diff {
+                let _ = writeln!(xml, "      <system-err>{}</system-err>", escape(diff));
+            }
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+
+    /// Writes the report as JUnit XML.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the XML file.
+    ///
+    /// # Returns
+    /// A `Result` indicating success or an error.
+    pub fn write_junit(&self, path: impl AsRef<Path>) -> AnyResult<()> {
+        write_file(path.as_ref(), self.to_junit())
+    }
+
+    /// Writes the report as pretty JSON.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the JSON file.
+    ///
+    /// # Returns
+    /// A `Result` indicating success or an error.
+    pub fn write_json(&self, path: impl AsRef<Path>) -> AnyResult<()> {
+        write_file(path.as_ref(), serde_json::to_string_pretty(self)? + "\n")
+    }
+}
+
+/// Runs the test suites of an app.
+#[derive(Clone)]
+pub struct TestRunner {
+    api: Api,
+    user: String,
+    concurrency: usize,
+    baseline_mode: BaselineMode,
+}
+
+impl TestRunner {
+    /// Creates a new test runner with the API of the app.
+    ///
+    /// # Arguments
+    /// * `api` - The API of the app.
+    pub fn new(api: Api) -> Self {
+        let user = api.default_user().unwrap_or(DEFAULT_TEST_USER).to_string();
+        Self {
+            api,
+            user,
+            concurrency: 1,
+            baseline_mode: BaselineMode::default(),
+        }
+    }
+
+    /// Sets the user of the requests, the default user of the API or `dify-test` by default.
+    ///
+    /// # Arguments
+    /// * `user` - The user.
+    pub fn with_user(mut self, user: impl Into<String>) -> Self {
+        self.user = user.into();
+        self
+    }
+
+    /// Sets the number of cases run concurrently, 1 by default so that the latencies
+    /// are not skewed by each other.
+    ///
+    /// # Arguments
+    /// * `concurrency` - The number of cases run concurrently.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets how the answers are compared with the baseline of the suite.
+    ///
+    /// # Arguments
+    /// * `mode` - The baseline mode.
+    pub fn with_baseline_mode(mut self, mode: BaselineMode) -> Self {
+        self.baseline_mode = mode;
+        self
+    }
+
+    /// Reads a test suite from a YAML file and runs it.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the suite file.
+    ///
+    /// # Returns
+    /// A `Result` containing the report or an error.
+    pub async fn run_file(&self, path: impl AsRef<Path>) -> AnyResult<TestReport> {
+        let suite = TestSuite::from_file(path)?;
+        self.run(&suite).await
+    }
+
+    /// Runs a test suite.
+    ///
+    /// # Arguments
+    /// * `suite` - The test suite.
+    ///
+    /// # Returns
+    /// A `Result` containing the report or an error.
+    ///
+    /// # Errors
+    /// Returns an error if the app mode cannot be detected or the baseline cannot be read
+    /// or written. The failures of the cases are recorded in the report.
+    pub async fn run(&self, suite: &TestSuite) -> AnyResult<TestReport> {
+        let started = Instant::now();
+        let mode = match &suite.app {
+            Some(mode) => mode.clone(),
+            None => {
+                self.api
+                    .info(InfoRequest {
+                        user: self.user.clone(),
+                    })
+                    .await?
+                    .mode
+            }
+        };
+        let mut baseline = match &suite.baseline {
+            Some(path) => read_baseline(path).await?,
+            None => BTreeMap::new(),
+        };
+
+        let mut cases: Vec<CaseResult> = stream::iter(&suite.cases)
+            .map(|case| self.run_case(suite, &mode, case))
+            .buffered(self.concurrency)
+            .collect()
+            .await;
+
+        if suite.baseline.is_some() {
+            for case in &mut cases {
+                let Some(answer) = &case.answer else {
+                    continue;
+                };
+                if self.baseline_mode == BaselineMode::Update && case.status == CaseStatus::Passed {
+                    baseline.insert(case.name.clone(), answer.clone());
+                    continue;
+                }
+                let Some(expected) = baseline.get(&case.name).filter(|e| *e != answer) else {
+                    continue;
+                };
+                let diff = TextDiff::from_lines(expected.as_str(), answer.as_str())
+                    .unified_diff()
+                    .header("baseline", "answer")
+                    .to_string();
+                case.diff = Some(diff);
+                if self.baseline_mode == BaselineMode::Fail && case.status == CaseStatus::Passed {
+                    case.status = CaseStatus::Failed;
+                    case.failures
+                        .push("expected the answer to equal the baseline".to_string());
+                }
+            }
+        }
+        if let (Some(path), BaselineMode::Update) = (&suite.baseline, self.baseline_mode) {
+            let json = serde_json::to_string_pretty(&baseline)? + "\n";
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+            tokio::fs::write(path, json).await?;
+        }
+        Ok(TestReport::new(
+            &suite.name,
+            cases,
+            started.elapsed().as_secs_f64(),
+        ))
+    }
+
+    /// Runs a test case and checks the assertions of the suite and the case.
+    async fn run_case(&self, suite: &TestSuite, mode: &AppMode, case: &TestCase) -> CaseResult {
+        if case.skip {
+            return CaseResult::new(&case.name, CaseStatus::Skipped);
+        }
+        let mut inputs = suite.inputs.clone();
+        inputs.extend(case.inputs.clone());
+
+        let started = Instant::now();
+        let output = self
+            .send(mode, suite.workflow_id.as_deref(), &case.query, inputs)
+            .await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+        let mut output = match output {
+            Ok(output) => output,
+            Err(e) => {
+                return CaseResult {
+                    error: Some(e.to_string()),
+                    latency_ms,
+                    ..CaseResult::new(&case.name, CaseStatus::Error)
+                }
+            }
+        };
+        output.latency_ms = latency_ms;
+
+        let failures: Vec<String> = suite
+            .assert
+            .iter()
+            .chain(&case.assert)
+            .filter_map(|assertion| assertion.check(&output))
+            .collect();
+        let status = match (&output.error, failures.is_empty()) {
+            (Some(_), _) => CaseStatus::Error,
+            (None, true) => CaseStatus::Passed,
+            (None, false) => CaseStatus::Failed,
+        };
+        CaseResult {
+            answer: Some(output.answer),
+            outputs: output.outputs,
+            latency_ms,
+            total_tokens: output.total_tokens,
+            failures,
+            error: output.error,
+            ..CaseResult::new(&case.name, status)
+        }
+    }
+
+    /// Sends the request of a case to the app.
+    async fn send(
+        &self,
+        mode: &AppMode,
+        workflow_id: Option<&str>,
+        query: &str,
+        inputs: HashMap<String, JsonValue>,
+    ) -> AnyResult<CaseOutput> {
+        let user = self.user.clone();
+        match mode {
+            AppMode::Workflow => {
+                let req_data = WorkflowsRunRequest {
+                    inputs,
+                    user,
+                    ..Default::default()
+                };
+                let resp = match workflow_id {
+                    Some(workflow_id) => self.api.workflows_run_by_id(workflow_id, req_data).await,
+                    None => self.api.workflows_run(req_data).await,
+                }?;
+                let data = resp.data;
+                let answer = match &data.outputs {
+                    Some(JsonValue::Object(outputs)) if outputs.len() == 1 => {
+                        match outputs.values().next() {
+                            Some(JsonValue::String(text)) => text.clone(),
+                            Some(value) => value.to_string(),
+                            None => String::new(),
+                        }
+                    }
+                    Some(JsonValue::Null) | None => String::new(),
+                    Some(outputs) => outputs.to_string(),
+                };
+                let error = match data.status {
+                    FinishedStatus::Succeeded => None,
+                    status => Some(
+                        data.error
+                            .filter(|e| !e.is_empty())
+                            .unwrap_or_else(|| format!("workflow {:?}", status)),
+                    ),
+                };
+                Ok(CaseOutput {
+                    answer,
+                    outputs: data.outputs,
+                    total_tokens: data.total_tokens.map(u64::from),
+                    error,
+                    ..Default::default()
+                })
+            }
+            AppMode::Completion => {
+                let mut req_data = CompletionMessagesRequest {
+                    inputs,
+                    user,
+                    ..Default::default()
+                };
+                if !query.is_empty() {
+                    req_data
+                        .inputs
+                        .insert("query".into(), JsonValue::String(query.into()));
+                }
+                let resp = self.api.completion_messages(req_data).await?;
+                Ok(CaseOutput::message(resp.answer, &resp.metadata))
+            }
+            // agent apps only support the streaming mode
+            AppMode::AgentChat => {
+                let req_data = ChatMessagesRequest {
+                    inputs,
+                    query: query.into(),
+                    user,
+                    ..Default::default()
+                };
+                let mut stream = self.api.chat_messages_stream(req_data).await?;
+                let mut answer = String::new();
+                let mut metadata = HashMap::new();
+                while let Some(event) = stream.next().await {
+                    match event? {
+                        SseMessageEvent::Message { answer: chunk, .. }
+                        | SseMessageEvent::AgentMessage { answer: chunk, .. } => {
+                            answer.push_str(&chunk)
+                        }
+                        SseMessageEvent::MessageReplace { answer: text, .. } => answer = text,
+                        SseMessageEvent::MessageEnd { metadata: m, .. } => metadata = m,
+                        SseMessageEvent::Error { code, message, .. } => {
+                            bail!("{}: {}", code, message)
+                        }
+                        _ => {}
+                    }
+                }
+                Ok(CaseOutput::message(answer, &metadata))
+            }
+            _ => {
+                let req_data = ChatMessagesRequest {
+                    inputs,
+                    query: query.into(),
+                    user,
+                    ..Default::default()
+                };
+                let resp = self.api.chat_messages(req_data).await?;
+                Ok(CaseOutput::message(resp.answer, &resp.metadata))
+            }
+        }
+    }
+}
+
+/// The output of a test case, checked by the assertions.
+#[derive(Debug, Default)]
+struct CaseOutput {
+    answer: String,
+    outputs: Option<JsonValue>,
+    latency_ms: u64,
+    total_tokens: Option<u64>,
+    error: Option<String>,
+}
+
+impl CaseOutput {
+    /// Creates the output of a message, with the usage of the metadata.
+    fn message(answer: String, metadata: &HashMap<String, JsonValue>) -> Self {
+        let total_tokens = metadata
+            .get("usage")
+            .and_then(|usage| usage.get("total_tokens"))
+            .and_then(JsonValue::as_u64);
+        Self {
+            answer,
+            total_tokens,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the document of the JSON paths, the outputs of a workflow
+    /// or the answer parsed as JSON.
+    fn document(&self) -> JsonValue {
+        match &self.outputs {
+            Some(outputs) => outputs.clone(),
+            None => serde_json::from_str(self.answer.trim())
+                .unwrap_or_else(|_| JsonValue::String(self.answer.clone())),
+        }
+    }
+}
+
+/// A segment of a JSON path.
+#[derive(Debug, Clone, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// Parses a JSON path of keys and indexes, such as `$.items[0].name` or `$['a key']`.
+fn parse_path(path: &str) -> AnyResult<Vec<PathSegment>> {
+    let illegal = || anyhow!("Assertion.JsonPath Illegal: {}", path);
+    let rest = path.trim();
+    let rest = rest.strip_prefix('$').unwrap_or(rest);
+    let mut segments = Vec::new();
+    let mut chars = rest.chars().peekable();
+    // a path without `$` may start with a key
+    let mut expect_key = !rest.is_empty() && !rest.starts_with(['.', '[']);
+    while expect_key || chars.peek().is_some() {
+        if !expect_key {
+            match chars.next() {
+                Some('.') => {}
+                Some('[') => {
+                    let inner: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                    let inner = inner.trim();
+                    let key = inner
+                        .strip_prefix('\'')
+                        .and_then(|k| k.strip_suffix('\''))
+                        .or_else(|| inner.strip_prefix('"').and_then(|k| k.strip_suffix('"')));
+                    let segment = match key {
+                        Some(key) => PathSegment::Key(key.to_string()),
+                        None => PathSegment::Index(inner.parse().map_err(|_| illegal())?),
+                    };
+                    segments.push(segment);
+                    continue;
+                }
+                _ => return Err(illegal()),
+            }
+        }
+        expect_key = false;
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|c| *c != '.' && *c != '[') {
+            key.push(c);
+        }
+        if key.is_empty() {
+            return Err(illegal());
+        }
+        segments.push(PathSegment::Key(key));
+    }
+    Ok(segments)
+}
+
+/// Returns the value at a JSON path, if any.
+fn json_path(value: &JsonValue, path: &str) -> AnyResult<Option<JsonValue>> {
+    let mut value = value;
+    for segment in parse_path(path)? {
+        let next = match segment {
+            PathSegment::Key(key) => value.get(key.as_str()),
+            PathSegment::Index(index) => value.get(index),
+        };
+        match next {
+            Some(next) => value = next,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(value.clone()))
+}
+
+/// Reads the baseline answers by case name, empty if the file does not exist.
+async fn read_baseline(path: &Path) -> AnyResult<BTreeMap<String, String>> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(json) => serde_json::from_str(&json)
+            .map_err(|e| anyhow!("Baseline {} Illegal: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Writes a file, creating the parent directories.
+fn write_file(path: &Path, contents: String) -> AnyResult<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+/// Escapes the XML special characters.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // control characters are not allowed in XML 1.0
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -2,10 +2,15 @@
 //!
 //! The re-exported items include:
 //! - `header`: Types and functions for working with HTTP headers.
+//! - `Certificate`: A root certificate for the [`ClientBuilder`](crate::ClientBuilder), with a TLS feature.
 //! - `multipart`: Types and functions for working with multipart requests.
 //! - `Method`: An enum representing HTTP methods.
+//! - `Proxy`: A proxy for the [`ClientBuilder`](crate::ClientBuilder).
 //! - `Request`: A builder for making HTTP requests.
 //! - `Response`: A response to an HTTP request.
+//! - `StatusCode`: An HTTP status code.
 //!
 //! This module is intended to provide a convenient way to access commonly used items from the reqwest crate.
-pub use reqwest::{header, multipart, Method, Request, Response};
+#[cfg(any(feature = "default-tls", feature = "rustls-tls"))]
+pub use reqwest::Certificate;
+pub use reqwest::{header, multipart, Method, Proxy, Request, Response, StatusCode};
@@ -51,27 +51,84 @@
 //!         ..Default::default()
 //!     };
 //!     // Reuse the client with a new api key
-//!     let mut api = client.api();
+//!     let api = client.api();
 //!     let result = api.chat_messages(data.clone()).await;
 //!     println!("{:?}", result);
-//!     // Override the api key
+//!     // Override the api key, sharing the connection pool of the client
+//!     let api = api.with_api_key("API_KEY_OVERRIDE").unwrap();
+//!     // The api owns the client and can be moved into spawned tasks
+//!     let handle = tokio::spawn(async move { api.chat_messages(data).await });
+//!     println!("{:?}", handle.await);
+//!
+//!     // Any other request rewriting can be done with a hook
+//!     let mut api = client.api();
 //!     api.before_send(|mut req| {
-//!         // rewrite the authorization header
-//!         let mut auth = header::HeaderValue::from_static("Bearer API_KEY_OVERRIDE");
-//!         auth.set_sensitive(true);
-//!         req.headers_mut().insert(header::AUTHORIZATION, auth);
+//!         let trace_id = header::HeaderValue::from_static("trace-id");
+//!         req.headers_mut().insert("x-trace-id", trace_id);
 //!         req
 //!     });
-//!     let result = api.chat_messages(data).await;
+//! }
+//! ```
+//!
+//! ## Client from environment variables or config files
+//!
+//! ```no_run
+//! use dify_client::{Client, Config};
+//!
+//! // DIFY_BASE_URL, DIFY_API_KEY and DIFY_TIMEOUT
+//! let client = Client::new_with_config(Config::from_env().unwrap());
+//! // the `support-bot` app of a TOML or JSON config file
+//! let config = Config::from_file("dify.toml", Some("support-bot")).unwrap();
+//! let client = Client::new_with_config(config);
+//! ```
+//!
+//! ## Client pool with multiple apps
+//!
+//! ```no_run
+//! use dify_client::{request, Client, ClientPool};
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     // All apps share the connection pool of the template client
+//!     let mut pool = ClientPool::new(Client::new("https://api.dify.ai", "API_KEY_DEFAULT"));
+//!     pool.register("support-bot", "API_KEY_SUPPORT", None)?;
+//!     pool.register("sales-bot", "API_KEY_SALES", Some("https://dify.example.com"))?;
+//!
+//!     let data = request::ChatMessagesRequest {
+//!         query: "What are the specs of the iPhone 13 Pro Max?".into(),
+//!         user: "afa".into(),
+//!         ..Default::default()
+//!     };
+//!     let result = pool.app("support-bot")?.chat_messages(data).await;
 //!     println!("{:?}", result);
+//!     Ok(())
 //! }
 //! ```
+//!
 //! For more API methods, refer to the [`Api`](api/struct.Api.html) struct.
 
 pub mod api;
+#[cfg(feature = "batch")]
+pub mod batch;
+pub mod cache;
+pub mod cassette;
 pub mod client;
+pub mod config;
+pub mod conversation;
+pub mod export;
+#[cfg(feature = "harness")]
+pub mod harness;
 pub mod http;
+pub mod pagination;
+pub mod pool;
+#[cfg(feature = "progress")]
+pub mod progress;
 pub mod request;
 pub mod response;
+pub mod schema;
+pub mod trace;
+mod util;
+pub mod validation;
 
 pub use client::*;
+pub use pool::ClientPool;
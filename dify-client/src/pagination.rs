@@ -0,0 +1,107 @@
+//! This module provides streams paging transparently through list endpoints.
+//!
+//! The list endpoints of Dify return one page at a time with a cursor (`last_id`, `first_id`
+//! or a page number) and a `has_more` flag. A [`Paginator`] fetches the pages lazily as the stream
+//! is polled, and yields the items one by one.
+//! Each paginator owns its cursor, so multiple paginators can run concurrently.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use dify_client::Client;
+//! use futures::StreamExt;
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let client = Client::new("https://api.dify.ai", "API_KEY");
+//!     let api = client.api();
+//!
+//!     let mut conversations = api.conversations_iter("afa");
+//!     while let Some(conversation) = conversations.next().await {
+//!         let conversation = conversation?;
+//!         let messages = api
+//!             .messages_iter(&conversation.id, "afa")
+//!             .collect_all()
+//!             .await?;
+//!         println!("{}: {} messages", conversation.name, messages.len());
+//!     }
+//!     Ok(())
+//! }
+//! ```
+use anyhow::{Error, Result as AnyResult};
+use futures::{
+    future::Future,
+    stream::{self, BoxStream, Stream, StreamExt, TryStreamExt},
+};
+use std::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// The default number of items fetched per page.
+pub const DEFAULT_PAGE_SIZE: u32 = 20;
+
+/// A page of items and the cursor of the next page, `None` if it is the last page.
+pub type Page<T, C> = (Vec<T>, Option<C>);
+
+/// A stream of items paging through a list endpoint.
+pub struct Paginator<T> {
+    inner: BoxStream<'static, AnyResult<T>>,
+}
+
+impl<T: Send + 'static> Paginator<T> {
+    /// Creates a new `Paginator` from the first cursor and a function fetching a page.
+    /// Paging stops at the last page, at an empty page, or after the first error.
+    ///
+    /// # Arguments
+    /// * `cursor` - The cursor of the first page.
+    /// * `fetch` - The function fetching the page of a cursor.
+    pub fn new<C, F, Fut>(cursor: C, mut fetch: F) -> Self
+    where
+        C: Send + 'static,
+        F: FnMut(C) -> Fut + Send + 'static,
+        Fut: Future<Output = AnyResult<Page<T, C>>> + Send + 'static,
+    {
+        let pages = stream::try_unfold(Some(cursor), move |cursor| {
+            let page = cursor.map(&mut fetch);
+            async move {
+                let page: Option<Page<T, C>> = match page {
+                    Some(page) => Some(page.await?),
+                    None => None,
+                };
+                Ok::<_, Error>(page.map(|(items, next)| {
+                    let next = if items.is_empty() { None } else { next };
+                    (items, next)
+                }))
+            }
+        });
+        let inner = pages
+            .map_ok(|items| stream::iter(items.into_iter().map(Ok::<T, Error>)))
+            .try_flatten()
+            .boxed();
+        Self { inner }
+    }
+
+    /// Collects all items of all pages.
+    ///
+    /// # Returns
+    /// A `Result` containing the items or the first error.
+    pub async fn collect_all(self) -> AnyResult<Vec<T>> {
+        self.inner.try_collect().await
+    }
+}
+
+impl<T> Stream for Paginator<T> {
+    type Item = AnyResult<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+impl<T> Debug for Paginator<T> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_struct("Paginator").finish_non_exhaustive()
+    }
+}
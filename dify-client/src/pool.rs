@@ -0,0 +1,179 @@
+//! This module provides a registry of Dify apps sharing one HTTP connection pool.
+//!
+//! Each Dify app has its own API key and optionally its own base URL.
+//! The `ClientPool` maps logical app names to clients built from a template client,
+//! so all apps reuse the same connections and HTTP settings.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use dify_client::{config::ConfigFile, request, Client, ClientPool};
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let template = Client::builder().base_url("https://api.dify.ai").build()?;
+//!     let mut pool = ClientPool::new(template);
+//!     pool.register("support-bot", "API_KEY_SUPPORT", None)?;
+//!     pool.register("summary-workflow", "API_KEY_SUMMARY", Some("https://dify.example.com"))?;
+//!
+//!     // or register all apps of a config file
+//!     let file = ConfigFile::from_file("dify.toml")?;
+//!     pool.register_config_file(&file)?;
+//!
+//!     let data = request::ChatMessagesRequest {
+//!         query: "how are you?".into(),
+//!         user: "afa".into(),
+//!         ..Default::default()
+//!     };
+//!     let result = pool.app("support-bot")?.chat_messages(data).await;
+//!     println!("{:?}", result);
+//!     Ok(())
+//! }
+//! ```
+use super::{
+    api::Api,
+    client::{Client, Config},
+    config::ConfigFile,
+};
+use anyhow::{anyhow, Result as AnyResult};
+use std::collections::HashMap;
+
+/// A registry of Dify apps keyed by app name, sharing one HTTP connection pool.
+#[derive(Clone, Debug)]
+pub struct ClientPool {
+    /// The template client, providing the connection pool, headers and defaults.
+    client: Client,
+    /// The clients of the registered apps.
+    apps: HashMap<String, Client>,
+}
+
+impl ClientPool {
+    /// Creates a new `ClientPool` from a template client.
+    /// The registered apps share the HTTP connection pool and headers of the template client,
+    /// and inherit its base URL and timeout unless overridden.
+    ///
+    /// # Arguments
+    /// * `client` - The template client.
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            apps: HashMap::new(),
+        }
+    }
+
+    /// Creates a new `ClientPool` from a template client and registers all apps of the config file.
+    ///
+    /// # Arguments
+    /// * `client` - The template client.
+    /// * `file` - The config file.
+    ///
+    /// # Returns
+    /// A `Result` containing the pool or an error.
+    pub fn from_config_file(client: Client, file: &ConfigFile) -> AnyResult<Self> {
+        let mut pool = Self::new(client);
+        pool.register_config_file(file)?;
+        Ok(pool)
+    }
+
+    /// Registers an app with its API key and an optional base URL.
+    /// An app registered with the same name is replaced.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the app.
+    /// * `api_key` - The API key of the app.
+    /// * `base_url` - The base URL of the app, the template base URL if `None`.
+    ///
+    /// # Returns
+    /// A `Result` indicating success or an error.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        api_key: &str,
+        base_url: Option<&str>,
+    ) -> AnyResult<()> {
+        let template = &self.client.config;
+        let config = Config {
+            base_url: base_url.unwrap_or(&template.base_url).into(),
+            api_key: api_key.into(),
+            timeout: template.timeout,
+        };
+        self.register_config(name, config)
+    }
+
+    /// Registers an app with its configuration.
+    /// An app registered with the same name is replaced.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the app.
+    /// * `config` - The configuration of the app.
+    ///
+    /// # Returns
+    /// A `Result` indicating success or an error.
+    pub fn register_config(&mut self, name: impl Into<String>, config: Config) -> AnyResult<()> {
+        let client = self.client.with_config(config)?;
+        self.apps.insert(name.into(), client);
+        Ok(())
+    }
+
+    /// Registers all apps of the config file.
+    ///
+    /// # Arguments
+    /// * `file` - The config file.
+    ///
+    /// # Returns
+    /// A `Result` indicating success or an error.
+    pub fn register_config_file(&mut self, file: &ConfigFile) -> AnyResult<()> {
+        for name in file.apps.keys() {
+            self.register_config(name.as_str(), file.app(name)?)?;
+        }
+        Ok(())
+    }
+
+    /// Removes an app from the pool.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the app.
+    ///
+    /// # Returns
+    /// The client of the removed app, if it was registered.
+    pub fn remove(&mut self, name: &str) -> Option<Client> {
+        self.apps.remove(name)
+    }
+
+    /// Returns the names of the registered apps.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.apps.keys().map(String::as_str)
+    }
+
+    /// Returns whether an app is registered.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the app.
+    pub fn contains(&self, name: &str) -> bool {
+        self.apps.contains_key(name)
+    }
+
+    /// Returns the client of a registered app.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the app.
+    ///
+    /// # Returns
+    /// A `Result` containing the client or an error if the app is not registered.
+    pub fn client(&self, name: &str) -> AnyResult<&Client> {
+        self.apps
+            .get(name)
+            .ok_or_else(|| anyhow!("ClientPool.App {} not found", name))
+    }
+
+    /// Returns the API bound to the API key of a registered app.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the app.
+    ///
+    /// # Returns
+    /// A `Result` containing the API or an error if the app is not registered.
+    pub fn app(&self, name: &str) -> AnyResult<Api> {
+        self.client(name).map(Client::api)
+    }
+}
@@ -0,0 +1,281 @@
+//! This module provides a live terminal renderer of workflow streams, behind the `progress` feature.
+//!
+//! A [`WorkflowProgress`] draws a spinner per node with its status, elapsed time and token count,
+//! nests the nodes of iterations and loops under their container, and prints the streamed text
+//! of `text_chunk` and `message` events above the spinners as complete lines.
+//! The events are also recorded in a [`WorkflowTrace`] returned when the stream ends.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use dify_client::{progress::WorkflowProgress, request, Client};
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let client = Client::new("https://api.dify.ai", "API_KEY");
+//!     let data = request::WorkflowsRunRequest {
+//!         user: "afa".into(),
+//!         ..Default::default()
+//!     };
+//!     let stream = client.api().workflows_run_stream(data).await?;
+//!     let trace = WorkflowProgress::new().render(stream).await?;
+//!     println!("{}", serde_json::to_string(&trace.outputs)?);
+//!     Ok(())
+//! }
+//! ```
+use super::{
+    response::{FinishedStatus, NodeFinishedData, SseMessageEvent},
+    trace::WorkflowTrace,
+};
+use anyhow::Result as AnyResult;
+use futures::{Stream, StreamExt};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::{collections::HashMap, time::Duration};
+
+pub use indicatif::ProgressDrawTarget;
+
+/// The template of running spinners.
+const RUNNING_TEMPLATE: &str = "{prefix}{spinner:.cyan} {msg} {elapsed:.dim}";
+/// The template of finished spinners.
+const FINISHED_TEMPLATE: &str = "{prefix}{msg}";
+/// The tick interval of running spinners.
+const TICK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A live terminal renderer of a workflow stream.
+pub struct WorkflowProgress {
+    multi: MultiProgress,
+    header: ProgressBar,
+    /// The spinners and their labels by node execution ID.
+    nodes: HashMap<String, (ProgressBar, String)>,
+    /// The nesting depths by node ID.
+    depths: HashMap<String, usize>,
+    trace: WorkflowTrace,
+    text: String,
+    line: String,
+    print_text: bool,
+}
+
+impl Default for WorkflowProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WorkflowProgress {
+    /// Creates a new renderer drawing to stderr.
+    pub fn new() -> Self {
+        Self::with_draw_target(ProgressDrawTarget::stderr())
+    }
+
+    /// Creates a new renderer drawing to the target.
+    ///
+    /// # Arguments
+    /// * `target` - The draw target, `ProgressDrawTarget::hidden()` to draw nothing.
+    pub fn with_draw_target(target: ProgressDrawTarget) -> Self {
+        let multi = MultiProgress::with_draw_target(target);
+        let header = multi.add(running_spinner(String::new()));
+        header.set_message("workflow");
+        Self {
+            multi,
+            header,
+            nodes: HashMap::new(),
+            depths: HashMap::new(),
+            trace: WorkflowTrace::new(),
+            text: String::new(),
+            line: String::new(),
+            print_text: true,
+        }
+    }
+
+    /// Sets whether the streamed text is printed, `true` by default.
+    ///
+    /// # Arguments
+    /// * `print_text` - Whether the streamed text is printed.
+    pub fn with_text(mut self, print_text: bool) -> Self {
+        self.print_text = print_text;
+        self
+    }
+
+    /// Returns the text streamed so far.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the trace recorded so far.
+    pub fn trace(&self) -> &WorkflowTrace {
+        &self.trace
+    }
+
+    /// Renders an event.
+    ///
+    /// # Arguments
+    /// * `event` - The event.
+    pub fn handle(&mut self, event: &SseMessageEvent) {
+        self.trace.push(event);
+        match event {
+            SseMessageEvent::WorkflowStarted {
+                workflow_run_id, ..
+            } => {
+                self.header
+                    .set_message(format!("workflow {}", workflow_run_id));
+            }
+            SseMessageEvent::NodeStarted { data, .. } => {
+                let depth = ["iteration_id", "loop_id"]
+                    .iter()
+                    .find_map(|name| data.extra.get(*name).and_then(|v| v.as_str()))
+                    .and_then(|id| self.depths.get(id))
+                    .map_or(1, |depth| depth + 1);
+                self.depths.insert(data.node_id.clone(), depth);
+                let label = format!("[{}] {}", data.node_type, data.title);
+                let spinner = self.multi.add(running_spinner("  ".repeat(depth)));
+                spinner.set_message(label.clone());
+                self.nodes.insert(data.id.clone(), (spinner, label));
+            }
+            SseMessageEvent::NodeFinished { data, .. } => {
+                if let Some((spinner, label)) = self.nodes.remove(&data.id) {
+                    spinner.set_style(finished_style());
+                    spinner.finish_with_message(node_summary(data, &label));
+                }
+            }
+            SseMessageEvent::TextChunk { data, .. } => self.write_text(&data.text),
+            SseMessageEvent::Message { answer, .. }
+            | SseMessageEvent::AgentMessage { answer, .. } => self.write_text(answer),
+            SseMessageEvent::WorkflowFinished { .. } => {
+                self.flush_text();
+                self.header.set_style(finished_style());
+                self.header.finish_with_message(self.workflow_summary());
+            }
+            SseMessageEvent::Error { code, message, .. } => {
+                self.flush_text();
+                self.header.set_style(finished_style());
+                self.header
+                    .abandon_with_message(format!("✗ workflow {}: {}", code, message));
+            }
+            _ => {}
+        }
+    }
+
+    /// Renders all events of the stream, until the stream ends.
+    ///
+    /// # Arguments
+    /// * `stream` - The stream of events.
+    ///
+    /// # Returns
+    /// A `Result` containing the trace of the workflow or the first error of the stream.
+    pub async fn render<S>(mut self, stream: S) -> AnyResult<WorkflowTrace>
+    where
+        S: Stream<Item = AnyResult<SseMessageEvent>>,
+    {
+        let mut stream = std::pin::pin!(stream);
+        while let Some(event) = stream.next().await {
+            match event {
+                Ok(event) => self.handle(&event),
+                Err(e) => {
+                    self.flush_text();
+                    self.header.set_style(finished_style());
+                    self.header.abandon_with_message(format!("✗ {}", e));
+                    self.abandon_nodes();
+                    return Err(e);
+                }
+            }
+        }
+        Ok(self.finish())
+    }
+
+    /// Stops rendering, and returns the trace of the workflow.
+    pub fn finish(mut self) -> WorkflowTrace {
+        self.flush_text();
+        if !self.header.is_finished() {
+            self.header.set_style(finished_style());
+            self.header.finish_with_message(self.workflow_summary());
+        }
+        self.abandon_nodes();
+        self.trace
+    }
+
+    /// Stops the spinners of the nodes still running.
+    fn abandon_nodes(&mut self) {
+        for (_, (spinner, _)) in self.nodes.drain() {
+            spinner.abandon();
+        }
+    }
+
+    /// Appends streamed text, printing the complete lines.
+    fn write_text(&mut self, text: &str) {
+        self.text.push_str(text);
+        if !self.print_text {
+            return;
+        }
+        self.line.push_str(text);
+        while let Some(end) = self.line.find('\n') {
+            let line: String = self.line.drain(..=end).collect();
+            let _ = self.multi.println(line.trim_end_matches(['\r', '\n']));
+        }
+    }
+
+    /// Prints the last incomplete line of the streamed text.
+    fn flush_text(&mut self) {
+        if !self.line.is_empty() {
+            let _ = self.multi.println(std::mem::take(&mut self.line));
+        }
+    }
+
+    fn workflow_summary(&self) -> String {
+        let trace = &self.trace;
+        let mut summary = format!(
+            "{} workflow {}",
+            status_symbol(trace.status.as_ref()),
+            trace.workflow_run_id.as_deref().unwrap_or_default()
+        );
+        if let Some(elapsed_time) = trace.elapsed_time {
+            summary.push_str(&format!(" {:.2}s", elapsed_time));
+        }
+        let cost = trace.cost();
+        if cost.total_tokens > 0 {
+            summary.push_str(&format!(" {} tokens", cost.total_tokens));
+        }
+        if let Some(error) = trace.error.as_ref() {
+            summary.push_str(&format!(" error: {}", error));
+        }
+        summary
+    }
+}
+
+fn running_spinner(prefix: String) -> ProgressBar {
+    let spinner = ProgressBar::new_spinner()
+        .with_style(ProgressStyle::with_template(RUNNING_TEMPLATE).expect("valid template"))
+        .with_prefix(prefix);
+    spinner.enable_steady_tick(TICK_INTERVAL);
+    spinner
+}
+
+fn finished_style() -> ProgressStyle {
+    ProgressStyle::with_template(FINISHED_TEMPLATE).expect("valid template")
+}
+
+fn status_symbol(status: Option<&FinishedStatus>) -> &'static str {
+    match status {
+        Some(FinishedStatus::Succeeded) => "✓",
+        Some(FinishedStatus::Failed) => "✗",
+        Some(FinishedStatus::Stopped) => "■",
+        Some(FinishedStatus::Running) | None => "…",
+    }
+}
+
+fn node_summary(data: &NodeFinishedData, label: &str) -> String {
+    let mut summary = format!("{} {}", status_symbol(Some(&data.status)), label);
+    if let Some(elapsed_time) = data.elapsed_time {
+        summary.push_str(&format!(" {:.2}s", elapsed_time));
+    }
+    let total_tokens = data
+        .execution_metadata
+        .as_ref()
+        .and_then(|m| m.total_tokens);
+    if let Some(total_tokens) = total_tokens.filter(|t| *t > 0) {
+        summary.push_str(&format!(" {} tokens", total_tokens));
+    }
+    if let Some(error) = data.error.as_ref().filter(|e| !e.is_empty()) {
+        summary.push_str(&format!(" error: {}", error));
+    }
+    summary
+}
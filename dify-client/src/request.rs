@@ -17,6 +17,7 @@
 //! // FILEPATH: ~/git/dify/dify-sdk-rs/dify-client/src/request.rs
 //! pub use bytes::Bytes;
 //! use serde::{Deserialize, Serialize};
+//! use serde_json::Value as JsonValue;
 //! use std::collections::HashMap;
 //!
 //!
@@ -27,7 +28,7 @@
 //!     /// 允许传入 App 定义的各变量值。
 //!     /// inputs 参数包含了多组键值对（Key/Value pairs），每组的键对应一个特定变量，每组的值则是该变量的具体值。
 //!     /// 默认 {}
-//!     pub inputs: HashMap<String, String>,
+//!     pub inputs: HashMap<String, JsonValue>,
 //!     /// 用户输入/提问内容。
 //!     pub query: String,
 //!     /// 响应模式
@@ -56,8 +57,11 @@
 //!
 //! For more information on each request structure and its fields, refer to the documentation comments provided for each structure.
 //!
+use super::response::FinishedStatus;
+use anyhow::{bail, Result as AnyResult};
 pub use bytes::Bytes;
 use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
 use std::collections::HashMap;
 
 /// 发送对话消息的请求
@@ -67,12 +71,13 @@ pub struct ChatMessagesRequest {
     /// 允许传入 App 定义的各变量值。  
     /// inputs 参数包含了多组键值对（Key/Value pairs），每组的键对应一个特定变量，每组的值则是该变量的具体值。  
     /// 默认 {}  
-    pub inputs: HashMap<String, String>,
+    pub inputs: HashMap<String, JsonValue>,
     /// 用户输入/提问内容。
     pub query: String,
     /// 响应模式  
     /// * streaming 流式模式（推荐）。基于 SSE（Server-Sent Events）实现类似打字机输出方式的流式返回。
     /// * blocking 阻塞模式，等待执行完毕后返回结果。（请求若流程较长可能会被中断）。  
+    ///
     /// 由于 Cloudflare 限制，请求会在 100 秒超时无返回后中断。
     pub response_mode: ResponseMode,
     /// 用户标识，用于定义终端用户的身份，方便检索、统计。  
@@ -87,9 +92,21 @@ pub struct ChatMessagesRequest {
     pub auto_generate_name: bool,
 }
 
+impl ChatMessagesRequest {
+    /// 使用可序列化的结构体设置变量值，会覆盖 `inputs` 中的同名变量。
+    pub fn with_inputs<T>(mut self, inputs: &T) -> AnyResult<Self>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.inputs.extend(to_inputs(inputs)?);
+        Ok(self)
+    }
+}
+
 /// 响应模式
 /// * streaming 流式模式（推荐）。基于 SSE（Server-Sent Events）实现类似打字机输出方式的流式返回。
 /// * blocking 阻塞模式，等待执行完毕后返回结果。（请求若流程较长可能会被中断）。  
+///
 /// 由于 Cloudflare 限制，请求会在 100 秒超时无返回后中断。
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -102,23 +119,53 @@ pub enum ResponseMode {
 }
 
 /// 文件类型
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum FileType {
+    /// 图片，如 JPG, JPEG, PNG, GIF, WEBP, SVG
     #[default]
     Image,
+    /// 文档，如 TXT, MD, PDF, HTML, XLSX, DOCX, CSV 等
+    Document,
+    /// 音频，如 MP3, M4A, WAV, WEBM, AMR
+    Audio,
+    /// 视频，如 MP4, MOV, MPEG, MPGA
+    Video,
+    /// 其他文件类型
+    Custom,
+}
+
+impl FileType {
+    /// Returns the file type of a file extension, `Custom` for unknown extensions.
+    ///
+    /// # Arguments
+    /// * `extension` - The file extension, with or without the leading dot, case-insensitive.
+    pub fn from_extension(extension: &str) -> Self {
+        let extension = extension.trim_start_matches('.').to_ascii_lowercase();
+        match extension.as_str() {
+            "jpg" | "jpeg" | "png" | "gif" | "webp" | "svg" => FileType::Image,
+            "txt" | "md" | "markdown" | "mdx" | "pdf" | "html" | "htm" | "xlsx" | "xls" | "doc"
+            | "docx" | "csv" | "eml" | "msg" | "pptx" | "ppt" | "xml" | "epub" | "json" => {
+                FileType::Document
+            }
+            "mp3" | "m4a" | "wav" | "webm" | "amr" | "mpga" => FileType::Audio,
+            "mp4" | "mov" | "mpeg" => FileType::Video,
+            _ => FileType::Custom,
+        }
+    }
 }
 
 /// 上传的文件
+/// 可用于 `files` 参数，也可通过 [`InputsBuilder`] 作为文件或文件列表类型的变量值。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case", tag = "transfer_method")]
 pub enum FileInput {
-    /// 图片地址方式传递
+    /// 文件地址方式传递
     RemoteUrl {
         /// 文件类型
         #[serde(rename = "type")]
         type_: FileType,
-        /// 图片地址
+        /// 文件地址
         url: String,
     },
     /// 上传文件方式传递
@@ -131,6 +178,103 @@ pub enum FileInput {
     },
 }
 
+/// 变量值构建器
+/// 用于构建 `inputs`，支持文本、数字、布尔、对象、数组以及文件、文件列表类型的变量。
+///
+/// # Example
+///
+/// ```
+/// use dify_client::request::{FileInput, FileType, InputsBuilder, WorkflowsRunRequest};
+///
+/// let inputs = InputsBuilder::new()
+///     .set("query", "What is Dify?")
+///     .set("top_k", 3)
+///     .set("verbose", true)
+///     .file(
+///         "contract",
+///         FileInput::LocalFile {
+///             type_: FileType::Document,
+///             upload_file_id: "file-id".into(),
+///         },
+///     )
+///     .build();
+/// let req = WorkflowsRunRequest {
+///     inputs,
+///     user: "afa".into(),
+///     ..Default::default()
+/// };
+/// assert_eq!(req.inputs["top_k"], 3);
+/// assert_eq!(req.inputs["contract"]["transfer_method"], "local_file");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InputsBuilder {
+    inputs: HashMap<String, JsonValue>,
+}
+
+impl InputsBuilder {
+    /// 创建变量值构建器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置变量值，支持字符串、数字、布尔以及 JSON 值
+    pub fn set(mut self, key: impl Into<String>, value: impl Into<JsonValue>) -> Self {
+        self.inputs.insert(key.into(), value.into());
+        self
+    }
+
+    /// 设置文件类型的变量值
+    pub fn file(self, key: impl Into<String>, file: FileInput) -> Self {
+        self.set(key, file)
+    }
+
+    /// 设置文件列表类型的变量值
+    pub fn files(self, key: impl Into<String>, files: Vec<FileInput>) -> Self {
+        let files = files.into_iter().map(JsonValue::from).collect::<Vec<_>>();
+        self.set(key, files)
+    }
+
+    /// 返回变量值
+    pub fn build(self) -> HashMap<String, JsonValue> {
+        self.inputs
+    }
+}
+
+/// 上传的文件作为变量值
+impl From<FileInput> for JsonValue {
+    fn from(file: FileInput) -> Self {
+        serde_json::to_value(file).expect("FileInput serialization is infallible")
+    }
+}
+
+/// 将可序列化的结构体或映射转换为变量值
+///
+/// # Example
+///
+/// ```
+/// use dify_client::request::to_inputs;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Inputs {
+///     query: String,
+///     top_k: u32,
+/// }
+///
+/// let inputs = to_inputs(&Inputs { query: "What is Dify?".into(), top_k: 3 }).unwrap();
+/// assert_eq!(inputs["top_k"], 3);
+/// ```
+pub fn to_inputs<T>(value: &T) -> AnyResult<HashMap<String, JsonValue>>
+where
+    T: Serialize + ?Sized,
+{
+    match serde_json::to_value(value)? {
+        JsonValue::Object(map) => Ok(map.into_iter().collect()),
+        JsonValue::Null => Ok(HashMap::new()),
+        _ => bail!("Inputs Illegal, a struct or map is required"),
+    }
+}
+
 /// 停止响应请求
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct StreamTaskStopRequest {
@@ -204,6 +348,46 @@ pub struct ParametersRequest {
     pub user: String,
 }
 
+/// 获取应用基本信息的请求
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct InfoRequest {
+    /// 用户标识，由开发者定义规则，需保证用户标识在应用内唯一。
+    pub user: String,
+}
+
+/// 获取应用 WebApp 设置的请求
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct SiteRequest {
+    /// 用户标识，由开发者定义规则，需保证用户标识在应用内唯一。
+    pub user: String,
+}
+
+/// 获取应用反馈列表的请求
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AppFeedbacksRequest {
+    /// 页码，从 1 开始，默认 1
+    pub page: Option<u32>,
+    /// 每页条数，默认 20 条。
+    pub limit: Option<u32>,
+}
+
+/// 获取 workflow 日志的请求
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct WorkflowLogsRequest {
+    /// 关键字
+    pub keyword: Option<String>,
+    /// 执行状态 succeeded / failed / stopped
+    pub status: Option<FinishedStatus>,
+    /// 页码，从 1 开始，默认 1
+    pub page: Option<u32>,
+    /// 每页条数，默认 20 条。
+    pub limit: Option<u32>,
+    /// 由哪个终端用户会话创建，即终端用户的 user 标识
+    pub created_by_end_user_session_id: Option<String>,
+    /// 由哪个管理员账号创建，即账号邮箱
+    pub created_by_account: Option<String>,
+}
+
 /// 获取应用Meta信息的请求
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct MetaRequest {
@@ -224,6 +408,34 @@ pub struct ConversationsRenameRequest {
     pub user: String,
 }
 
+/// 获取对话变量的请求
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ConversationVariablesRequest {
+    /// 会话 ID
+    pub conversation_id: String,
+    /// 用户标识，由开发者定义规则，需保证用户标识在应用内唯一。
+    pub user: String,
+    /// 当前页最后面一条记录的 ID，默认 None
+    pub last_id: Option<String>,
+    /// 一次请求返回多少条记录，默认 20 条。
+    pub limit: Option<u32>,
+    /// 按变量名称筛选
+    pub variable_name: Option<String>,
+}
+
+/// 更新对话变量的请求
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ConversationVariableUpdateRequest {
+    /// 会话 ID
+    pub conversation_id: String,
+    /// 变量 ID
+    pub variable_id: String,
+    /// 变量的新值，需与变量的值类型一致
+    pub value: JsonValue,
+    /// 用户标识，由开发者定义规则，需保证用户标识在应用内唯一。
+    pub user: String,
+}
+
 /// 删除会话请求
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct ConversationsDeleteRequest {
@@ -269,10 +481,11 @@ pub struct WorkflowsRunRequest {
     /// 允许传入 App 定义的各变量值。  
     /// inputs 参数包含了多组键值对（Key/Value pairs），每组的键对应一个特定变量，每组的值则是该变量的具体值。  
     /// 默认 {}  
-    pub inputs: HashMap<String, String>,
+    pub inputs: HashMap<String, JsonValue>,
     /// 响应模式  
     /// * streaming 流式模式（推荐）。基于 SSE（Server-Sent Events）实现类似打字机输出方式的流式返回。
     /// * blocking 阻塞模式，等待执行完毕后返回结果。（请求若流程较长可能会被中断）。  
+    ///
     /// 由于 Cloudflare 限制，请求会在 100 秒超时无返回后中断。
     pub response_mode: ResponseMode,
     /// 用户标识，用于定义终端用户的身份，方便检索、统计。  
@@ -282,16 +495,28 @@ pub struct WorkflowsRunRequest {
     pub files: Vec<FileInput>,
 }
 
+impl WorkflowsRunRequest {
+    /// 使用可序列化的结构体设置变量值，会覆盖 `inputs` 中的同名变量。
+    pub fn with_inputs<T>(mut self, inputs: &T) -> AnyResult<Self>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.inputs.extend(to_inputs(inputs)?);
+        Ok(self)
+    }
+}
+
 /// 文本生成请求
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CompletionMessagesRequest {
     /// 允许传入 App 定义的各变量值。  
     /// inputs 参数包含了多组键值对（Key/Value pairs），每组的键对应一个特定变量，每组的值则是该变量的具体值。  
     /// 默认 {}  
-    pub inputs: HashMap<String, String>,
+    pub inputs: HashMap<String, JsonValue>,
     /// 响应模式  
     /// * streaming 流式模式（推荐）。基于 SSE（Server-Sent Events）实现类似打字机输出方式的流式返回。
     /// * blocking 阻塞模式，等待执行完毕后返回结果。（请求若流程较长可能会被中断）。  
+    ///
     /// 由于 Cloudflare 限制，请求会在 100 秒超时无返回后中断。
     pub response_mode: ResponseMode,
     /// 用户标识，用于定义终端用户的身份，方便检索、统计。  
@@ -302,3 +527,14 @@ pub struct CompletionMessagesRequest {
     /// 文件列表，适用于传入文件（图片）结合文本理解并回答问题，仅当模型支持 Vision 能力时可用。
     pub files: Vec<FileInput>,
 }
+
+impl CompletionMessagesRequest {
+    /// 使用可序列化的结构体设置变量值，会覆盖 `inputs` 中的同名变量。
+    pub fn with_inputs<T>(mut self, inputs: &T) -> AnyResult<Self>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.inputs.extend(to_inputs(inputs)?);
+        Ok(self)
+    }
+}
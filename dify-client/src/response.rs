@@ -69,9 +69,9 @@ use anyhow::{anyhow, bail, Result as AnyResult};
 use eventsource_stream::EventStream;
 use futures::Stream;
 use pin_project_lite::pin_project;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
 use serde_json::Value as JsonValue;
-use serde_with::{serde_as, EnumMap};
+use serde_with::{serde_as, DisplayFromStr, PickFirst};
 use std::{
     collections::HashMap,
     fmt::{Display, Formatter, Result as FmtResult},
@@ -94,12 +94,15 @@ impl Display for ErrorResponse {
 }
 
 impl ErrorResponse {
+    /// 无法解析的错误响应的错误码
+    pub const UNKNOWN_CODE: &'static str = "unknown_error";
+
     pub fn unknown<T>(message: T) -> Self
     where
         T: ToString,
     {
         ErrorResponse {
-            code: "unknown_error".into(),
+            code: Self::UNKNOWN_CODE.into(),
             message: message.to_string(),
             status: 503,
         }
@@ -140,6 +143,8 @@ pub struct ChatMessagesResponse {
 }
 
 /// 流式模式分块数据事件
+// 事件按值匹配，不对大的 workflow 事件装箱
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "event", rename_all = "snake_case")]
 pub enum SseMessageEvent {
@@ -244,6 +249,104 @@ pub enum SseMessageEvent {
         #[serde(flatten)]
         extra: HashMap<String, JsonValue>,
     },
+    /// 迭代节点开始
+    IterationStarted {
+        /// 消息基础信息
+        #[serde(flatten)]
+        base: Option<MessageBase>,
+        /// 任务 ID，用于请求跟踪和下方的停止响应接口
+        task_id: String,
+        /// workflow 执行 ID
+        workflow_run_id: String,
+        /// 迭代详细内容
+        data: IterationStartedData,
+        #[serde(flatten)]
+        extra: HashMap<String, JsonValue>,
+    },
+    /// 迭代节点进入下一轮
+    IterationNext {
+        /// 消息基础信息
+        #[serde(flatten)]
+        base: Option<MessageBase>,
+        /// 任务 ID，用于请求跟踪和下方的停止响应接口
+        task_id: String,
+        /// workflow 执行 ID
+        workflow_run_id: String,
+        /// 迭代详细内容
+        data: IterationNextData,
+        #[serde(flatten)]
+        extra: HashMap<String, JsonValue>,
+    },
+    /// 迭代节点结束，成功失败同一事件中不同状态
+    IterationCompleted {
+        /// 消息基础信息
+        #[serde(flatten)]
+        base: Option<MessageBase>,
+        /// 任务 ID，用于请求跟踪和下方的停止响应接口
+        task_id: String,
+        /// workflow 执行 ID
+        workflow_run_id: String,
+        /// 迭代详细内容
+        data: IterationCompletedData,
+        #[serde(flatten)]
+        extra: HashMap<String, JsonValue>,
+    },
+    /// 循环节点开始
+    LoopStarted {
+        /// 消息基础信息
+        #[serde(flatten)]
+        base: Option<MessageBase>,
+        /// 任务 ID，用于请求跟踪和下方的停止响应接口
+        task_id: String,
+        /// workflow 执行 ID
+        workflow_run_id: String,
+        /// 循环详细内容
+        data: IterationStartedData,
+        #[serde(flatten)]
+        extra: HashMap<String, JsonValue>,
+    },
+    /// 循环节点进入下一轮
+    LoopNext {
+        /// 消息基础信息
+        #[serde(flatten)]
+        base: Option<MessageBase>,
+        /// 任务 ID，用于请求跟踪和下方的停止响应接口
+        task_id: String,
+        /// workflow 执行 ID
+        workflow_run_id: String,
+        /// 循环详细内容
+        data: IterationNextData,
+        #[serde(flatten)]
+        extra: HashMap<String, JsonValue>,
+    },
+    /// 循环节点结束，成功失败同一事件中不同状态
+    LoopCompleted {
+        /// 消息基础信息
+        #[serde(flatten)]
+        base: Option<MessageBase>,
+        /// 任务 ID，用于请求跟踪和下方的停止响应接口
+        task_id: String,
+        /// workflow 执行 ID
+        workflow_run_id: String,
+        /// 循环详细内容
+        data: IterationCompletedData,
+        #[serde(flatten)]
+        extra: HashMap<String, JsonValue>,
+    },
+    /// workflow 输出的文本块，如 LLM 节点流式输出到结束节点或直接回复节点的文本
+    TextChunk {
+        /// 消息基础信息
+        #[serde(flatten)]
+        base: Option<MessageBase>,
+        /// 任务 ID，用于请求跟踪和下方的停止响应接口
+        task_id: String,
+        /// workflow 执行 ID
+        workflow_run_id: String,
+        /// 文本块内容
+        data: TextChunkData,
+        #[serde(flatten)]
+        extra: HashMap<String, JsonValue>,
+    },
     /// workflow 执行结束，成功失败同一事件中不同状态
     WorkflowFinished {
         /// 消息基础信息
@@ -312,6 +415,36 @@ pub enum SseMessageEvent {
     },
     // 每 10s 一次的 ping 事件，保持连接存活。
     Ping,
+    /// 暂未建模的事件，如 tts_message 等，忽略其内容。
+    #[serde(other)]
+    Unknown,
+}
+
+impl SseMessageEvent {
+    /// 消息基础信息，ping 及未知事件为 None
+    pub fn base(&self) -> Option<&MessageBase> {
+        match self {
+            SseMessageEvent::Message { base, .. }
+            | SseMessageEvent::MessageFile { base, .. }
+            | SseMessageEvent::MessageEnd { base, .. }
+            | SseMessageEvent::MessageReplace { base, .. }
+            | SseMessageEvent::WorkflowStarted { base, .. }
+            | SseMessageEvent::NodeStarted { base, .. }
+            | SseMessageEvent::NodeFinished { base, .. }
+            | SseMessageEvent::IterationStarted { base, .. }
+            | SseMessageEvent::IterationNext { base, .. }
+            | SseMessageEvent::IterationCompleted { base, .. }
+            | SseMessageEvent::LoopStarted { base, .. }
+            | SseMessageEvent::LoopNext { base, .. }
+            | SseMessageEvent::LoopCompleted { base, .. }
+            | SseMessageEvent::TextChunk { base, .. }
+            | SseMessageEvent::WorkflowFinished { base, .. }
+            | SseMessageEvent::AgentMessage { base, .. }
+            | SseMessageEvent::AgentThought { base, .. }
+            | SseMessageEvent::Error { base, .. } => base.as_ref(),
+            SseMessageEvent::Ping | SseMessageEvent::Unknown => None,
+        }
+    }
 }
 
 /// workflow 详细内容
@@ -329,6 +462,15 @@ pub struct WorkflowStartedData {
     pub created_at: u64,
 }
 
+/// 文本块内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextChunkData {
+    /// 文本内容
+    pub text: String,
+    /// 文本来源的变量选择器，如 ["llm", "text"]
+    pub from_variable_selector: Option<Vec<String>>,
+}
+
 /// workflow 执行结束详细内容
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkflowFinishedData {
@@ -356,6 +498,32 @@ pub struct WorkflowFinishedData {
     pub extra: HashMap<String, JsonValue>,
 }
 
+impl WorkflowFinishedData {
+    /// 将输出内容反序列化为指定类型
+    ///
+    /// # Returns
+    /// A `Result` containing the outputs or an error.
+    ///
+    /// # Errors
+    /// Returns an error if the workflow did not succeed or the outputs cannot be deserialized.
+    pub fn outputs_as<O>(&self) -> AnyResult<O>
+    where
+        O: DeserializeOwned,
+    {
+        match self.status {
+            FinishedStatus::Succeeded => {}
+            FinishedStatus::Failed => bail!(
+                "WorkflowFinishedData.Status failed: {}",
+                self.error.as_deref().unwrap_or_default()
+            ),
+            _ => bail!("WorkflowFinishedData.Status {:?}", self.status),
+        }
+        let outputs = self.outputs.clone().unwrap_or(JsonValue::Null);
+        serde_json::from_value(outputs)
+            .map_err(|e| anyhow!("WorkflowFinishedData.Outputs Illegal: {}", e))
+    }
+}
+
 /// node 详细内容
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NodeStartedData {
@@ -364,7 +532,7 @@ pub struct NodeStartedData {
     /// 节点 ID
     pub node_id: String,
     /// 节点类型
-    pub node_type: String,
+    pub node_type: NodeType,
     /// 节点名称
     pub title: String,
     /// 执行序号，用于展示 Tracing Node 顺序
@@ -375,6 +543,9 @@ pub struct NodeStartedData {
     pub inputs: Option<JsonValue>,
     /// 开始时间
     pub created_at: u64,
+    /// 其他字段，如迭代、循环及并行分支的 iteration_id、loop_id、parallel_id
+    #[serde(flatten)]
+    pub extra: HashMap<String, JsonValue>,
 }
 
 /// node 执行结束详细内容
@@ -384,6 +555,12 @@ pub struct NodeFinishedData {
     pub id: String,
     /// 节点 ID
     pub node_id: String,
+    /// 节点类型，旧版本服务端不返回
+    #[serde(default)]
+    pub node_type: Option<NodeType>,
+    /// 节点名称，旧版本服务端不返回
+    #[serde(default)]
+    pub title: Option<String>,
     /// 执行序号，用于展示 Tracing Node 顺序
     pub index: u32,
     /// 前置节点 ID，用于画布展示执行路径
@@ -408,8 +585,92 @@ pub struct NodeFinishedData {
     pub extra: HashMap<String, JsonValue>,
 }
 
-/// 执行结束状态
+/// 迭代或循环开始详细内容
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IterationStartedData {
+    /// 迭代或循环节点执行 ID
+    pub id: String,
+    /// 节点 ID
+    pub node_id: String,
+    /// 节点类型，iteration 或 loop
+    pub node_type: NodeType,
+    /// 节点名称
+    pub title: String,
+    /// 节点输入内容
+    #[serde(default)]
+    pub inputs: Option<JsonValue>,
+    /// 开始时间
+    pub created_at: u64,
+    /// 其他字段，如 metadata、parallel_id
+    #[serde(flatten)]
+    pub extra: HashMap<String, JsonValue>,
+}
+
+/// 迭代或循环进入下一轮详细内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IterationNextData {
+    /// 迭代或循环节点执行 ID
+    pub id: String,
+    /// 节点 ID
+    pub node_id: String,
+    /// 节点类型，iteration 或 loop
+    pub node_type: NodeType,
+    /// 节点名称
+    pub title: String,
+    /// 本轮序号，从 0 开始
+    pub index: u32,
+    /// 本轮开始时间
+    pub created_at: u64,
+    /// 其他字段，如上一轮输出 pre_iteration_output、pre_loop_output
+    #[serde(flatten)]
+    pub extra: HashMap<String, JsonValue>,
+}
+
+/// 迭代或循环结束详细内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IterationCompletedData {
+    /// 迭代或循环节点执行 ID
+    pub id: String,
+    /// 节点 ID
+    pub node_id: String,
+    /// 节点类型，iteration 或 loop
+    pub node_type: NodeType,
+    /// 节点名称
+    pub title: String,
+    /// 节点输入内容
+    #[serde(default)]
+    pub inputs: Option<JsonValue>,
+    /// 输出内容
+    #[serde(default)]
+    pub outputs: Option<JsonValue>,
+    /// 执行状态 succeeded / failed / stopped
+    pub status: FinishedStatus,
+    /// 错误原因
+    #[serde(default)]
+    pub error: Option<String>,
+    /// 耗时(s)
+    #[serde(default)]
+    pub elapsed_time: Option<f64>,
+    /// 总使用 tokens
+    #[serde(default)]
+    pub total_tokens: Option<u32>,
+    /// 执行节点元数据，含每轮耗时 iteration_duration_map、loop_duration_map
+    #[serde(default)]
+    pub execution_metadata: Option<ExecutionMetadata>,
+    /// 执行轮数
+    #[serde(default)]
+    pub steps: Option<u32>,
+    /// 开始时间
+    pub created_at: u64,
+    /// 结束时间
+    #[serde(default)]
+    pub finished_at: Option<u64>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, JsonValue>,
+}
+
+/// 执行结束状态
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum FinishedStatus {
     Running,
@@ -427,6 +688,306 @@ pub struct ExecutionMetadata {
     pub total_price: Option<String>,
     /// 货币，如 USD / RMB
     pub currency: Option<String>,
+    /// 其他元数据，如 iteration_id、iteration_index、loop_id、parallel_id
+    #[serde(flatten)]
+    pub extra: HashMap<String, JsonValue>,
+}
+
+/// 节点类型
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[serde(from = "String", into = "String")]
+pub enum NodeType {
+    /// 开始
+    Start,
+    /// 结束
+    End,
+    /// 直接回复
+    Answer,
+    /// LLM
+    Llm,
+    /// 知识检索
+    KnowledgeRetrieval,
+    /// 条件分支
+    IfElse,
+    /// 代码执行
+    Code,
+    /// 模板转换
+    TemplateTransform,
+    /// HTTP 请求
+    HttpRequest,
+    /// 工具
+    Tool,
+    /// 变量聚合器
+    VariableAggregator,
+    /// 变量赋值
+    VariableAssigner,
+    /// 迭代
+    Iteration,
+    /// 迭代开始
+    IterationStart,
+    /// 循环
+    Loop,
+    /// 循环开始
+    LoopStart,
+    /// 退出循环
+    LoopEnd,
+    /// 参数提取器
+    ParameterExtractor,
+    /// 问题分类器
+    QuestionClassifier,
+    /// Agent
+    Agent,
+    /// 文档提取器
+    DocumentExtractor,
+    /// 列表操作
+    ListOperator,
+    /// 其他类型
+    Other(String),
+}
+
+impl NodeType {
+    /// Returns the node type as it is sent by Dify.
+    pub fn as_str(&self) -> &str {
+        match self {
+            NodeType::Start => "start",
+            NodeType::End => "end",
+            NodeType::Answer => "answer",
+            NodeType::Llm => "llm",
+            NodeType::KnowledgeRetrieval => "knowledge-retrieval",
+            NodeType::IfElse => "if-else",
+            NodeType::Code => "code",
+            NodeType::TemplateTransform => "template-transform",
+            NodeType::HttpRequest => "http-request",
+            NodeType::Tool => "tool",
+            NodeType::VariableAggregator => "variable-aggregator",
+            NodeType::VariableAssigner => "assigner",
+            NodeType::Iteration => "iteration",
+            NodeType::IterationStart => "iteration-start",
+            NodeType::Loop => "loop",
+            NodeType::LoopStart => "loop-start",
+            NodeType::LoopEnd => "loop-end",
+            NodeType::ParameterExtractor => "parameter-extractor",
+            NodeType::QuestionClassifier => "question-classifier",
+            NodeType::Agent => "agent",
+            NodeType::DocumentExtractor => "document-extractor",
+            NodeType::ListOperator => "list-operator",
+            NodeType::Other(node_type) => node_type,
+        }
+    }
+
+    /// Returns whether the node contains other nodes, as iterations and loops do.
+    pub fn is_container(&self) -> bool {
+        matches!(self, NodeType::Iteration | NodeType::Loop)
+    }
+}
+
+impl From<&str> for NodeType {
+    fn from(node_type: &str) -> Self {
+        match node_type {
+            "start" => NodeType::Start,
+            "end" => NodeType::End,
+            "answer" => NodeType::Answer,
+            "llm" => NodeType::Llm,
+            "knowledge-retrieval" => NodeType::KnowledgeRetrieval,
+            "if-else" => NodeType::IfElse,
+            "code" => NodeType::Code,
+            "template-transform" => NodeType::TemplateTransform,
+            "http-request" => NodeType::HttpRequest,
+            "tool" => NodeType::Tool,
+            // variable-assigner 为旧版变量聚合器
+            "variable-aggregator" | "variable-assigner" => NodeType::VariableAggregator,
+            "assigner" => NodeType::VariableAssigner,
+            "iteration" => NodeType::Iteration,
+            "iteration-start" => NodeType::IterationStart,
+            "loop" => NodeType::Loop,
+            "loop-start" => NodeType::LoopStart,
+            "loop-end" => NodeType::LoopEnd,
+            "parameter-extractor" => NodeType::ParameterExtractor,
+            "question-classifier" => NodeType::QuestionClassifier,
+            "agent" => NodeType::Agent,
+            "document-extractor" => NodeType::DocumentExtractor,
+            "list-operator" => NodeType::ListOperator,
+            other => NodeType::Other(other.into()),
+        }
+    }
+}
+
+impl From<String> for NodeType {
+    fn from(node_type: String) -> Self {
+        node_type.as_str().into()
+    }
+}
+
+impl From<NodeType> for String {
+    fn from(node_type: NodeType) -> Self {
+        node_type.as_str().into()
+    }
+}
+
+impl Default for NodeType {
+    fn default() -> Self {
+        NodeType::Other(String::new())
+    }
+}
+
+impl Display for NodeType {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// LLM 节点的模型用量
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NodeUsage {
+    /// 提示 tokens
+    pub prompt_tokens: u32,
+    /// 补全 tokens
+    pub completion_tokens: u32,
+    /// 总使用 tokens
+    pub total_tokens: u32,
+    /// 总费用
+    pub total_price: Option<String>,
+    /// 货币，如 USD / RMB
+    pub currency: Option<String>,
+    /// 延迟(s)
+    pub latency: Option<f64>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, JsonValue>,
+}
+
+/// LLM 节点的输出
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LlmNodeOutputs {
+    /// 生成的文本
+    #[serde(default)]
+    pub text: String,
+    /// 模型用量
+    pub usage: Option<NodeUsage>,
+    /// 结束原因，如 stop / length
+    pub finish_reason: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, JsonValue>,
+}
+
+/// 知识检索节点的检索结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetrievalResult {
+    /// 分段内容
+    #[serde(default)]
+    pub content: String,
+    /// 文档标题
+    pub title: Option<String>,
+    /// 元数据，如 dataset_id、document_id、segment_id、score
+    #[serde(default)]
+    pub metadata: HashMap<String, JsonValue>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, JsonValue>,
+}
+
+/// 知识检索节点的输出
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetrievalNodeOutputs {
+    /// 检索结果
+    #[serde(default)]
+    pub result: Vec<RetrievalResult>,
+}
+
+/// 问题分类器节点的输出
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClassifierNodeOutputs {
+    /// 分类名称
+    #[serde(default)]
+    pub class_name: String,
+    /// 分类 ID
+    pub class_id: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, JsonValue>,
+}
+
+/// HTTP 请求节点的输出
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HttpNodeOutputs {
+    /// 响应状态码
+    pub status_code: u16,
+    /// 响应体
+    #[serde(default)]
+    pub body: String,
+    /// 响应头
+    #[serde(default)]
+    pub headers: HashMap<String, JsonValue>,
+    /// 响应文件
+    #[serde(default)]
+    pub files: Vec<JsonValue>,
+}
+
+impl NodeFinishedData {
+    /// Deserializes the outputs of the node.
+    ///
+    /// # Returns
+    /// A `Result` containing the outputs or an error if they are missing or malformed.
+    ///
+    /// # Errors
+    /// Returns an error if the outputs are missing or do not match the type.
+    pub fn outputs_as<T: DeserializeOwned>(&self) -> AnyResult<T> {
+        let outputs = self
+            .outputs
+            .clone()
+            .ok_or_else(|| anyhow!("NodeFinishedData.Outputs Illegal"))?;
+        serde_json::from_value(outputs)
+            .map_err(|e| anyhow!("NodeFinishedData.Outputs Illegal: {}", e))
+    }
+
+    /// Deserializes the process data of the node.
+    ///
+    /// # Returns
+    /// A `Result` containing the process data or an error if it is missing or malformed.
+    ///
+    /// # Errors
+    /// Returns an error if the process data is missing or does not match the type.
+    pub fn process_data_as<T: DeserializeOwned>(&self) -> AnyResult<T> {
+        let process_data = self
+            .process_data
+            .clone()
+            .ok_or_else(|| anyhow!("NodeFinishedData.ProcessData Illegal"))?;
+        serde_json::from_value(process_data)
+            .map_err(|e| anyhow!("NodeFinishedData.ProcessData Illegal: {}", e))
+    }
+
+    /// Returns whether the node is of the type, assumed when the server does not send it.
+    fn is_type(&self, node_type: NodeType) -> bool {
+        self.node_type.as_ref().is_none_or(|t| *t == node_type)
+    }
+
+    /// Returns the outputs of an LLM node, `None` for other nodes or malformed outputs.
+    pub fn llm_outputs(&self) -> Option<LlmNodeOutputs> {
+        self.is_type(NodeType::Llm)
+            .then(|| self.outputs_as().ok())
+            .flatten()
+    }
+
+    /// Returns the results of a knowledge retrieval node,
+    /// `None` for other nodes or malformed outputs.
+    pub fn retrieval_outputs(&self) -> Option<RetrievalNodeOutputs> {
+        self.is_type(NodeType::KnowledgeRetrieval)
+            .then(|| self.outputs_as().ok())
+            .flatten()
+    }
+
+    /// Returns the outputs of a question classifier node,
+    /// `None` for other nodes or malformed outputs.
+    pub fn classifier_outputs(&self) -> Option<ClassifierNodeOutputs> {
+        self.is_type(NodeType::QuestionClassifier)
+            .then(|| self.outputs_as().ok())
+            .flatten()
+    }
+
+    /// Returns the outputs of an HTTP request node, `None` for other nodes or malformed outputs.
+    pub fn http_outputs(&self) -> Option<HttpNodeOutputs> {
+        self.is_type(NodeType::HttpRequest)
+            .then(|| self.outputs_as().ok())
+            .flatten()
+    }
 }
 
 /// 应用类型
@@ -523,6 +1084,62 @@ pub struct ConversationsResponse {
     pub data: Vec<ConversationData>,
 }
 
+/// 获取对话变量的响应
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConversationVariablesResponse {
+    /// 返回条数，若传入超过系统限制，返回系统限制数量
+    pub limit: u32,
+    /// 是否存在下一页
+    pub has_more: bool,
+    /// 对话变量列表
+    pub data: Vec<ConversationVariable>,
+}
+
+/// 对话变量
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConversationVariable {
+    /// 变量 ID
+    pub id: String,
+    /// 变量名称
+    pub name: String,
+    /// 变量值类型，如 string, number, object, secret, array[string]
+    pub value_type: String,
+    /// 变量值
+    pub value: JsonValue,
+    /// 变量描述
+    #[serde(default)]
+    pub description: Option<String>,
+    /// 创建时间
+    #[serde(default)]
+    pub created_at: u64,
+    /// 更新时间
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+impl ConversationVariable {
+    /// 将变量值反序列化为指定类型
+    /// 以 JSON 字符串返回的对象或数组值会先被解析。
+    ///
+    /// # Returns
+    /// A `Result` containing the value or an error.
+    pub fn value_as<T>(&self) -> AnyResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        let error = match serde_json::from_value(self.value.clone()) {
+            Ok(value) => return Ok(value),
+            Err(e) => e,
+        };
+        if let Some(text) = self.value.as_str() {
+            if let Ok(value) = serde_json::from_str(text) {
+                return Ok(value);
+            }
+        }
+        bail!("ConversationVariable.Value Illegal: {}", error)
+    }
+}
+
 /// 会话数据
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ConversationData {
@@ -531,68 +1148,124 @@ pub struct ConversationData {
     /// 会话名称，默认为会话中用户最开始问题的截取。
     pub name: String,
     /// 用户输入参数。
-    pub inputs: HashMap<String, String>,
+    pub inputs: HashMap<String, JsonValue>,
     /// 开场白
     pub introduction: String,
     /// 创建时间
     pub created_at: u64,
 }
 
-#[serde_as]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 /// 获取应用配置信息的响应
 pub struct ParametersResponse {
     /// 开场白
+    #[serde(default)]
     pub opening_statement: String,
     /// 开场推荐问题列表
+    #[serde(default)]
     pub suggested_questions: Vec<String>,
     /// 启用回答后给出推荐问题。
+    #[serde(default)]
     pub suggested_questions_after_answer: ParameterSuggestedQuestionsAfterAnswer,
     /// 语音转文本
+    #[serde(default)]
     pub speech_to_text: ParameterSpeechToText,
+    /// 文本转语音
+    #[serde(default)]
+    pub text_to_speech: ParameterTextToSpeech,
     /// 引用和归属
+    #[serde(default)]
     pub retriever_resource: ParameterRetrieverResource,
     /// 标记回复
+    #[serde(default)]
     pub annotation_reply: ParameterAnnotationReply,
+    /// 更多类似的
+    #[serde(default)]
+    pub more_like_this: ParameterMoreLikeThis,
+    /// 敏感词规避
+    #[serde(default)]
+    pub sensitive_word_avoidance: ParameterSensitiveWordAvoidance,
     /// 用户输入表单配置
+    #[serde(default)]
     pub user_input_form: Vec<ParameterUserInputFormItem>,
     /// 文件上传配置
-    #[serde_as(as = "EnumMap")]
-    pub file_upload: Vec<ParameterFileUploadItem>,
+    #[serde(default)]
+    pub file_upload: ParameterFileUpload,
+    /// 系统参数
+    #[serde(default)]
     pub system_parameters: SystemParameters,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 /// 启用回答后给出推荐问题。
 pub struct ParameterSuggestedQuestionsAfterAnswer {
     /// 是否开启
     pub enabled: bool,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 /// 语音转文本
 pub struct ParameterSpeechToText {
     /// 是否开启
     pub enabled: bool,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+/// 文本转语音
+pub struct ParameterTextToSpeech {
+    /// 是否开启
+    #[serde(default)]
+    pub enabled: bool,
+    /// 声音类型
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voice: Option<String>,
+    /// 语言
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// 自动播放，`enabled` 或 `disabled`
+    #[serde(default, rename = "autoPlay", skip_serializing_if = "Option::is_none")]
+    pub auto_play: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 /// 引用和归属
 pub struct ParameterRetrieverResource {
     /// 是否开启
     pub enabled: bool,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 /// 标记回复
 pub struct ParameterAnnotationReply {
     /// 是否开启
     pub enabled: bool,
 }
 
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+/// 更多类似的
+pub struct ParameterMoreLikeThis {
+    /// 是否开启
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+/// 敏感词规避
+pub struct ParameterSensitiveWordAvoidance {
+    /// 是否开启
+    #[serde(default)]
+    pub enabled: bool,
+    /// 审查类型，如 keywords, openai_moderation, api
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+    /// 审查配置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub configs: Option<JsonValue>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 /// 用户输入表单配置
+/// 未知类型的控件会保留为 `Other`，而不会导致整个响应解析失败。
 pub enum ParameterUserInputFormItem {
     /// 文本输入控件
     #[serde(rename = "text-input")]
@@ -603,6 +1276,15 @@ pub enum ParameterUserInputFormItem {
         variable: String,
         /// 是否必填
         required: bool,
+        /// 最大长度
+        #[serde(default)]
+        max_length: Option<u32>,
+        /// 默认值
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default: Option<JsonValue>,
+        /// 是否隐藏
+        #[serde(default)]
+        hide: bool,
     },
     /// 段落文本输入控件
     Paragraph {
@@ -612,6 +1294,15 @@ pub enum ParameterUserInputFormItem {
         variable: String,
         /// 是否必填
         required: bool,
+        /// 最大长度
+        #[serde(default)]
+        max_length: Option<u32>,
+        /// 默认值
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default: Option<JsonValue>,
+        /// 是否隐藏
+        #[serde(default)]
+        hide: bool,
     },
     /// 数字输入空间
     Number {
@@ -621,7 +1312,14 @@ pub enum ParameterUserInputFormItem {
         variable: String,
         /// 是否必填
         required: bool,
+        /// 默认值
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default: Option<JsonValue>,
+        /// 是否隐藏
+        #[serde(default)]
+        hide: bool,
     },
+    /// 下拉控件
     Select {
         /// 控件展示标签名
         label: String,
@@ -631,37 +1329,186 @@ pub enum ParameterUserInputFormItem {
         required: bool,
         /// 选项值
         options: Vec<String>,
+        /// 默认值
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default: Option<JsonValue>,
+        /// 是否隐藏
+        #[serde(default)]
+        hide: bool,
     },
-}
-
-#[derive(Debug, Clone, Deserialize, Serialize)]
-#[serde(rename_all = "snake_case")]
-/// 文件上传配置
-pub enum ParameterFileUploadItem {
-    /// 当前仅支持图片类型
-    Image {
+    /// 复选框控件
+    Checkbox {
+        /// 控件展示标签名
+        label: String,
+        /// 控件 ID
+        variable: String,
+        /// 是否必填
+        required: bool,
+        /// 默认值
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default: Option<JsonValue>,
+        /// 是否隐藏
+        #[serde(default)]
+        hide: bool,
+    },
+    /// 单文件控件
+    File {
+        /// 控件展示标签名
+        label: String,
+        /// 控件 ID
+        variable: String,
+        /// 是否必填
+        required: bool,
+        /// 允许的文件类型
+        #[serde(default)]
+        allowed_file_types: Vec<FileType>,
+        /// 允许的文件扩展名，文件类型为 `custom` 时有效
+        #[serde(default)]
+        allowed_file_extensions: Vec<String>,
+        /// 允许的传递方式
+        #[serde(default)]
+        allowed_file_upload_methods: Vec<TransferMethod>,
+        /// 是否隐藏
+        #[serde(default)]
+        hide: bool,
+    },
+    /// 文件列表控件
+    #[serde(rename = "file-list")]
+    FileList {
+        /// 控件展示标签名
+        label: String,
+        /// 控件 ID
+        variable: String,
+        /// 是否必填
+        required: bool,
+        /// 最大文件数
+        #[serde(default)]
+        max_length: Option<u32>,
+        /// 允许的文件类型
+        #[serde(default)]
+        allowed_file_types: Vec<FileType>,
+        /// 允许的文件扩展名，文件类型为 `custom` 时有效
+        #[serde(default)]
+        allowed_file_extensions: Vec<String>,
+        /// 允许的传递方式
+        #[serde(default)]
+        allowed_file_upload_methods: Vec<TransferMethod>,
+        /// 是否隐藏
+        #[serde(default)]
+        hide: bool,
+    },
+    /// 外部数据工具，变量值由服务端通过工具获取
+    #[serde(alias = "external-data-tool")]
+    ExternalDataTool {
+        /// 控件展示标签名
+        #[serde(default)]
+        label: String,
+        /// 控件 ID
+        variable: String,
+        /// 是否必填
+        #[serde(default)]
+        required: bool,
+        /// 工具类型，如 api
+        #[serde(default, rename = "type")]
+        type_: String,
         /// 是否开启
+        #[serde(default)]
         enabled: bool,
-        /// 图片数量限制，默认 3
-        number_limits: u32,
-        /// 传递方式
-        transfer_methods: Vec<TransferMethod>,
+        /// 工具配置
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        config: Option<JsonValue>,
     },
+    /// 未知类型的控件，保留原始配置，如 `{"<type>": {...}}`
+    #[serde(untagged)]
+    Other(JsonValue),
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+/// 文件上传配置
+pub struct ParameterFileUpload {
+    /// 图片上传配置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<ParameterImageUpload>,
+    /// 是否开启文件上传
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// 允许的文件类型
+    #[serde(default)]
+    pub allowed_file_types: Vec<FileType>,
+    /// 允许的文件扩展名
+    #[serde(default)]
+    pub allowed_file_extensions: Vec<String>,
+    /// 允许的传递方式
+    #[serde(default)]
+    pub allowed_file_upload_methods: Vec<TransferMethod>,
+    /// 文件数量限制
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub number_limits: Option<u32>,
+    /// 其他配置，如 `fileUploadConfig`
+    #[serde(flatten)]
+    pub extra: HashMap<String, JsonValue>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+/// 图片上传配置
+pub struct ParameterImageUpload {
+    /// 是否开启
+    #[serde(default)]
+    pub enabled: bool,
+    /// 图片数量限制，默认 3
+    #[serde(default)]
+    pub number_limits: u32,
+    /// 图片解析精度，如 high, low
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// 传递方式
+    #[serde(default)]
+    pub transfer_methods: Vec<TransferMethod>,
 }
 
 /// 文件传递方式
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum TransferMethod {
     RemoteUrl,
     LocalFile,
+    /// 工具生成的文件
+    ToolFile,
 }
 
 /// 系统参数
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// 大小限制可能以数字或字符串返回，均可解析。
+#[serde_as]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct SystemParameters {
     /// 图片文件上传大小限制（MB）
+    #[serde(default, deserialize_with = "string_or_number")]
     pub image_file_size_limit: String,
+    /// 视频文件上传大小限制（MB）
+    #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video_file_size_limit: Option<u64>,
+    /// 音频文件上传大小限制（MB）
+    #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_file_size_limit: Option<u64>,
+    /// 文件上传大小限制（MB）
+    #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_size_limit: Option<u64>,
+    /// 工作流文件上传数量限制
+    #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workflow_file_upload_limit: Option<u64>,
+}
+
+/// 将数字或字符串解析为字符串
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match JsonValue::deserialize(deserializer)? {
+        JsonValue::String(s) => Ok(s),
+        JsonValue::Null => Ok(String::new()),
+        value => Ok(value.to_string()),
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -670,6 +1517,195 @@ pub struct MetaResponse {
     pub tool_icons: HashMap<String, ToolIcon>,
 }
 
+/// 获取 workflow 日志的响应
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WorkflowLogsResponse {
+    /// 当前页码
+    pub page: u32,
+    /// 每页条数
+    pub limit: u32,
+    /// 总条数
+    pub total: u64,
+    /// 是否存在下一页
+    pub has_more: bool,
+    /// 日志列表
+    pub data: Vec<WorkflowLog>,
+}
+
+/// workflow 日志
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WorkflowLog {
+    /// 日志 ID
+    pub id: String,
+    /// workflow 执行信息
+    pub workflow_run: WorkflowLogRun,
+    /// 来源，如 service-api, web-app
+    #[serde(default)]
+    pub created_from: String,
+    /// 创建者角色，end_user 终端用户，account 管理员
+    #[serde(default)]
+    pub created_by_role: String,
+    /// 创建者管理员账号
+    #[serde(default)]
+    pub created_by_account: Option<WorkflowLogAccount>,
+    /// 创建者终端用户
+    #[serde(default)]
+    pub created_by_end_user: Option<WorkflowLogEndUser>,
+    /// 创建时间
+    pub created_at: u64,
+}
+
+/// workflow 日志中的执行信息
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WorkflowLogRun {
+    /// workflow 执行 ID
+    pub id: String,
+    /// workflow 版本
+    #[serde(default)]
+    pub version: Option<String>,
+    /// 执行状态 running / succeeded / failed / stopped
+    pub status: FinishedStatus,
+    /// 错误原因
+    #[serde(default)]
+    pub error: Option<String>,
+    /// 耗时(s)
+    #[serde(default)]
+    pub elapsed_time: Option<f64>,
+    /// 总使用 tokens
+    #[serde(default)]
+    pub total_tokens: Option<u32>,
+    /// 总步数
+    #[serde(default)]
+    pub total_steps: u32,
+    /// 开始时间
+    pub created_at: u64,
+    /// 结束时间
+    #[serde(default)]
+    pub finished_at: Option<u64>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, JsonValue>,
+}
+
+/// workflow 日志中的管理员账号
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WorkflowLogAccount {
+    /// 账号 ID
+    pub id: String,
+    /// 名称
+    #[serde(default)]
+    pub name: String,
+    /// 邮箱
+    #[serde(default)]
+    pub email: String,
+}
+
+/// workflow 日志中的终端用户
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WorkflowLogEndUser {
+    /// 终端用户 ID
+    pub id: String,
+    /// 终端用户类型，如 service_api
+    #[serde(default, rename = "type")]
+    pub type_: String,
+    /// 是否匿名
+    #[serde(default)]
+    pub is_anonymous: bool,
+    /// 会话 ID，即请求中的 user 标识
+    #[serde(default)]
+    pub session_id: String,
+}
+
+/// 获取应用基本信息的响应
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct InfoResponse {
+    /// 应用名称
+    pub name: String,
+    /// 应用描述
+    #[serde(default)]
+    pub description: String,
+    /// 应用标签
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 应用类型
+    pub mode: AppMode,
+    /// 作者名称
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_name: Option<String>,
+}
+
+/// 获取应用 WebApp 设置的响应
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SiteResponse {
+    /// WebApp 名称
+    pub title: String,
+    /// 聊天颜色主题，hex 格式
+    pub chat_color_theme: Option<String>,
+    /// 聊天颜色主题是否反转
+    pub chat_color_theme_inverted: bool,
+    /// 图标类型，emoji 表情，image 图片
+    pub icon_type: Option<String>,
+    /// 图标，emoji 表情或图片 ID
+    pub icon: Option<String>,
+    /// 背景色，hex 格式
+    pub icon_background: Option<String>,
+    /// 图标地址
+    pub icon_url: Option<String>,
+    /// 描述
+    pub description: Option<String>,
+    /// 版权信息
+    pub copyright: Option<String>,
+    /// 隐私政策链接
+    pub privacy_policy: Option<String>,
+    /// 自定义免责声明
+    pub custom_disclaimer: Option<String>,
+    /// 默认语言
+    pub default_language: Option<String>,
+    /// 是否显示工作流详情
+    pub show_workflow_steps: bool,
+    /// 是否使用 WebApp 图标替换回复中的机器人图标
+    pub use_icon_as_answer_icon: bool,
+}
+
+/// 获取应用反馈列表的响应
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AppFeedbacksResponse {
+    /// 反馈列表
+    pub data: Vec<AppFeedback>,
+}
+
+/// 应用反馈
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AppFeedback {
+    /// 反馈 ID
+    pub id: String,
+    /// 应用 ID
+    pub app_id: String,
+    /// 会话 ID
+    pub conversation_id: String,
+    /// 消息 ID
+    pub message_id: String,
+    /// 点赞 like / 点踩 dislike
+    pub rating: Feedback,
+    /// 反馈内容
+    #[serde(default)]
+    pub content: Option<String>,
+    /// 反馈来源，user 终端用户，admin 管理员
+    pub from_source: String,
+    /// 终端用户 ID
+    #[serde(default)]
+    pub from_end_user_id: Option<String>,
+    /// 管理员账号 ID
+    #[serde(default)]
+    pub from_account_id: Option<String>,
+    /// 创建时间
+    #[serde(deserialize_with = "string_or_number")]
+    pub created_at: String,
+    /// 更新时间
+    #[serde(deserialize_with = "string_or_number")]
+    pub updated_at: String,
+}
+
 /// 工具图标
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(untagged)]
@@ -715,6 +1751,33 @@ pub struct WorkflowsRunResponse {
     pub data: WorkflowFinishedData,
 }
 
+impl WorkflowsRunResponse {
+    /// 执行的 workflow 版本 ID
+    pub fn workflow_id(&self) -> &str {
+        &self.data.workflow_id
+    }
+
+    /// 将输出内容反序列化为指定类型
+    ///
+    /// # Returns
+    /// A `Result` containing the outputs or an error.
+    pub fn outputs_as<O>(&self) -> AnyResult<O>
+    where
+        O: DeserializeOwned,
+    {
+        self.data.outputs_as()
+    }
+}
+
+/// 执行 workflow 的类型化响应
+#[derive(Debug, Clone)]
+pub struct WorkflowsRunTypedResponse<O> {
+    /// 执行 workflow 响应
+    pub response: WorkflowsRunResponse,
+    /// 反序列化后的输出内容
+    pub outputs: O,
+}
+
 /// 文本生成的响应
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompletionMessagesResponse {
@@ -769,13 +1832,14 @@ where
 
         loop {
             match this.stream.as_mut().poll_next(cx) {
-                Poll::Ready(Some(Ok(event))) => match event.event.as_str() {
-                    "message" => match serde_json::from_str::<SseMessageEvent>(&event.data) {
-                        Ok(msg_event) => return Poll::Ready(Some(Ok(msg_event))),
-                        Err(e) => return Poll::Ready(Some(Err(e.into()))),
-                    },
-                    _ => {}
-                },
+                Poll::Ready(Some(Ok(event))) => {
+                    if event.event == "message" {
+                        match serde_json::from_str::<SseMessageEvent>(&event.data) {
+                            Ok(msg_event) => return Poll::Ready(Some(Ok(msg_event))),
+                            Err(e) => return Poll::Ready(Some(Err(e.into()))),
+                        }
+                    }
+                }
                 Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(anyhow!(e.to_string())))),
                 Poll::Ready(None) => {
                     *this.terminated = true;
@@ -799,6 +1863,21 @@ where
     }
 }
 
+/// 解析响应，无法解析的错误响应以实际的 HTTP 状态码为状态
+pub(crate) fn parse_response_with_status<T>(status: u16, text: &str) -> AnyResult<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    parse_response(text).map_err(|e| match e.downcast::<ErrorResponse>() {
+        Ok(err) if err.code == ErrorResponse::UNKNOWN_CODE => anyhow!(ErrorResponse {
+            status: status.into(),
+            ..err
+        }),
+        Ok(err) => anyhow!(err),
+        Err(e) => e,
+    })
+}
+
 /// 解析错误响应
 pub(crate) fn parse_error_response<T>(text: &str) -> AnyResult<T> {
     if let Ok(err) = serde_json::from_str::<ErrorResponse>(text) {
@@ -0,0 +1,194 @@
+//! This module provides conversion of the app parameters into a JSON Schema.
+//!
+//! The `user_input_form` of the [`ParametersResponse`] is converted into a JSON Schema
+//! (draft 2020-12) object describing the `inputs` of the app, so the app can be rendered as a form
+//! or exposed as a typed tool to LLM tool-calling frameworks.
+//!
+//! Text and paragraph variables become strings with `maxLength`, numbers become numbers,
+//! selects become string enums, checkboxes become booleans, and files become objects
+//! in the shape of [`FileInput`](crate::request::FileInput).
+//! Hidden variables are described with their default values but are never required,
+//! so inputs filled in by the app are still accepted. External data tools and unknown
+//! form items are left out.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use dify_client::{request, Client};
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let client = Client::new("https://api.dify.ai", "API_KEY");
+//!     let params = client
+//!         .api()
+//!         .parameters(request::ParametersRequest { user: "afa".into() })
+//!         .await?;
+//!     let schema = params.inputs_schema();
+//!     println!("{}", serde_json::to_string_pretty(&schema)?);
+//!     Ok(())
+//! }
+//! ```
+use super::{
+    request::FileType,
+    response::{ParameterUserInputFormItem, ParametersResponse, TransferMethod},
+};
+use serde_json::{json, Map, Value as JsonValue};
+
+/// The JSON Schema dialect of the generated schemas.
+pub const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Returns the JSON Schema of a file variable.
+///
+/// # Arguments
+/// * `label` - The label of the variable.
+/// * `allowed_file_types` - The allowed file types, any type if empty.
+/// * `allowed_file_upload_methods` - The allowed transfer methods, any method if empty.
+fn file_schema(
+    label: &str,
+    allowed_file_types: &[FileType],
+    allowed_file_upload_methods: &[TransferMethod],
+) -> JsonValue {
+    let mut type_ = json!({"type": "string"});
+    if !allowed_file_types.is_empty() {
+        type_["enum"] = json!(allowed_file_types);
+    }
+    let methods = if allowed_file_upload_methods.is_empty() {
+        json!(["remote_url", "local_file"])
+    } else {
+        json!(allowed_file_upload_methods)
+    };
+    json!({
+        "title": label,
+        "type": "object",
+        "properties": {
+            "transfer_method": {"type": "string", "enum": methods},
+            "type": type_,
+            "url": {"type": "string", "format": "uri"},
+            "upload_file_id": {"type": "string"},
+        },
+        "required": ["transfer_method", "type"],
+    })
+}
+
+/// Inserts the default value into the schema, if any.
+fn with_default(mut schema: JsonValue, default: &Option<JsonValue>) -> JsonValue {
+    if let Some(default) = default.as_ref().filter(|d| !d.is_null()) {
+        schema["default"] = default.clone();
+    }
+    schema
+}
+
+impl ParameterUserInputFormItem {
+    /// Returns whether the form item is hidden from users.
+    pub fn hidden(&self) -> bool {
+        match self {
+            ParameterUserInputFormItem::TextInput { hide, .. }
+            | ParameterUserInputFormItem::Paragraph { hide, .. }
+            | ParameterUserInputFormItem::Number { hide, .. }
+            | ParameterUserInputFormItem::Select { hide, .. }
+            | ParameterUserInputFormItem::Checkbox { hide, .. }
+            | ParameterUserInputFormItem::File { hide, .. }
+            | ParameterUserInputFormItem::FileList { hide, .. } => *hide,
+            ParameterUserInputFormItem::ExternalDataTool { .. }
+            | ParameterUserInputFormItem::Other(_) => false,
+        }
+    }
+
+    /// Returns the JSON Schema of the variable of the form item.
+    ///
+    /// # Returns
+    /// The schema, or `None` for external data tools and unknown form items.
+    pub fn json_schema(&self) -> Option<JsonValue> {
+        let schema = match self {
+            ParameterUserInputFormItem::TextInput {
+                label,
+                max_length,
+                default,
+                ..
+            }
+            | ParameterUserInputFormItem::Paragraph {
+                label,
+                max_length,
+                default,
+                ..
+            } => {
+                let mut schema = json!({"title": label, "type": "string"});
+                if let Some(max_length) = max_length.filter(|m| *m > 0) {
+                    schema["maxLength"] = json!(max_length);
+                }
+                with_default(schema, default)
+            }
+            ParameterUserInputFormItem::Number { label, default, .. } => {
+                with_default(json!({"title": label, "type": "number"}), default)
+            }
+            ParameterUserInputFormItem::Select {
+                label,
+                options,
+                default,
+                ..
+            } => with_default(
+                json!({"title": label, "type": "string", "enum": options}),
+                default,
+            ),
+            ParameterUserInputFormItem::Checkbox { label, default, .. } => {
+                with_default(json!({"title": label, "type": "boolean"}), default)
+            }
+            ParameterUserInputFormItem::File {
+                label,
+                allowed_file_types,
+                allowed_file_upload_methods,
+                ..
+            } => file_schema(label, allowed_file_types, allowed_file_upload_methods),
+            ParameterUserInputFormItem::FileList {
+                label,
+                max_length,
+                allowed_file_types,
+                allowed_file_upload_methods,
+                ..
+            } => {
+                let mut schema = json!({
+                    "title": label,
+                    "type": "array",
+                    "items": file_schema(label, allowed_file_types, allowed_file_upload_methods),
+                });
+                if let Some(max_length) = max_length.filter(|m| *m > 0) {
+                    schema["maxItems"] = json!(max_length);
+                }
+                schema
+            }
+            ParameterUserInputFormItem::ExternalDataTool { .. }
+            | ParameterUserInputFormItem::Other(_) => return None,
+        };
+        Some(schema)
+    }
+}
+
+impl ParametersResponse {
+    /// Returns the JSON Schema of the inputs of the app.
+    /// The schema is an object with a property for each variable
+    /// and lists the required visible variables in `required`.
+    ///
+    /// # Returns
+    /// The JSON Schema document.
+    pub fn inputs_schema(&self) -> JsonValue {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        for item in &self.user_input_form {
+            let Some(schema) = item.json_schema() else {
+                continue;
+            };
+            let variable = item.variable().to_string();
+            if item.required() && !item.hidden() {
+                required.push(variable.clone());
+            }
+            properties.insert(variable, schema);
+        }
+        json!({
+            "$schema": JSON_SCHEMA_DIALECT,
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+        })
+    }
+}
@@ -0,0 +1,657 @@
+//! This module provides a trace of a workflow execution built from the stream events.
+//!
+//! The [`WorkflowTrace`] consumes the events of a streaming workflow run, or of a chatflow,
+//! and records each node execution with its inputs, process data, outputs, status, error,
+//! timing and cost. Nodes of parallel branches keep their `parallel_id`.
+//!
+//! An iteration or loop node is traced from its `iteration_*` or `loop_*` events: each
+//! `iteration_next` or `loop_next` event opens a [`RoundTrace`], and the nodes executed inside
+//! the iteration or loop are nested under the round given by their `iteration_index` or
+//! `loop_index`, or under the latest round. The `iteration_completed` and `loop_completed`
+//! events finish the node and give the elapsed time of each round.
+//!
+//! The trace renders as an indented timeline with `Display`, and serializes to JSON for logs.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use dify_client::{request, trace::WorkflowTrace, Client};
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let client = Client::new("https://api.dify.ai", "API_KEY");
+//!     let data = request::WorkflowsRunRequest {
+//!         user: "afa".into(),
+//!         ..Default::default()
+//!     };
+//!     let stream = client.api().workflows_run_stream(data).await?;
+//!     let trace = WorkflowTrace::collect(stream).await?;
+//!     if trace.is_failed() {
+//!         eprintln!("{}", trace);
+//!         for node in trace.failed_nodes() {
+//!             eprintln!("{} failed: {:?}", node.title, node.error);
+//!         }
+//!     }
+//!     println!("{}", serde_json::to_string(&trace)?);
+//!     Ok(())
+//! }
+//! ```
+use super::response::{
+    ExecutionMetadata, FinishedStatus, IterationCompletedData, IterationStartedData,
+    NodeFinishedData, NodeStartedData, NodeType, SseMessageEvent,
+};
+use anyhow::Result as AnyResult;
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+/// The trace of a node execution.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct NodeTrace {
+    /// The node execution ID.
+    pub id: String,
+    /// The node ID.
+    pub node_id: String,
+    /// The node type.
+    pub node_type: NodeType,
+    /// The node title.
+    pub title: String,
+    /// The execution index.
+    pub index: u32,
+    /// The ID of the predecessor node.
+    pub predecessor_node_id: Option<String>,
+    /// The ID of the iteration or loop node containing this node.
+    pub container_node_id: Option<String>,
+    /// The index of the iteration or loop round.
+    pub round: Option<u32>,
+    /// The ID of the parallel branch.
+    pub parallel_id: Option<String>,
+    /// The inputs of the node.
+    pub inputs: Option<JsonValue>,
+    /// The process data of the node.
+    pub process_data: Option<JsonValue>,
+    /// The outputs of the node.
+    pub outputs: Option<JsonValue>,
+    /// The status of the node, `None` while running.
+    pub status: Option<FinishedStatus>,
+    /// The error of the node.
+    pub error: Option<String>,
+    /// The start time.
+    pub created_at: u64,
+    /// The elapsed time in seconds.
+    pub elapsed_time: Option<f64>,
+    /// The total tokens used.
+    pub total_tokens: Option<u32>,
+    /// The total price.
+    pub total_price: Option<f64>,
+    /// The currency of the price.
+    pub currency: Option<String>,
+    /// The rounds of this iteration or loop node.
+    pub rounds: Vec<RoundTrace>,
+}
+
+/// The trace of a round of an iteration or loop node.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RoundTrace {
+    /// The index of the round, from 0.
+    pub index: u32,
+    /// The start time.
+    pub created_at: u64,
+    /// The elapsed time in seconds.
+    pub elapsed_time: Option<f64>,
+    /// The nodes executed in this round.
+    pub nodes: Vec<NodeTrace>,
+}
+
+/// The total cost of a workflow execution.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct TraceCost {
+    /// The total tokens used.
+    pub total_tokens: u64,
+    /// The total price.
+    pub total_price: f64,
+    /// The currency of the price.
+    pub currency: Option<String>,
+}
+
+/// The trace of a workflow execution.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct WorkflowTrace {
+    /// The workflow run ID.
+    pub workflow_run_id: Option<String>,
+    /// The task ID.
+    pub task_id: Option<String>,
+    /// The workflow ID.
+    pub workflow_id: Option<String>,
+    /// The status of the workflow, `None` while running.
+    pub status: Option<FinishedStatus>,
+    /// The error of the workflow or the stream.
+    pub error: Option<String>,
+    /// The elapsed time in seconds.
+    pub elapsed_time: Option<f64>,
+    /// The outputs of the workflow.
+    pub outputs: Option<JsonValue>,
+    /// The top-level nodes in execution order.
+    pub nodes: Vec<NodeTrace>,
+}
+
+/// Returns the first non-null field of the node data or the execution metadata.
+fn extra_field(
+    extra: &HashMap<String, JsonValue>,
+    metadata: Option<&ExecutionMetadata>,
+    names: &[&str],
+) -> Option<JsonValue> {
+    names
+        .iter()
+        .flat_map(|name| [extra.get(*name), metadata.and_then(|m| m.extra.get(*name))])
+        .flatten()
+        .find(|value| !value.is_null())
+        .cloned()
+}
+
+fn extra_str(
+    extra: &HashMap<String, JsonValue>,
+    metadata: Option<&ExecutionMetadata>,
+    names: &[&str],
+) -> Option<String> {
+    extra_field(extra, metadata, names).and_then(|v| v.as_str().map(Into::into))
+}
+
+fn extra_u32(
+    extra: &HashMap<String, JsonValue>,
+    metadata: Option<&ExecutionMetadata>,
+    names: &[&str],
+) -> Option<u32> {
+    extra_field(extra, metadata, names).and_then(|v| v.as_u64().map(|n| n as u32))
+}
+
+impl NodeTrace {
+    fn started(data: &NodeStartedData) -> Self {
+        Self {
+            id: data.id.clone(),
+            node_id: data.node_id.clone(),
+            node_type: data.node_type.clone(),
+            title: data.title.clone(),
+            index: data.index,
+            predecessor_node_id: data.predecessor_node_id.clone(),
+            container_node_id: extra_str(&data.extra, None, &["iteration_id", "loop_id"]),
+            round: extra_u32(&data.extra, None, &["iteration_index", "loop_index"]),
+            parallel_id: extra_str(&data.extra, None, &["parallel_id"]),
+            inputs: data.inputs.clone(),
+            created_at: data.created_at,
+            ..Default::default()
+        }
+    }
+
+    fn container_started(data: &IterationStartedData) -> Self {
+        Self {
+            id: data.id.clone(),
+            node_id: data.node_id.clone(),
+            node_type: data.node_type.clone(),
+            title: data.title.clone(),
+            container_node_id: extra_str(&data.extra, None, &["iteration_id", "loop_id"]),
+            parallel_id: extra_str(&data.extra, None, &["parallel_id"]),
+            inputs: data.inputs.clone(),
+            created_at: data.created_at,
+            ..Default::default()
+        }
+    }
+
+    fn finish(&mut self, data: &NodeFinishedData) {
+        let metadata = data.execution_metadata.as_ref();
+        if let Some(node_type) = data.node_type.as_ref() {
+            self.node_type = node_type.clone();
+        }
+        if let Some(title) = data.title.as_ref() {
+            self.title = title.clone();
+        }
+        self.index = data.index;
+        self.predecessor_node_id = data.predecessor_node_id.clone();
+        if self.container_node_id.is_none() {
+            self.container_node_id = extra_str(&data.extra, metadata, &["iteration_id", "loop_id"]);
+        }
+        if self.round.is_none() {
+            self.round = extra_u32(&data.extra, metadata, &["iteration_index", "loop_index"]);
+        }
+        if self.parallel_id.is_none() {
+            self.parallel_id = extra_str(&data.extra, metadata, &["parallel_id"]);
+        }
+        if data.inputs.is_some() {
+            self.inputs = data.inputs.clone();
+        }
+        self.process_data = data.process_data.clone();
+        self.outputs = data.outputs.clone();
+        self.status = Some(data.status.clone());
+        self.error = data.error.clone().filter(|e| !e.is_empty());
+        self.elapsed_time = data.elapsed_time;
+        if let Some(metadata) = metadata {
+            self.total_tokens = metadata.total_tokens;
+            self.total_price = metadata.total_price.as_deref().and_then(|p| p.parse().ok());
+            self.currency = metadata.currency.clone();
+        }
+        self.finish_rounds(metadata, None);
+    }
+
+    fn complete(&mut self, data: &IterationCompletedData) {
+        let metadata = data.execution_metadata.as_ref();
+        if data.inputs.is_some() {
+            self.inputs = data.inputs.clone();
+        }
+        self.outputs = data.outputs.clone();
+        self.status = Some(data.status.clone());
+        self.error = data.error.clone().filter(|e| !e.is_empty());
+        self.elapsed_time = data.elapsed_time;
+        self.total_tokens = data.total_tokens.or(metadata.and_then(|m| m.total_tokens));
+        if let Some(metadata) = metadata {
+            self.total_price = metadata.total_price.as_deref().and_then(|p| p.parse().ok());
+            self.currency = metadata.currency.clone();
+        }
+        self.finish_rounds(metadata, data.finished_at);
+    }
+
+    /// Sets the elapsed time of the rounds from the duration map of the metadata, or else
+    /// from the start time of the next round.
+    fn finish_rounds(&mut self, metadata: Option<&ExecutionMetadata>, finished_at: Option<u64>) {
+        let durations = metadata
+            .and_then(|m| {
+                m.extra
+                    .get("iteration_duration_map")
+                    .or_else(|| m.extra.get("loop_duration_map"))
+            })
+            .and_then(JsonValue::as_object);
+        let ends: Vec<_> = self
+            .rounds
+            .iter()
+            .skip(1)
+            .map(|r| Some(r.created_at))
+            .chain([finished_at])
+            .collect();
+        for (round, end) in self.rounds.iter_mut().zip(ends) {
+            let duration = durations
+                .and_then(|d| d.get(&round.index.to_string()))
+                .and_then(JsonValue::as_f64);
+            round.elapsed_time = duration
+                .or(round.elapsed_time)
+                .or_else(|| end.map(|end| end.saturating_sub(round.created_at) as f64));
+        }
+    }
+
+    /// Returns the round by index, opening it if it is not traced yet.
+    fn round_mut(&mut self, index: u32, created_at: u64) -> &mut RoundTrace {
+        let i = match self.rounds.iter().rposition(|r| r.index == index) {
+            Some(i) => i,
+            None => {
+                self.rounds.push(RoundTrace {
+                    index,
+                    created_at,
+                    ..Default::default()
+                });
+                self.rounds.len() - 1
+            }
+        };
+        &mut self.rounds[i]
+    }
+
+    /// Returns the nodes executed inside this iteration or loop node, round by round.
+    pub fn children(&self) -> impl Iterator<Item = &NodeTrace> {
+        self.rounds.iter().flat_map(|r| &r.nodes)
+    }
+
+    /// Returns the cost of the node, the sum of its rounds for iterations and loops.
+    pub fn cost(&self) -> TraceCost {
+        if self.rounds.is_empty() {
+            return TraceCost {
+                total_tokens: self.total_tokens.unwrap_or_default() as u64,
+                total_price: self.total_price.unwrap_or_default(),
+                currency: self.currency.clone(),
+            };
+        }
+        sum_costs(self.rounds.iter().map(RoundTrace::cost))
+    }
+
+    /// Returns whether the node failed.
+    pub fn is_failed(&self) -> bool {
+        self.status == Some(FinishedStatus::Failed)
+    }
+
+    /// Finds the node execution by ID, in this node or its children.
+    fn find_mut(&mut self, id: &str) -> Option<&mut NodeTrace> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.rounds
+            .iter_mut()
+            .rev()
+            .flat_map(|r| r.nodes.iter_mut().rev())
+            .find_map(|n| n.find_mut(id))
+    }
+
+    /// Finds the latest execution of the container node, in this node or its children.
+    fn find_container_mut(&mut self, node_id: &str) -> Option<&mut NodeTrace> {
+        let found = self.rounds.iter().enumerate().rev().find_map(|(i, r)| {
+            r.nodes
+                .iter()
+                .rposition(|n| n.contains(node_id))
+                .map(|j| (i, j))
+        });
+        match found {
+            Some((i, j)) => self.rounds[i].nodes[j].find_container_mut(node_id),
+            None => (self.node_id == node_id).then_some(self),
+        }
+    }
+
+    /// Returns whether this node or its children are executions of the node.
+    fn contains(&self, node_id: &str) -> bool {
+        self.node_id == node_id || self.children().any(|n| n.contains(node_id))
+    }
+
+    fn fmt_indented(&self, f: &mut Formatter, depth: usize) -> FmtResult {
+        let status = match &self.status {
+            Some(status) => format!("{:?}", status).to_lowercase(),
+            None => "running".into(),
+        };
+        write!(
+            f,
+            "{:indent$}#{} {} [{}] {}",
+            "",
+            self.index,
+            self.title,
+            self.node_type,
+            status,
+            indent = depth * 2
+        )?;
+        if let Some(parallel_id) = self.parallel_id.as_ref() {
+            write!(f, " parallel={}", parallel_id)?;
+        }
+        if let Some(elapsed_time) = self.elapsed_time {
+            write!(f, " {:.3}s", elapsed_time)?;
+        }
+        if let Some(total_tokens) = self.total_tokens.filter(|t| *t > 0) {
+            write!(f, " {} tokens", total_tokens)?;
+        }
+        writeln!(f)?;
+        if let Some(error) = self.error.as_ref() {
+            writeln!(f, "{:indent$}error: {}", "", error, indent = depth * 2 + 2)?;
+        }
+        for round in &self.rounds {
+            round.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl RoundTrace {
+    /// Returns the cost of the round, the sum of its nodes.
+    pub fn cost(&self) -> TraceCost {
+        sum_costs(self.nodes.iter().map(NodeTrace::cost))
+    }
+
+    fn fmt_indented(&self, f: &mut Formatter, depth: usize) -> FmtResult {
+        write!(f, "{:indent$}round {}", "", self.index, indent = depth * 2)?;
+        if let Some(elapsed_time) = self.elapsed_time {
+            write!(f, " {:.3}s", elapsed_time)?;
+        }
+        let cost = self.cost();
+        if cost.total_tokens > 0 {
+            write!(f, " {} tokens", cost.total_tokens)?;
+        }
+        writeln!(f)?;
+        for node in &self.nodes {
+            node.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+fn sum_costs(costs: impl Iterator<Item = TraceCost>) -> TraceCost {
+    costs.fold(TraceCost::default(), |mut total, cost| {
+        total.total_tokens += cost.total_tokens;
+        total.total_price += cost.total_price;
+        if total.currency.is_none() {
+            total.currency = cost.currency;
+        }
+        total
+    })
+}
+
+impl WorkflowTrace {
+    /// Creates an empty trace.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collects the trace from a stream of events, until the stream ends.
+    ///
+    /// # Arguments
+    /// * `stream` - The stream of events.
+    ///
+    /// # Returns
+    /// A `Result` containing the trace or the first error of the stream.
+    pub async fn collect<S>(stream: S) -> AnyResult<Self>
+    where
+        S: Stream<Item = AnyResult<SseMessageEvent>>,
+    {
+        let mut stream = std::pin::pin!(stream);
+        let mut trace = Self::new();
+        while let Some(event) = stream.next().await {
+            trace.push(&event?);
+        }
+        Ok(trace)
+    }
+
+    /// Records an event in the trace, events unrelated to the workflow are ignored.
+    ///
+    /// # Arguments
+    /// * `event` - The event.
+    pub fn push(&mut self, event: &SseMessageEvent) {
+        match event {
+            SseMessageEvent::WorkflowStarted {
+                task_id,
+                workflow_run_id,
+                data,
+                ..
+            } => {
+                self.task_id = Some(task_id.clone());
+                self.workflow_run_id = Some(workflow_run_id.clone());
+                self.workflow_id = Some(data.workflow_id.clone());
+            }
+            SseMessageEvent::NodeStarted { data, .. } => self.insert(NodeTrace::started(data)),
+            SseMessageEvent::NodeFinished { data, .. } => match self.find_mut(&data.id) {
+                Some(node) => node.finish(data),
+                None => {
+                    // the node started before the trace, record the finished node only
+                    let mut node = NodeTrace {
+                        id: data.id.clone(),
+                        node_id: data.node_id.clone(),
+                        created_at: data.created_at,
+                        ..Default::default()
+                    };
+                    node.finish(data);
+                    self.nodes.push(node);
+                }
+            },
+            SseMessageEvent::IterationStarted { data, .. }
+            | SseMessageEvent::LoopStarted { data, .. } => {
+                match self.find_running_container_mut(&data.node_id) {
+                    Some(node) if data.inputs.is_some() => node.inputs = data.inputs.clone(),
+                    Some(_) => {}
+                    // the node_started event of the container is missing
+                    None => self.insert(NodeTrace::container_started(data)),
+                }
+            }
+            SseMessageEvent::IterationNext { data, .. }
+            | SseMessageEvent::LoopNext { data, .. } => {
+                if let Some(node) = self.find_running_container_mut(&data.node_id) {
+                    node.round_mut(data.index, data.created_at);
+                }
+            }
+            SseMessageEvent::IterationCompleted { data, .. }
+            | SseMessageEvent::LoopCompleted { data, .. } => {
+                if let Some(node) = self.find_container_mut(&data.node_id) {
+                    node.complete(data);
+                }
+            }
+            SseMessageEvent::WorkflowFinished {
+                task_id,
+                workflow_run_id,
+                data,
+                ..
+            } => {
+                self.task_id = Some(task_id.clone());
+                self.workflow_run_id = Some(workflow_run_id.clone());
+                self.workflow_id = Some(data.workflow_id.clone());
+                self.status = Some(data.status.clone());
+                self.error = data.error.clone().filter(|e| !e.is_empty());
+                self.elapsed_time = data.elapsed_time;
+                self.outputs = data.outputs.clone();
+            }
+            SseMessageEvent::Error { code, message, .. } => {
+                self.error = Some(format!("{}: {}", code, message));
+            }
+            _ => {}
+        }
+    }
+
+    /// Nests the node under the round of its container, or records it as a top-level node.
+    fn insert(&mut self, mut node: NodeTrace) {
+        let container = node
+            .container_node_id
+            .as_deref()
+            .and_then(|id| self.find_container_mut(id));
+        match container {
+            Some(container) => {
+                let index = node
+                    .round
+                    .or_else(|| container.rounds.last().map(|r| r.index))
+                    .unwrap_or_default();
+                node.round = Some(index);
+                container.round_mut(index, node.created_at).nodes.push(node);
+            }
+            None => self.nodes.push(node),
+        }
+    }
+
+    fn find_mut(&mut self, id: &str) -> Option<&mut NodeTrace> {
+        self.nodes.iter_mut().rev().find_map(|n| n.find_mut(id))
+    }
+
+    /// Finds the latest execution of the container node, if it is still running.
+    fn find_running_container_mut(&mut self, node_id: &str) -> Option<&mut NodeTrace> {
+        self.find_container_mut(node_id)
+            .filter(|n| n.status.is_none())
+    }
+
+    fn find_container_mut(&mut self, node_id: &str) -> Option<&mut NodeTrace> {
+        self.nodes
+            .iter_mut()
+            .rev()
+            .find_map(|n| n.find_container_mut(node_id))
+    }
+
+    /// Returns whether the workflow or any node failed.
+    pub fn is_failed(&self) -> bool {
+        self.status == Some(FinishedStatus::Failed) || !self.failed_nodes().is_empty()
+    }
+
+    /// Returns all node executions, parents before their children.
+    pub fn all_nodes(&self) -> Vec<&NodeTrace> {
+        fn walk<'a>(nodes: &'a [NodeTrace], all: &mut Vec<&'a NodeTrace>) {
+            for node in nodes {
+                all.push(node);
+                for round in &node.rounds {
+                    walk(&round.nodes, all);
+                }
+            }
+        }
+        let mut all = Vec::new();
+        walk(&self.nodes, &mut all);
+        all
+    }
+
+    /// Returns the failed node executions.
+    pub fn failed_nodes(&self) -> Vec<&NodeTrace> {
+        self.all_nodes()
+            .into_iter()
+            .filter(|n| n.is_failed())
+            .collect()
+    }
+
+    /// Returns the total cost of the workflow execution.
+    pub fn cost(&self) -> TraceCost {
+        sum_costs(self.nodes.iter().map(NodeTrace::cost))
+    }
+
+    /// Returns the critical path, the chain of top-level nodes from the start node
+    /// with the longest total elapsed time.
+    pub fn critical_path(&self) -> Vec<&NodeTrace> {
+        let mut latest = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            latest.insert(node.node_id.as_str(), i);
+        }
+        // the finish time and the predecessor of each node, nodes come after their predecessors
+        let mut finish: Vec<(f64, Option<usize>)> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let predecessor = node
+                .predecessor_node_id
+                .as_deref()
+                .and_then(|id| latest.get(id).copied())
+                .filter(|p| *p < finish.len());
+            let start = predecessor.map(|p| finish[p].0).unwrap_or_default();
+            finish.push((start + node.elapsed_time.unwrap_or_default(), predecessor));
+        }
+        let mut last = finish
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1 .0.total_cmp(&b.1 .0))
+            .map(|(i, _)| i);
+        let mut path = Vec::new();
+        while let Some(i) = last {
+            path.push(&self.nodes[i]);
+            last = finish[i].1;
+        }
+        path.reverse();
+        path
+    }
+}
+
+impl Display for WorkflowTrace {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let status = match &self.status {
+            Some(status) => format!("{:?}", status).to_lowercase(),
+            None => "running".into(),
+        };
+        write!(
+            f,
+            "workflow {} [{}]",
+            self.workflow_run_id.as_deref().unwrap_or("-"),
+            status
+        )?;
+        if let Some(elapsed_time) = self.elapsed_time {
+            write!(f, " {:.3}s", elapsed_time)?;
+        }
+        let cost = self.cost();
+        if cost.total_tokens > 0 {
+            write!(f, " {} tokens", cost.total_tokens)?;
+        }
+        if cost.total_price > 0.0 {
+            write!(
+                f,
+                " {:.6} {}",
+                cost.total_price,
+                cost.currency.as_deref().unwrap_or_default()
+            )?;
+        }
+        writeln!(f)?;
+        if let Some(error) = self.error.as_ref() {
+            writeln!(f, "  error: {}", error)?;
+        }
+        for node in &self.nodes {
+            node.fmt_indented(f, 1)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,8 @@
+//! This module provides helpers shared by the modules of the crate.
+
+/// Returns the 64-bit FNV-1a hash of the data, stable across platforms and releases.
+pub(crate) fn stable_hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
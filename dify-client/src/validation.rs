@@ -0,0 +1,412 @@
+//! This module provides validation of request inputs against the app parameters.
+//!
+//! The `user_input_form` of the [`ParametersResponse`] describes each variable of the app.
+//! The inputs are checked for missing required variables, select values not in the options,
+//! non-numeric numbers, non-boolean checkboxes, texts exceeding the max length and
+//! malformed or too many files, and all violations are reported at once.
+//!
+//! # Examples
+//!
+//! Validating inputs manually:
+//!
+//! ```no_run
+//! use dify_client::{request, Client};
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let client = Client::new("https://api.dify.ai", "API_KEY");
+//!     let api = client.api();
+//!     let params = api
+//!         .parameters(request::ParametersRequest { user: "afa".into() })
+//!         .await?;
+//!     let inputs = request::InputsBuilder::new().set("count", "three").build();
+//!     if let Err(e) = params.validate_inputs(&inputs) {
+//!         for violation in e.violations {
+//!             eprintln!("{}", violation);
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Validating inputs automatically before each request, with the parameters cached per app:
+//!
+//! ```no_run
+//! use dify_client::{request, validation::InputsValidator, Client};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let client = Client::new("https://api.dify.ai", "API_KEY");
+//!     let api = client.api().with_validator(InputsValidator::cached());
+//!     let data = request::WorkflowsRunRequest {
+//!         user: "afa".into(),
+//!         ..Default::default()
+//!     };
+//!     // fails with a `ValidationError` before sending if the inputs are invalid
+//!     let result = api.workflows_run(data).await;
+//!     println!("{:?}", result);
+//! }
+//! ```
+use super::{
+    api::Api,
+    request::ParametersRequest,
+    response::{ParameterUserInputFormItem, ParametersResponse},
+};
+use anyhow::Result as AnyResult;
+use serde_json::Value as JsonValue;
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::{Arc, RwLock},
+};
+
+/// A violation of an input variable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputViolation {
+    /// The variable name.
+    pub variable: String,
+    /// The kind of the violation.
+    pub kind: ViolationKind,
+}
+
+/// The kind of an input violation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViolationKind {
+    /// The required variable is missing or empty.
+    Missing,
+    /// The value is not a string.
+    NotString,
+    /// The value is not a number.
+    NotNumber,
+    /// The value is not a boolean.
+    NotBool,
+    /// The value is not a file, or a file list contains a non-file.
+    NotFile,
+    /// The value is not a list of files.
+    NotFileList,
+    /// The file list has more files than the max length.
+    TooManyFiles { count: usize, max_length: u32 },
+    /// The value is not one of the select options.
+    NotInOptions { options: Vec<String> },
+    /// The text is longer than the max length.
+    TooLong { length: usize, max_length: u32 },
+}
+
+impl Display for InputViolation {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match &self.kind {
+            ViolationKind::Missing => write!(f, "{}: required", self.variable),
+            ViolationKind::NotString => write!(f, "{}: must be a string", self.variable),
+            ViolationKind::NotNumber => write!(f, "{}: must be a number", self.variable),
+            ViolationKind::NotBool => write!(f, "{}: must be a boolean", self.variable),
+            ViolationKind::NotFile => write!(f, "{}: must be a file", self.variable),
+            ViolationKind::NotFileList => write!(f, "{}: must be a list of files", self.variable),
+            ViolationKind::TooManyFiles { count, max_length } => write!(
+                f,
+                "{}: {} files exceed max length {}",
+                self.variable, count, max_length
+            ),
+            ViolationKind::NotInOptions { options } => {
+                write!(f, "{}: must be one of {:?}", self.variable, options)
+            }
+            ViolationKind::TooLong { length, max_length } => write!(
+                f,
+                "{}: length {} exceeds max length {}",
+                self.variable, length, max_length
+            ),
+        }
+    }
+}
+
+/// The error of an inputs validation, containing all violations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// The violations of the inputs.
+    pub violations: Vec<InputViolation>,
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "Inputs Illegal: ")?;
+        for (i, violation) in self.violations.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ValidationError {}
+
+/// Returns whether the value is missing for a required variable.
+fn is_missing(value: Option<&JsonValue>) -> bool {
+    match value {
+        None | Some(JsonValue::Null) => true,
+        Some(JsonValue::String(s)) => s.is_empty(),
+        Some(JsonValue::Array(a)) => a.is_empty(),
+        _ => false,
+    }
+}
+
+/// Checks the text value against the max length.
+fn check_text(value: &JsonValue, max_length: Option<u32>) -> Option<ViolationKind> {
+    let Some(text) = value.as_str() else {
+        return Some(ViolationKind::NotString);
+    };
+    let length = text.chars().count();
+    match max_length {
+        Some(max_length) if max_length > 0 && length > max_length as usize => {
+            Some(ViolationKind::TooLong { length, max_length })
+        }
+        _ => None,
+    }
+}
+
+/// Checks the value is a number or a numeric string.
+fn check_number(value: &JsonValue) -> Option<ViolationKind> {
+    match value {
+        JsonValue::Number(_) => None,
+        JsonValue::String(s) if s.trim().parse::<f64>().is_ok() => None,
+        _ => Some(ViolationKind::NotNumber),
+    }
+}
+
+/// Checks the value is one of the options.
+fn check_select(value: &JsonValue, options: &[String]) -> Option<ViolationKind> {
+    match value.as_str() {
+        Some(s) if options.iter().any(|o| o == s) => None,
+        _ => Some(ViolationKind::NotInOptions {
+            options: options.to_vec(),
+        }),
+    }
+}
+
+/// Returns whether the value is a file, an object with a transfer method.
+fn is_file(value: &JsonValue) -> bool {
+    value
+        .get("transfer_method")
+        .is_some_and(JsonValue::is_string)
+}
+
+/// Checks the value is a list of files not longer than the max length.
+fn check_file_list(value: &JsonValue, max_length: Option<u32>) -> Option<ViolationKind> {
+    let Some(files) = value.as_array() else {
+        return Some(ViolationKind::NotFileList);
+    };
+    if !files.iter().all(is_file) {
+        return Some(ViolationKind::NotFile);
+    }
+    match max_length {
+        Some(max_length) if max_length > 0 && files.len() > max_length as usize => {
+            Some(ViolationKind::TooManyFiles {
+                count: files.len(),
+                max_length,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Returns a field of the config of an unknown form item, `{"<type>": {...}}`.
+fn other_field<'a>(item: &'a JsonValue, field: &str) -> Option<&'a JsonValue> {
+    item.as_object()?.values().next()?.get(field)
+}
+
+impl ParameterUserInputFormItem {
+    /// Returns the variable name of the form item.
+    /// Unknown form items without a variable return an empty name.
+    pub fn variable(&self) -> &str {
+        match self {
+            ParameterUserInputFormItem::TextInput { variable, .. }
+            | ParameterUserInputFormItem::Paragraph { variable, .. }
+            | ParameterUserInputFormItem::Number { variable, .. }
+            | ParameterUserInputFormItem::Select { variable, .. }
+            | ParameterUserInputFormItem::Checkbox { variable, .. }
+            | ParameterUserInputFormItem::File { variable, .. }
+            | ParameterUserInputFormItem::FileList { variable, .. }
+            | ParameterUserInputFormItem::ExternalDataTool { variable, .. } => variable,
+            ParameterUserInputFormItem::Other(item) => other_field(item, "variable")
+                .and_then(JsonValue::as_str)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Returns whether the form item is required.
+    pub fn required(&self) -> bool {
+        match self {
+            ParameterUserInputFormItem::TextInput { required, .. }
+            | ParameterUserInputFormItem::Paragraph { required, .. }
+            | ParameterUserInputFormItem::Number { required, .. }
+            | ParameterUserInputFormItem::Select { required, .. }
+            | ParameterUserInputFormItem::Checkbox { required, .. }
+            | ParameterUserInputFormItem::File { required, .. }
+            | ParameterUserInputFormItem::FileList { required, .. }
+            | ParameterUserInputFormItem::ExternalDataTool { required, .. } => *required,
+            ParameterUserInputFormItem::Other(item) => other_field(item, "required")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Validates a value against the form item.
+    /// External data tools are filled by the server and unknown form items
+    /// are not validated. Hidden form items are never missing, like in
+    /// [`ParametersResponse::inputs_schema`].
+    ///
+    /// # Arguments
+    /// * `value` - The value of the variable, `None` if absent.
+    ///
+    /// # Returns
+    /// The violation kind, or `None` if the value is valid.
+    pub fn validate(&self, value: Option<&JsonValue>) -> Option<ViolationKind> {
+        if matches!(
+            self,
+            ParameterUserInputFormItem::ExternalDataTool { .. }
+                | ParameterUserInputFormItem::Other(_)
+        ) {
+            return None;
+        }
+        if is_missing(value) {
+            return (self.required() && !self.hidden()).then_some(ViolationKind::Missing);
+        }
+        let value = value?;
+        match self {
+            ParameterUserInputFormItem::TextInput { max_length, .. }
+            | ParameterUserInputFormItem::Paragraph { max_length, .. } => {
+                check_text(value, *max_length)
+            }
+            ParameterUserInputFormItem::Number { .. } => check_number(value),
+            ParameterUserInputFormItem::Select { options, .. } => check_select(value, options),
+            ParameterUserInputFormItem::Checkbox { .. } => {
+                (!value.is_boolean()).then_some(ViolationKind::NotBool)
+            }
+            ParameterUserInputFormItem::File { .. } => {
+                (!is_file(value)).then_some(ViolationKind::NotFile)
+            }
+            ParameterUserInputFormItem::FileList { max_length, .. } => {
+                check_file_list(value, *max_length)
+            }
+            ParameterUserInputFormItem::ExternalDataTool { .. }
+            | ParameterUserInputFormItem::Other(_) => None,
+        }
+    }
+}
+
+impl ParametersResponse {
+    /// Validates the inputs against the user input form.
+    /// Variables not in the form are ignored.
+    ///
+    /// # Arguments
+    /// * `inputs` - The inputs of the request.
+    ///
+    /// # Returns
+    /// A `Result` indicating success or a `ValidationError` with all violations.
+    pub fn validate_inputs(
+        &self,
+        inputs: &HashMap<String, JsonValue>,
+    ) -> Result<(), ValidationError> {
+        let violations = self
+            .user_input_form
+            .iter()
+            .filter_map(|item| {
+                let variable = item.variable();
+                item.validate(inputs.get(variable))
+                    .map(|kind| InputViolation {
+                        variable: variable.to_string(),
+                        kind,
+                    })
+            })
+            .collect::<Vec<_>>();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError { violations })
+        }
+    }
+}
+
+/// The parameters cached per app.
+type ParametersCache = Arc<RwLock<HashMap<String, Arc<ParametersResponse>>>>;
+
+/// Validates the inputs of the requests sent by an [`Api`] before sending.
+///
+/// A cached validator fetches the parameters once per app (base URL and API key),
+/// an uncached validator fetches them before every request.
+/// Clones of a cached validator share the cache.
+#[derive(Clone, Debug, Default)]
+pub struct InputsValidator {
+    cache: Option<ParametersCache>,
+}
+
+impl InputsValidator {
+    /// Creates a validator fetching the parameters before every request.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a validator caching the parameters per app.
+    pub fn cached() -> Self {
+        Self {
+            cache: Some(Arc::default()),
+        }
+    }
+
+    /// Clears the cached parameters of all apps.
+    pub fn invalidate(&self) {
+        if let Some(cache) = self.cache.as_ref() {
+            cache.write().unwrap_or_else(|e| e.into_inner()).clear();
+        }
+    }
+
+    /// Returns the parameters of the app of the API, from the cache if available.
+    ///
+    /// # Arguments
+    /// * `api` - The API of the app.
+    /// * `user` - The user for fetching the parameters.
+    ///
+    /// # Returns
+    /// A `Result` containing the parameters or an error.
+    pub async fn parameters(&self, api: &Api, user: &str) -> AnyResult<Arc<ParametersResponse>> {
+        let Some(cache) = self.cache.as_ref() else {
+            return Ok(Arc::new(api.parameters(Self::request(user)).await?));
+        };
+        let key = api.app_key();
+        if let Some(params) = cache.read().unwrap_or_else(|e| e.into_inner()).get(&key) {
+            return Ok(params.clone());
+        }
+        let params = Arc::new(api.parameters(Self::request(user)).await?);
+        cache
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, params.clone());
+        Ok(params)
+    }
+
+    /// Validates the inputs against the parameters of the app of the API.
+    ///
+    /// # Arguments
+    /// * `api` - The API of the app.
+    /// * `inputs` - The inputs of the request.
+    /// * `user` - The user for fetching the parameters.
+    ///
+    /// # Returns
+    /// A `Result` indicating success or an error, a `ValidationError` if the inputs are invalid.
+    pub async fn validate(
+        &self,
+        api: &Api,
+        inputs: &HashMap<String, JsonValue>,
+        user: &str,
+    ) -> AnyResult<()> {
+        let params = self.parameters(api, user).await?;
+        params.validate_inputs(inputs)?;
+        Ok(())
+    }
+
+    /// Returns the parameters request for the user.
+    fn request(user: &str) -> ParametersRequest {
+        ParametersRequest { user: user.into() }
+    }
+}
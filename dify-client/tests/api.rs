@@ -0,0 +1,1942 @@
+use dify_client::{
+    api::{CancellationToken, RequestOptions},
+    export::{self, ConversationExporter, ExportFormat},
+    http::header,
+    pagination::Paginator,
+    request::{self, InputsBuilder},
+    response,
+    trace::WorkflowTrace,
+    validation::{InputsValidator, ValidationError, ViolationKind},
+    Client,
+};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+use wiremock::{
+    matchers::{
+        body_partial_json, header as header_eq, method, path, query_param, query_param_is_missing,
+    },
+    Mock, MockServer, ResponseTemplate,
+};
+
+fn workflows_run_response() -> serde_json::Value {
+    json!({
+        "workflow_run_id": "run-1",
+        "task_id": "task-1",
+        "data": {
+            "id": "run-1",
+            "workflow_id": "workflow-1",
+            "status": "succeeded",
+            "outputs": {"output": "done"},
+            "error": null,
+            "elapsed_time": 0.5,
+            "total_tokens": 10,
+            "total_steps": 3,
+            "created_at": 1705395332,
+            "finished_at": 1705395333
+        }
+    })
+}
+
+#[tokio::test]
+async fn test_request_options_headers() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/workflows/run"))
+        .and(header_eq("authorization", "Bearer API_KEY_OVERRIDE"))
+        .and(header_eq("idempotency-key", "order-42"))
+        .and(header_eq("x-trace-id", "trace"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(workflows_run_response()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = Client::new(&server.uri(), "API_KEY");
+    let mut headers = header::HeaderMap::new();
+    headers.insert("x-trace-id", header::HeaderValue::from_static("trace"));
+    let options = RequestOptions {
+        headers,
+        idempotency_key: Some("order-42".into()),
+        api_key: Some("API_KEY_OVERRIDE".into()),
+        ..Default::default()
+    };
+    let data = request::WorkflowsRunRequest {
+        user: "afa".into(),
+        ..Default::default()
+    };
+    let result = client.api().with_options(options).workflows_run(data).await;
+    let response = result.expect("workflows run failed");
+    assert_eq!(response.workflow_run_id, "run-1");
+}
+
+#[tokio::test]
+async fn test_request_options_timeout() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/meta"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"tool_icons": {}}))
+                .set_delay(Duration::from_secs(2)),
+        )
+        .mount(&server)
+        .await;
+
+    let client = Client::new(&server.uri(), "API_KEY");
+    let options = RequestOptions {
+        timeout: Some(Duration::from_millis(100)),
+        ..Default::default()
+    };
+    let data = request::MetaRequest { user: "afa".into() };
+    let result = client.api().with_options(options).meta(data).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_request_options_cancellation() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/meta"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"tool_icons": {}}))
+                .set_delay(Duration::from_secs(5)),
+        )
+        .mount(&server)
+        .await;
+
+    let client = Client::new(&server.uri(), "API_KEY");
+    let token = CancellationToken::new();
+    let options = RequestOptions {
+        cancellation_token: Some(token.clone()),
+        ..Default::default()
+    };
+    let api = client.api().with_options(options);
+    let handle = tokio::spawn(async move {
+        let data = request::MetaRequest { user: "afa".into() };
+        api.meta(data).await
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    token.cancel();
+    let result = handle.await.expect("join failed");
+    let error = result.expect_err("request not cancelled");
+    assert_eq!(error.to_string(), "Request cancelled");
+}
+
+#[tokio::test]
+async fn test_typed_inputs() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/workflows/run"))
+        .and(body_partial_json(json!({
+            "inputs": {
+                "input": "text",
+                "count": 3,
+                "ratio": 0.5,
+                "verbose": true,
+                "tags": ["a", "b"],
+                "contract": {
+                    "transfer_method": "local_file",
+                    "type": "document",
+                    "upload_file_id": "file-1"
+                },
+                "images": [{
+                    "transfer_method": "remote_url",
+                    "type": "image",
+                    "url": "https://example.com/a.png"
+                }]
+            }
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(workflows_run_response()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let inputs = InputsBuilder::new()
+        .set("input", "text")
+        .set("count", 3)
+        .set("ratio", 0.5)
+        .set("verbose", true)
+        .set("tags", json!(["a", "b"]))
+        .file(
+            "contract",
+            request::FileInput::LocalFile {
+                type_: request::FileType::Document,
+                upload_file_id: "file-1".into(),
+            },
+        )
+        .files(
+            "images",
+            vec![request::FileInput::RemoteUrl {
+                type_: request::FileType::Image,
+                url: "https://example.com/a.png".into(),
+            }],
+        )
+        .build();
+    let data = request::WorkflowsRunRequest {
+        inputs,
+        user: "afa".into(),
+        ..Default::default()
+    };
+    let client = Client::new(&server.uri(), "API_KEY");
+    let result = client.api().workflows_run(data).await;
+    assert!(result.is_ok());
+}
+
+#[derive(Serialize)]
+struct SummaryInputs {
+    input: String,
+    max_words: u32,
+}
+
+#[derive(Deserialize)]
+struct SummaryOutputs {
+    output: String,
+}
+
+#[tokio::test]
+async fn test_workflows_run_typed() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/workflows/run"))
+        .and(body_partial_json(json!({
+            "inputs": {"input": "text", "max_words": 20, "style": "short"}
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(workflows_run_response()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = Client::new(&server.uri(), "API_KEY");
+    let inputs = SummaryInputs {
+        input: "text".into(),
+        max_words: 20,
+    };
+    let data = request::WorkflowsRunRequest {
+        inputs: InputsBuilder::new().set("style", "short").build(),
+        user: "afa".into(),
+        ..Default::default()
+    };
+    let result = client
+        .api()
+        .workflows_run_typed::<_, SummaryOutputs>(&inputs, data)
+        .await
+        .expect("workflows run failed");
+    assert_eq!(result.outputs.output, "done");
+    assert_eq!(result.response.task_id, "task-1");
+
+    assert!(request::to_inputs(&"text").is_err());
+    let mut failed: response::WorkflowsRunResponse =
+        serde_json::from_value(workflows_run_response()).unwrap();
+    failed.data.status = response::FinishedStatus::Failed;
+    failed.data.error = Some("node failed".into());
+    let error = failed.outputs_as::<SummaryOutputs>().err().unwrap();
+    assert!(error.to_string().contains("node failed"));
+}
+
+fn parameters_response() -> serde_json::Value {
+    json!({
+        "opening_statement": "",
+        "suggested_questions": [],
+        "suggested_questions_after_answer": {"enabled": false},
+        "speech_to_text": {"enabled": false},
+        "retriever_resource": {"enabled": false},
+        "annotation_reply": {"enabled": false},
+        "user_input_form": [
+            {"text-input": {"label": "Name", "variable": "name", "required": true, "max_length": 5}},
+            {"paragraph": {"label": "Bio", "variable": "bio", "required": false}},
+            {"number": {"label": "Age", "variable": "age", "required": false}},
+            {"select": {"label": "Style", "variable": "style", "required": true, "options": ["short", "long"]}}
+        ],
+        "file_upload": {"image": {"enabled": false, "number_limits": 3, "transfer_methods": ["remote_url"]}},
+        "system_parameters": {"image_file_size_limit": "10"}
+    })
+}
+
+#[test]
+fn test_validate_inputs() {
+    let params: response::ParametersResponse =
+        serde_json::from_value(parameters_response()).unwrap();
+    let inputs = InputsBuilder::new()
+        .set("name", "Alice")
+        .set("age", "42")
+        .set("style", "short")
+        .build();
+    assert!(params.validate_inputs(&inputs).is_ok());
+
+    let inputs = InputsBuilder::new()
+        .set("name", "Alice Smith")
+        .set("bio", 1)
+        .set("age", "forty")
+        .set("style", "medium")
+        .build();
+    let error = params.validate_inputs(&inputs).unwrap_err();
+    let kinds = error
+        .violations
+        .iter()
+        .map(|v| (v.variable.as_str(), v.kind.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![
+            (
+                "name",
+                ViolationKind::TooLong {
+                    length: 11,
+                    max_length: 5
+                }
+            ),
+            ("bio", ViolationKind::NotString),
+            ("age", ViolationKind::NotNumber),
+            (
+                "style",
+                ViolationKind::NotInOptions {
+                    options: vec!["short".into(), "long".into()]
+                }
+            ),
+        ]
+    );
+
+    let error = params.validate_inputs(&Default::default()).unwrap_err();
+    assert_eq!(error.violations.len(), 2);
+    assert!(error
+        .violations
+        .iter()
+        .all(|v| v.kind == ViolationKind::Missing));
+}
+
+#[test]
+fn test_hidden_required_input() {
+    let mut params: response::ParametersResponse =
+        serde_json::from_value(parameters_response()).unwrap();
+    params.user_input_form.push(
+        serde_json::from_value(json!(
+            {"text-input": {"label": "Secret", "variable": "secret", "required": true, "hide": true}}
+        ))
+        .unwrap(),
+    );
+    let inputs = InputsBuilder::new()
+        .set("name", "Alice")
+        .set("style", "short")
+        .build();
+    // the validator and the schema agree that a hidden variable is not required
+    assert!(params.validate_inputs(&inputs).is_ok());
+    let schema = params.inputs_schema();
+    assert_eq!(schema["required"], json!(["name", "style"]));
+    assert_eq!(schema["properties"]["secret"]["type"], "string");
+
+    let inputs = InputsBuilder::new()
+        .set("name", "Alice")
+        .set("style", "short")
+        .set("secret", 1)
+        .build();
+    let error = params.validate_inputs(&inputs).unwrap_err();
+    assert_eq!(error.violations[0].kind, ViolationKind::NotString);
+}
+
+#[test]
+fn test_inputs_schema() {
+    let mut params: response::ParametersResponse =
+        serde_json::from_value(parameters_response()).unwrap();
+    params.user_input_form.extend(
+        serde_json::from_value::<Vec<response::ParameterUserInputFormItem>>(json!([
+            {"checkbox": {"label": "Agree", "variable": "agree", "required": false, "default": true}},
+            {"file-list": {"label": "Images", "variable": "images", "required": false, "max_length": 2,
+                "allowed_file_types": ["image"], "allowed_file_upload_methods": ["remote_url"]}},
+            {"text-input": {"label": "Secret", "variable": "secret", "required": true, "default": "s3cr3t", "hide": true}}
+        ]))
+        .unwrap(),
+    );
+    let schema = params.inputs_schema();
+    assert_eq!(schema["type"], "object");
+    assert_eq!(schema["required"], json!(["name", "style"]));
+    let properties = &schema["properties"];
+    assert_eq!(
+        properties["name"],
+        json!({"title": "Name", "type": "string", "maxLength": 5})
+    );
+    assert_eq!(properties["age"]["type"], "number");
+    assert_eq!(properties["style"]["enum"], json!(["short", "long"]));
+    assert_eq!(
+        properties["agree"],
+        json!({"title": "Agree", "type": "boolean", "default": true})
+    );
+    assert_eq!(properties["images"]["maxItems"], 2);
+    assert_eq!(
+        properties["images"]["items"]["properties"]["type"]["enum"],
+        json!(["image"])
+    );
+    assert_eq!(
+        properties["secret"],
+        json!({"title": "Secret", "type": "string", "default": "s3cr3t"})
+    );
+}
+
+#[test]
+fn test_parameters_modern() {
+    let params: response::ParametersResponse = serde_json::from_value(json!({
+        "opening_statement": "",
+        "suggested_questions": [],
+        "suggested_questions_after_answer": {"enabled": false},
+        "speech_to_text": {"enabled": false},
+        "text_to_speech": {"enabled": true, "voice": "alloy", "language": "en-US", "autoPlay": "disabled"},
+        "retriever_resource": {"enabled": true},
+        "annotation_reply": {"enabled": false},
+        "more_like_this": {"enabled": false},
+        "sensitive_word_avoidance": {"enabled": true, "type": "keywords", "configs": []},
+        "user_input_form": [
+            {"text-input": {"label": "Name", "variable": "name", "required": true, "max_length": 48, "default": "Bob", "hide": true}},
+            {"checkbox": {"label": "Agree", "variable": "agree", "required": true}},
+            {"file": {"label": "Contract", "variable": "contract", "required": true,
+                "allowed_file_types": ["document"], "allowed_file_extensions": [],
+                "allowed_file_upload_methods": ["local_file", "remote_url"]}},
+            {"file-list": {"label": "Images", "variable": "images", "required": false, "max_length": 1,
+                "allowed_file_types": ["image"], "allowed_file_upload_methods": ["remote_url"]}},
+            {"external_data_tool": {"label": "Weather", "variable": "weather", "required": true,
+                "type": "api", "enabled": true, "config": {"api_based_extension_id": "ext-1"}}},
+            {"color-picker": {"label": "Color", "variable": "color", "required": true}}
+        ],
+        "file_upload": {
+            "image": {"enabled": true, "number_limits": 3, "detail": "high", "transfer_methods": ["remote_url", "local_file"]},
+            "enabled": true,
+            "allowed_file_types": ["image", "document"],
+            "allowed_file_extensions": [".png"],
+            "allowed_file_upload_methods": ["local_file"],
+            "number_limits": 5,
+            "fileUploadConfig": {"file_size_limit": 15}
+        },
+        "system_parameters": {
+            "image_file_size_limit": 10,
+            "video_file_size_limit": 100,
+            "audio_file_size_limit": "50",
+            "file_size_limit": 15,
+            "workflow_file_upload_limit": 10
+        }
+    }))
+    .expect("parameters not deserialized");
+    assert_eq!(params.text_to_speech.auto_play.as_deref(), Some("disabled"));
+    assert_eq!(
+        params.sensitive_word_avoidance.type_.as_deref(),
+        Some("keywords")
+    );
+    assert_eq!(params.file_upload.number_limits, Some(5));
+    assert!(params.file_upload.extra.contains_key("fileUploadConfig"));
+    assert_eq!(params.system_parameters.image_file_size_limit, "10");
+    assert_eq!(params.system_parameters.audio_file_size_limit, Some(50));
+    assert!(matches!(
+        &params.user_input_form[0],
+        response::ParameterUserInputFormItem::TextInput { hide: true, .. }
+    ));
+    let color = &params.user_input_form[5];
+    assert!(matches!(
+        color,
+        response::ParameterUserInputFormItem::Other(_)
+    ));
+    assert_eq!(color.variable(), "color");
+
+    let file = request::FileInput::LocalFile {
+        type_: request::FileType::Document,
+        upload_file_id: "file-1".into(),
+    };
+    let inputs = InputsBuilder::new()
+        .set("name", "Alice")
+        .set("agree", true)
+        .file("contract", file.clone())
+        .files("images", vec![file.clone()])
+        .build();
+    assert!(params.validate_inputs(&inputs).is_ok());
+
+    let inputs = InputsBuilder::new()
+        .set("name", "Alice")
+        .set("agree", "yes")
+        .set("contract", "file-1")
+        .files("images", vec![file.clone(), file])
+        .build();
+    let error = params.validate_inputs(&inputs).unwrap_err();
+    let kinds = error
+        .violations
+        .iter()
+        .map(|v| (v.variable.as_str(), v.kind.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![
+            ("agree", ViolationKind::NotBool),
+            ("contract", ViolationKind::NotFile),
+            (
+                "images",
+                ViolationKind::TooManyFiles {
+                    count: 2,
+                    max_length: 1
+                }
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_inputs_validator_cached() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/parameters"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(parameters_response()))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/workflows/run"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(workflows_run_response()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = Client::new(&server.uri(), "API_KEY");
+    let api = client
+        .api()
+        .with_user("afa")
+        .with_validator(InputsValidator::cached());
+
+    let data = request::WorkflowsRunRequest {
+        inputs: InputsBuilder::new().set("style", "medium").build(),
+        ..Default::default()
+    };
+    let error = api.workflows_run(data).await.unwrap_err();
+    let error = error
+        .downcast::<ValidationError>()
+        .expect("not a validation error");
+    assert_eq!(error.violations.len(), 2);
+
+    let data = request::WorkflowsRunRequest {
+        inputs: InputsBuilder::new()
+            .set("name", "Bob")
+            .set("style", "long")
+            .build(),
+        ..Default::default()
+    };
+    assert!(api.workflows_run(data).await.is_ok());
+}
+
+fn conversation(id: &str) -> serde_json::Value {
+    json!({"id": id, "name": id, "inputs": {}, "introduction": "", "created_at": 1705395332})
+}
+
+fn message(id: &str) -> serde_json::Value {
+    json!({
+        "id": id,
+        "conversation_id": "c1",
+        "inputs": {},
+        "query": "hi",
+        "answer": "hello",
+        "message_files": [],
+        "feedback": null,
+        "retriever_resources": [],
+        "created_at": 1705395332
+    })
+}
+
+#[tokio::test]
+async fn test_conversations_iter() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/conversations"))
+        .and(query_param_is_missing("last_id"))
+        .and(query_param("limit", "2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "has_more": true,
+            "limit": 2,
+            "data": [conversation("c1"), conversation("c2")]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/conversations"))
+        .and(query_param("last_id", "c2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "has_more": false,
+            "limit": 2,
+            "data": [conversation("c3")]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = Client::new(&server.uri(), "API_KEY");
+    let data = request::ConversationsRequest {
+        user: "afa".into(),
+        limit: Some(2),
+        ..Default::default()
+    };
+    let conversations = client
+        .api()
+        .conversations_iter_with(data)
+        .collect_all()
+        .await
+        .expect("conversations failed");
+    let ids = conversations
+        .iter()
+        .map(|c| c.id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec!["c1", "c2", "c3"]);
+}
+
+#[tokio::test]
+async fn test_messages_iter() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/messages"))
+        .and(query_param_is_missing("first_id"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "has_more": true,
+            "limit": 20,
+            "data": [message("m3"), message("m4")]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/messages"))
+        .and(query_param("first_id", "m3"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "has_more": true,
+            "limit": 20,
+            "data": [message("m1"), message("m2")]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/messages"))
+        .and(query_param("first_id", "m1"))
+        .respond_with(ResponseTemplate::new(500).set_body_json(json!({
+            "code": "internal_server_error",
+            "message": "boom",
+            "status": 500
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = Client::new(&server.uri(), "API_KEY");
+    let messages: Paginator<_> = client.api().messages_iter("c1", "afa");
+    let results = messages.collect::<Vec<_>>().await;
+    let ids = results
+        .iter()
+        .filter_map(|r| r.as_ref().ok())
+        .map(|m| m.id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec!["m4", "m3", "m2", "m1"]);
+    assert_eq!(results.len(), 5);
+    assert!(results[4].is_err());
+}
+
+#[tokio::test]
+async fn test_conversation_export_resume() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/conversations"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "has_more": false,
+            "limit": 20,
+            "data": [conversation("c1"), conversation("c2")]
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/messages"))
+        .and(query_param("conversation_id", "c2"))
+        .respond_with(ResponseTemplate::new(500).set_body_json(json!({
+            "code": "internal_server_error",
+            "message": "boom",
+            "status": 500
+        })))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    let mut answer = message("m2");
+    answer["feedback"] = json!({"rating": "like"});
+    answer["retriever_resources"] = json!([{"dataset_name": "Docs", "document_name": "FAQ"}]);
+    Mock::given(method("GET"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "has_more": false,
+            "limit": 20,
+            "data": [message("m1"), answer]
+        })))
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let client = Client::new(&server.uri(), "API_KEY");
+    let exporter = ConversationExporter::new(client.api(), dir.path(), ExportFormat::Jsonl)
+        .with_checkpoint(dir.path().join("checkpoint.json"));
+
+    assert!(exporter.export_users(&["afa"]).await.is_err());
+    let summary = exporter
+        .export_users(&["afa"])
+        .await
+        .expect("export failed");
+    assert_eq!(summary.conversations, 1);
+    assert_eq!(summary.skipped, 1);
+    let summary = exporter
+        .export_users(&["afa"])
+        .await
+        .expect("export failed");
+    assert_eq!(summary.conversations, 0);
+
+    let names = ["a/b", "a b", "a_b"]
+        .iter()
+        .map(|user| exporter.transcript_path(user))
+        .collect::<std::collections::HashSet<_>>();
+    assert_eq!(names.len(), 3);
+    assert!(exporter
+        .transcript_path("a/b")
+        .to_string_lossy()
+        .ends_with(".jsonl"));
+    let exported = export::read_jsonl(exporter.transcript_path("afa")).expect("read failed");
+    let ids = exported
+        .iter()
+        .map(|c| c.conversation.id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec!["c1", "c2"]);
+    assert_eq!(exported[0].messages[0].id, "m1");
+
+    let markdown = export::render_markdown(&exported[1]);
+    assert!(markdown.contains("- Feedback: like"));
+    assert!(markdown.contains("- Docs / FAQ"));
+}
+
+fn chat_messages_response(conversation_id: &str) -> serde_json::Value {
+    json!({
+        "message_id": "m1",
+        "conversation_id": conversation_id,
+        "created_at": 1705395332,
+        "event": "message",
+        "mode": "chat",
+        "answer": "hello",
+        "metadata": {}
+    })
+}
+
+#[tokio::test]
+async fn test_conversation() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat-messages"))
+        .and(body_partial_json(json!({
+            "conversation_id": "",
+            "user": "afa",
+            "inputs": {"name": "Alice"}
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(chat_messages_response("conv-1")))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let sse = format!(
+        "data: {}\n\ndata: {}\n\n",
+        json!({"event": "message", "message_id": "m2", "conversation_id": "conv-1",
+            "created_at": 1705395332, "id": "m2", "task_id": "t1", "answer": "hi"}),
+        json!({"event": "message_end", "message_id": "m2", "conversation_id": "conv-1",
+            "created_at": 1705395332, "id": "m2", "task_id": "t1", "metadata": {}})
+    );
+    Mock::given(method("POST"))
+        .and(path("/v1/chat-messages"))
+        .and(body_partial_json(json!({
+            "conversation_id": "conv-1",
+            "response_mode": "streaming"
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(sse, "text/event-stream"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/conversations/conv-1/name"))
+        .and(body_partial_json(
+            json!({"auto_generate": true, "user": "afa"}),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"result": "success"})))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/v1/conversations/conv-1"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = Client::new(&server.uri(), "API_KEY");
+    let conversation = client
+        .api()
+        .conversation("afa")
+        .with_inputs(InputsBuilder::new().set("name", "Alice").build());
+    assert!(conversation.id().is_none());
+    assert!(conversation.rename(None).await.is_err());
+    conversation.send("hello").await.expect("send failed");
+    assert_eq!(conversation.id().as_deref(), Some("conv-1"));
+
+    let events = conversation
+        .send_stream("more")
+        .await
+        .expect("send stream failed")
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|e| e.is_ok()));
+
+    let result = conversation.rename(None).await.expect("rename failed");
+    assert_eq!(result.result, "success");
+    conversation.delete().await.expect("delete failed");
+    assert!(conversation.id().is_none());
+}
+
+fn conversation_variable(id: &str, value: serde_json::Value) -> serde_json::Value {
+    json!({
+        "id": id,
+        "name": id,
+        "value_type": "object",
+        "value": value,
+        "description": "",
+        "created_at": 1705395332,
+        "updated_at": 1705395332
+    })
+}
+
+#[tokio::test]
+async fn test_conversation_variables() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/conversations/conv-1/variables"))
+        .and(query_param("variable_name", "memory"))
+        .and(query_param_is_missing("last_id"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "limit": 1,
+            "has_more": true,
+            "data": [conversation_variable("v1", json!("{\"topic\": \"rust\"}"))]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/conversations/conv-1/variables"))
+        .and(query_param("last_id", "v1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "limit": 1,
+            "has_more": false,
+            "data": [conversation_variable("v2", json!({"topic": "go"}))]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path("/v1/conversations/conv-1/variables/v1"))
+        .and(body_partial_json(
+            json!({"value": {"topic": "serde"}, "user": "afa"}),
+        ))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(conversation_variable("v1", json!({"topic": "serde"}))),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    #[derive(Deserialize)]
+    struct Memory {
+        topic: String,
+    }
+
+    let client = Client::new(&server.uri(), "API_KEY");
+    let api = client.api().with_user("afa");
+    let data = request::ConversationVariablesRequest {
+        conversation_id: "conv-1".into(),
+        limit: Some(1),
+        variable_name: Some("memory".into()),
+        ..Default::default()
+    };
+    let variables = api
+        .conversation_variables_iter(data)
+        .collect_all()
+        .await
+        .expect("conversation variables failed");
+    let topics = variables
+        .iter()
+        .map(|v| v.value_as::<Memory>().unwrap().topic)
+        .collect::<Vec<_>>();
+    assert_eq!(topics, vec!["rust", "go"]);
+
+    let data = request::ConversationVariableUpdateRequest {
+        conversation_id: "conv-1".into(),
+        variable_id: "v1".into(),
+        value: json!({"topic": "serde"}),
+        ..Default::default()
+    };
+    let variable = api
+        .conversation_variable_update(data)
+        .await
+        .expect("update failed");
+    assert_eq!(variable.value_as::<Memory>().unwrap().topic, "serde");
+}
+
+fn app_feedback(id: &str) -> serde_json::Value {
+    json!({
+        "id": id,
+        "app_id": "app-1",
+        "conversation_id": "c1",
+        "message_id": "m1",
+        "rating": "like",
+        "content": "good",
+        "from_source": "user",
+        "from_end_user_id": "u1",
+        "from_account_id": null,
+        "created_at": "2025-04-24T09:24:38",
+        "updated_at": "2025-04-24T09:24:38"
+    })
+}
+
+#[tokio::test]
+async fn test_app_info_site_feedbacks() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/info"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "name": "Support Bot",
+            "description": "answers questions",
+            "tags": ["support"],
+            "mode": "advanced-chat",
+            "author_name": "afa"
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/site"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "title": "Support",
+            "chat_color_theme": "#ff0000",
+            "chat_color_theme_inverted": false,
+            "icon_type": "emoji",
+            "icon": "🤖",
+            "icon_background": "#ffffff",
+            "icon_url": null,
+            "description": "",
+            "copyright": "ACME",
+            "privacy_policy": "",
+            "custom_disclaimer": "",
+            "default_language": "en-US",
+            "show_workflow_steps": true,
+            "use_icon_as_answer_icon": false
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/app/feedbacks"))
+        .and(query_param("page", "1"))
+        .and(query_param("limit", "2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": [app_feedback("f1"), app_feedback("f2")]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/app/feedbacks"))
+        .and(query_param("page", "2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": [app_feedback("f3")]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = Client::new(&server.uri(), "API_KEY");
+    let api = client.api();
+    let info = api.info(Default::default()).await.expect("info failed");
+    assert_eq!(info.mode, response::AppMode::AdvancedChat);
+    assert_eq!(info.tags, vec!["support"]);
+    let site = api.site(Default::default()).await.expect("site failed");
+    assert_eq!(site.default_language.as_deref(), Some("en-US"));
+    assert!(site.show_workflow_steps);
+
+    let data = request::AppFeedbacksRequest {
+        limit: Some(2),
+        ..Default::default()
+    };
+    let feedbacks = api
+        .app_feedbacks_iter(data)
+        .collect_all()
+        .await
+        .expect("feedbacks failed");
+    let ids = feedbacks.iter().map(|f| f.id.as_str()).collect::<Vec<_>>();
+    assert_eq!(ids, vec!["f1", "f2", "f3"]);
+}
+
+fn workflow_log(id: &str, status: &str) -> serde_json::Value {
+    json!({
+        "id": id,
+        "workflow_run": {
+            "id": format!("run-{}", id),
+            "version": "2025-01-01 00:00:00",
+            "status": status,
+            "error": null,
+            "elapsed_time": 1.5,
+            "total_tokens": 42,
+            "total_steps": 3,
+            "created_at": 1705395332,
+            "finished_at": 1705395334
+        },
+        "created_from": "service-api",
+        "created_by_role": "end_user",
+        "created_by_account": null,
+        "created_by_end_user": {
+            "id": "eu-1",
+            "type": "service_api",
+            "is_anonymous": false,
+            "session_id": "afa"
+        },
+        "created_at": 1705395332
+    })
+}
+
+#[tokio::test]
+async fn test_workflows_logs_iter() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/workflows/logs"))
+        .and(query_param("status", "failed"))
+        .and(query_param("keyword", "invoice"))
+        .and(query_param("page", "1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "page": 1,
+            "limit": 1,
+            "total": 2,
+            "has_more": true,
+            "data": [workflow_log("l1", "failed")]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/workflows/logs"))
+        .and(query_param("page", "2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "page": 2,
+            "limit": 1,
+            "total": 2,
+            "has_more": false,
+            "data": [workflow_log("l2", "failed")]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = Client::new(&server.uri(), "API_KEY");
+    let data = request::WorkflowLogsRequest {
+        keyword: Some("invoice".into()),
+        status: Some(response::FinishedStatus::Failed),
+        limit: Some(1),
+        ..Default::default()
+    };
+    let logs = client
+        .api()
+        .workflows_logs_iter(data)
+        .collect_all()
+        .await
+        .expect("workflow logs failed");
+    assert_eq!(logs.len(), 2);
+    assert_eq!(logs[1].workflow_run.id, "run-l2");
+    assert_eq!(
+        logs[0].workflow_run.status,
+        response::FinishedStatus::Failed
+    );
+    assert_eq!(
+        logs[0].created_by_end_user.as_ref().unwrap().session_id,
+        "afa"
+    );
+}
+
+#[tokio::test]
+async fn test_workflows_run_by_id() {
+    let server = MockServer::start().await;
+    let mut pinned = workflows_run_response();
+    pinned["data"]["workflow_id"] = json!("workflow-2");
+    Mock::given(method("POST"))
+        .and(path("/v1/workflows/workflow-2/run"))
+        .and(body_partial_json(json!({"response_mode": "blocking"})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(pinned))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let sse = format!(
+        "data: {}\n\n",
+        json!({
+            "event": "workflow_started",
+            "task_id": "task-1",
+            "workflow_run_id": "run-2",
+            "data": {
+                "id": "run-2",
+                "workflow_id": "workflow-2",
+                "sequence_number": 1,
+                "inputs": {},
+                "created_at": 1705395332
+            }
+        })
+    );
+    Mock::given(method("POST"))
+        .and(path("/v1/workflows/workflow-2/run"))
+        .and(body_partial_json(json!({"response_mode": "streaming"})))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(sse, "text/event-stream"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = Client::new(&server.uri(), "API_KEY");
+    let api = client.api().with_user("afa");
+    let response = api
+        .workflows_run_by_id("workflow-2", Default::default())
+        .await
+        .expect("workflows run failed");
+    assert_eq!(response.workflow_id(), "workflow-2");
+
+    let events = api
+        .workflows_run_stream_by_id("workflow-2", Default::default())
+        .await
+        .expect("workflows run stream failed")
+        .collect::<Vec<_>>()
+        .await;
+    match events[0].as_ref().expect("event failed") {
+        response::SseMessageEvent::WorkflowStarted { data, .. } => {
+            assert_eq!(data.workflow_id, "workflow-2")
+        }
+        event => panic!("unexpected event {:?}", event),
+    }
+    assert!(api
+        .workflows_run_by_id("", Default::default())
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_workflow_trace() {
+    let server = MockServer::start().await;
+    let base = json!({"task_id": "t1", "workflow_run_id": "run-1"});
+    let event = |event: &str, data: serde_json::Value| {
+        let mut value = base.clone();
+        value["event"] = json!(event);
+        value["data"] = data;
+        format!("data: {}\n\n", value)
+    };
+    let started = |id: &str,
+                   node_id: &str,
+                   node_type: &str,
+                   pred: Option<&str>,
+                   extra: serde_json::Value| {
+        let mut data = json!({"id": id, "node_id": node_id, "node_type": node_type, "title": node_id,
+            "index": 1, "predecessor_node_id": pred, "inputs": {}, "created_at": 1705395332});
+        data.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        event("node_started", data)
+    };
+    let finished = |id: &str,
+                    node_id: &str,
+                    pred: Option<&str>,
+                    status: &str,
+                    elapsed: f64,
+                    metadata: serde_json::Value| {
+        event(
+            "node_finished",
+            json!({"id": id, "node_id": node_id, "index": 1,
+            "predecessor_node_id": pred, "outputs": {}, "status": status,
+            "error": if status == "failed" { json!("boom") } else { json!(null) },
+            "elapsed_time": elapsed, "execution_metadata": metadata, "created_at": 1705395332}),
+        )
+    };
+    let round = |i: u32| json!({"iteration_id": "iter", "iteration_index": i});
+    let iteration = |name: &str, extra: serde_json::Value| {
+        let mut data = json!({"id": "e2", "node_id": "iter", "node_type": "iteration",
+            "title": "iter", "created_at": 1705395332});
+        data.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        event(name, data)
+    };
+    let sse = [
+        event("workflow_started", json!({"id": "run-1", "workflow_id": "wf-1",
+            "sequence_number": 1, "inputs": {}, "created_at": 1705395332})),
+        started("e1", "start", "start", None, json!({})),
+        finished("e1", "start", None, "succeeded", 0.1, json!(null)),
+        started("e2", "iter", "iteration", Some("start"), json!({})),
+        iteration("iteration_started", json!({"inputs": {"items": [1, 2]}})),
+        iteration("iteration_next", json!({"index": 0})),
+        started("e3", "llm", "llm", None, round(0)),
+        finished("e3", "llm", None, "succeeded", 1.0,
+            json!({"total_tokens": 10, "total_price": "0.01", "currency": "USD", "iteration_index": 0})),
+        iteration("iteration_next", json!({"index": 1, "created_at": 1705395333})),
+        // the round of the node comes from the latest iteration_next event
+        started("e4", "llm", "llm", None, json!({"iteration_id": "iter"})),
+        finished("e4", "llm", None, "failed", 2.0,
+            json!({"total_tokens": 20, "total_price": "0.02", "currency": "USD"})),
+        iteration("iteration_completed", json!({"status": "failed", "error": "boom",
+            "outputs": null, "elapsed_time": 3.5, "total_tokens": 30, "steps": 2,
+            "execution_metadata": {"iteration_duration_map": {"0": 1.25, "1": 2.25}},
+            "finished_at": 1705395336})),
+        finished("e2", "iter", Some("start"), "failed", 3.5,
+            json!({"total_tokens": 30, "total_price": "0.03", "currency": "USD"})),
+        started("e5", "code", "code", Some("start"), json!({"parallel_id": "p1"})),
+        finished("e5", "code", Some("start"), "succeeded", 0.5, json!(null)),
+        event("workflow_finished", json!({"id": "run-1", "workflow_id": "wf-1", "status": "failed",
+            "outputs": null, "error": "boom", "elapsed_time": 4.0, "total_tokens": 30,
+            "total_steps": 5, "created_at": 1705395332, "finished_at": 1705395336})),
+    ]
+    .concat();
+    Mock::given(method("POST"))
+        .and(path("/v1/workflows/run"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(sse, "text/event-stream"))
+        .mount(&server)
+        .await;
+
+    let client = Client::new(&server.uri(), "API_KEY");
+    let data = request::WorkflowsRunRequest {
+        user: "afa".into(),
+        ..Default::default()
+    };
+    let stream = client.api().workflows_run_stream(data).await.unwrap();
+    let trace = WorkflowTrace::collect(stream).await.unwrap();
+
+    assert_eq!(trace.workflow_id.as_deref(), Some("wf-1"));
+    assert_eq!(trace.status, Some(response::FinishedStatus::Failed));
+    assert_eq!(trace.nodes.len(), 3);
+    let iteration = &trace.nodes[1];
+    assert_eq!(iteration.inputs, Some(json!({"items": [1, 2]})));
+    assert_eq!(iteration.rounds.len(), 2);
+    assert_eq!(iteration.rounds[1].index, 1);
+    assert_eq!(iteration.rounds[1].created_at, 1705395333);
+    assert_eq!(iteration.rounds[1].elapsed_time, Some(2.25));
+    assert_eq!(iteration.rounds[1].nodes[0].round, Some(1));
+    assert_eq!(iteration.rounds[0].cost().total_tokens, 10);
+    assert_eq!(iteration.children().count(), 2);
+    assert_eq!(trace.nodes[2].parallel_id.as_deref(), Some("p1"));
+    assert!(trace.is_failed());
+    let failed: Vec<_> = trace.failed_nodes().iter().map(|n| n.id.as_str()).collect();
+    assert_eq!(failed, ["e2", "e4"]);
+
+    let cost = trace.cost();
+    assert_eq!(cost.total_tokens, 30);
+    assert!((cost.total_price - 0.03).abs() < 1e-9);
+    assert_eq!(cost.currency.as_deref(), Some("USD"));
+
+    let path: Vec<_> = trace
+        .critical_path()
+        .iter()
+        .map(|n| n.node_id.as_str())
+        .collect();
+    assert_eq!(path, ["start", "iter"]);
+
+    let text = trace.to_string();
+    assert!(text.starts_with("workflow run-1 [failed] 4.000s 30 tokens"));
+    assert!(text.contains(
+        "    round 1 2.250s 20 tokens\n      #1 llm [llm] failed 2.000s 20 tokens\n        error: boom"
+    ));
+    let value = serde_json::to_value(&trace).unwrap();
+    assert_eq!(
+        value["nodes"][1]["rounds"][0]["nodes"][0]["total_tokens"],
+        10
+    );
+}
+
+#[test]
+fn test_node_types_and_outputs() {
+    use response::{NodeFinishedData, NodeType};
+
+    let node_type: NodeType = serde_json::from_value(json!("knowledge-retrieval")).unwrap();
+    assert_eq!(node_type, NodeType::KnowledgeRetrieval);
+    let node_type: NodeType = serde_json::from_value(json!("custom-node")).unwrap();
+    assert_eq!(node_type, NodeType::Other("custom-node".into()));
+    assert_eq!(
+        serde_json::to_value(&node_type).unwrap(),
+        json!("custom-node")
+    );
+    assert_eq!(
+        NodeType::QuestionClassifier.to_string(),
+        "question-classifier"
+    );
+    assert!(NodeType::Loop.is_container());
+
+    let finished = |node_type: &str, outputs: serde_json::Value| -> NodeFinishedData {
+        serde_json::from_value(json!({"id": "e1", "node_id": "n1", "node_type": node_type,
+            "title": "Node", "index": 1, "outputs": outputs, "status": "succeeded",
+            "created_at": 1705395332}))
+        .unwrap()
+    };
+    let llm = finished(
+        "llm",
+        json!({"text": "hello", "finish_reason": "stop",
+            "usage": {"prompt_tokens": 3, "completion_tokens": 2, "total_tokens": 5,
+                "total_price": "0.001", "currency": "USD", "latency": 0.5}}),
+    );
+    let outputs = llm.llm_outputs().unwrap();
+    assert_eq!(outputs.text, "hello");
+    assert_eq!(outputs.usage.unwrap().total_tokens, 5);
+    assert!(llm.http_outputs().is_none());
+
+    let retrieval = finished(
+        "knowledge-retrieval",
+        json!({"result": [{"content": "chunk", "title": "doc.md", "metadata": {"score": 0.9}}]}),
+    );
+    let outputs = retrieval.retrieval_outputs().unwrap();
+    assert_eq!(outputs.result[0].content, "chunk");
+    assert_eq!(outputs.result[0].metadata["score"], 0.9);
+
+    let classifier = finished("question-classifier", json!({"class_name": "billing"}));
+    assert_eq!(
+        classifier.classifier_outputs().unwrap().class_name,
+        "billing"
+    );
+
+    let http = finished(
+        "http-request",
+        json!({"status_code": 404, "body": "missing", "headers": {"content-type": "text/plain"}, "files": []}),
+    );
+    let outputs = http.http_outputs().unwrap();
+    assert_eq!(outputs.status_code, 404);
+    assert_eq!(outputs.body, "missing");
+    assert!(http.outputs_as::<Vec<u32>>().is_err());
+}
+
+#[cfg(feature = "progress")]
+#[tokio::test]
+async fn test_workflow_progress() {
+    use dify_client::progress::{ProgressDrawTarget, WorkflowProgress};
+
+    let events = [
+        json!({"event": "workflow_started", "task_id": "t1", "workflow_run_id": "run-1",
+            "data": {"id": "run-1", "workflow_id": "wf-1", "sequence_number": 1,
+                "inputs": {}, "created_at": 1705395332}}),
+        json!({"event": "node_started", "task_id": "t1", "workflow_run_id": "run-1",
+            "data": {"id": "e1", "node_id": "llm", "node_type": "llm", "title": "LLM",
+                "index": 1, "predecessor_node_id": null, "inputs": {}, "created_at": 1705395332}}),
+        json!({"event": "text_chunk", "task_id": "t1", "workflow_run_id": "run-1",
+            "data": {"text": "hello\nwor", "from_variable_selector": ["llm", "text"]}}),
+        json!({"event": "text_chunk", "task_id": "t1", "workflow_run_id": "run-1",
+            "data": {"text": "ld", "from_variable_selector": null}}),
+        json!({"event": "node_finished", "task_id": "t1", "workflow_run_id": "run-1",
+            "data": {"id": "e1", "node_id": "llm", "index": 1, "predecessor_node_id": null,
+                "outputs": {"text": "hello\nworld"}, "status": "succeeded", "elapsed_time": 1.5,
+                "execution_metadata": {"total_tokens": 12}, "created_at": 1705395332}}),
+        json!({"event": "workflow_finished", "task_id": "t1", "workflow_run_id": "run-1",
+            "data": {"id": "run-1", "workflow_id": "wf-1", "status": "succeeded",
+                "outputs": {"text": "hello\nworld"}, "error": null, "elapsed_time": 1.6,
+                "total_tokens": 12, "total_steps": 1, "created_at": 1705395332,
+                "finished_at": 1705395334}}),
+    ];
+    let events: Vec<response::SseMessageEvent> = events
+        .into_iter()
+        .map(|event| serde_json::from_value(event).unwrap())
+        .collect();
+
+    let mut progress = WorkflowProgress::with_draw_target(ProgressDrawTarget::hidden());
+    for event in &events[..4] {
+        progress.handle(event);
+    }
+    assert_eq!(progress.text(), "hello\nworld");
+    assert_eq!(progress.trace().nodes[0].status, None);
+
+    let stream = futures::stream::iter(events[4..].iter().cloned().map(Ok));
+    let trace = progress.render(stream).await.unwrap();
+    assert_eq!(trace.status, Some(response::FinishedStatus::Succeeded));
+    assert_eq!(trace.nodes[0].total_tokens, Some(12));
+
+    let stream = futures::stream::iter([Err(anyhow::anyhow!("Stream broken"))]);
+    let progress = WorkflowProgress::with_draw_target(ProgressDrawTarget::hidden());
+    assert!(progress.render(stream).await.is_err());
+}
+
+#[tokio::test]
+async fn test_files_upload_document() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/files/upload"))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+            "id": "file-1", "name": "notes.md", "size": 7, "extension": "md",
+            "mime_type": "text/markdown", "created_by": "u1", "created_at": 1705395332
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let api = Client::new(&server.uri(), "API_KEY").api();
+    let data = request::FilesUploadRequest {
+        file: "# notes".into(),
+        user: "afa".into(),
+    };
+    let error = api.files_upload(data.clone()).await.unwrap_err();
+    assert_eq!(error.to_string(), "FilesUploadRequest.FileName Illegal");
+
+    let resp = api.files_upload_with_name("notes.md", data).await.unwrap();
+    assert_eq!(
+        request::FileType::from_extension(&resp.extension),
+        request::FileType::Document
+    );
+    assert_eq!(
+        request::FileType::from_extension(".PNG"),
+        request::FileType::Image
+    );
+}
+
+#[cfg(feature = "batch")]
+fn workflows_run_result(status: &str, text: &str) -> serde_json::Value {
+    json!({
+        "workflow_run_id": format!("run-{}", text),
+        "task_id": "t1",
+        "data": {"id": "run-1", "workflow_id": "wf-1", "status": status,
+            "outputs": {"summary": text}, "error": if status == "failed" { json!("boom") } else { json!(null) },
+            "elapsed_time": 1.0, "total_tokens": 10, "total_steps": 2,
+            "created_at": 1705395332, "finished_at": 1705395333}
+    })
+}
+
+#[cfg(feature = "batch")]
+#[tokio::test]
+async fn test_batch_runner() {
+    use dify_client::batch::{self, BatchRunner, BatchStatus};
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/parameters"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "user_input_form": [
+                {"text-input": {"label": "Text", "variable": "text", "required": true}},
+                {"number": {"label": "Count", "variable": "count", "required": false}},
+                {"checkbox": {"label": "Flag", "variable": "flag", "required": false}}
+            ]
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/workflows/run"))
+        .and(body_partial_json(json!({"inputs": {"text": "b"}})))
+        .respond_with(ResponseTemplate::new(503).set_body_json(json!({
+            "code": "unavailable", "message": "busy", "status": 503
+        })))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/workflows/run"))
+        .and(body_partial_json(json!({"inputs": {"text": "c"}})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(workflows_run_result("failed", "c")))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/workflows/run"))
+        .and(body_partial_json(json!({"user": "dify-batch"})))
+        .respond_with(|req: &wiremock::Request| {
+            let body: serde_json::Value = req.body_json().unwrap();
+            let text = body["inputs"]["text"].as_str().unwrap_or_default();
+            ResponseTemplate::new(200).set_body_json(workflows_run_result("succeeded", text))
+        })
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("rows.csv");
+    let output = dir.path().join("results.jsonl");
+    std::fs::write(
+        &input,
+        "article,count,flag,note\na,1,yes,x\nb,2,no,y\nc,,,z\nd,abc,,w\n,3,,v\n",
+    )
+    .unwrap();
+    let rows = batch::read_rows(&input).unwrap();
+    assert_eq!(rows.len(), 5);
+    assert_eq!(rows[1].values["article"], "b");
+
+    let runner = BatchRunner::new(Client::new(&server.uri(), "API_KEY").api())
+        .with_concurrency(2)
+        .with_retries(2, Duration::from_millis(10))
+        .with_mapping("article", "text");
+    let summary = runner.run_file(&input, &output).await.unwrap();
+    assert_eq!(
+        (
+            summary.total,
+            summary.succeeded,
+            summary.failed,
+            summary.invalid
+        ),
+        (5, 2, 1, 2)
+    );
+    assert_eq!(summary.total_tokens, 30);
+
+    let results = batch::read_results(&output).unwrap();
+    assert_eq!(results.len(), 5);
+    assert_eq!(results[0].outputs, Some(json!({"summary": "a"})));
+    assert_eq!(results[1].status, BatchStatus::Succeeded);
+    assert_eq!(results[1].attempts, 2);
+    assert_eq!(results[2].error.as_deref(), Some("boom"));
+    assert_eq!(results[3].status, BatchStatus::Invalid);
+    assert!(results[4].error.as_deref().unwrap().contains("text"));
+
+    // resume after a crash which tore the last line
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&output)
+        .unwrap();
+    std::io::Write::write_all(&mut file, b"{\"index\": 0, \"sta").unwrap();
+    let summary = runner.run_file(&input, &output).await.unwrap();
+    assert_eq!((summary.skipped, summary.succeeded), (5, 0));
+    let summary = runner
+        .clone()
+        .with_retry_failed(true)
+        .run_file(&input, &output)
+        .await
+        .unwrap();
+    assert_eq!(
+        (summary.skipped, summary.failed, summary.invalid),
+        (2, 1, 2)
+    );
+    assert_eq!(batch::read_results(&output).unwrap().len(), 5);
+
+    // a row edited in the input is run again
+    std::fs::write(
+        &input,
+        "article,count,flag,note\ne,1,yes,x\nb,2,no,y\nc,,,z\nd,abc,,w\n,3,,v\n",
+    )
+    .unwrap();
+    let summary = runner.run_file(&input, &output).await.unwrap();
+    assert_eq!((summary.skipped, summary.succeeded), (4, 1));
+    let results = batch::read_results(&output).unwrap();
+    assert_eq!(results[0].outputs, Some(json!({"summary": "e"})));
+    assert_ne!(results[0].row_hash, Some(rows[0].hash()));
+    assert_eq!(results[1].row_hash, Some(rows[1].hash()));
+}
+
+#[cfg(feature = "batch")]
+#[tokio::test]
+async fn test_batch_runner_unexpected_body() {
+    use dify_client::batch::{self, BatchRunner, BatchStatus};
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/parameters"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "user_input_form": [
+                {"text-input": {"label": "Text", "variable": "text", "required": true}}
+            ]
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/workflows/run"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<html>moved</html>"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("rows.jsonl");
+    let output = dir.path().join("results.jsonl");
+    std::fs::write(&input, "{\"text\": \"a\"}\n").unwrap();
+
+    // a successful response with an unexpected body is not retried
+    let runner = BatchRunner::new(Client::new(&server.uri(), "API_KEY").api())
+        .with_retries(2, Duration::from_millis(10));
+    let summary = runner.run_file(&input, &output).await.unwrap();
+    assert_eq!(summary.failed, 1);
+    let results = batch::read_results(&output).unwrap();
+    assert_eq!(results[0].status, BatchStatus::Failed);
+    assert_eq!(results[0].attempts, 1);
+    assert!(results[0]
+        .error
+        .as_deref()
+        .unwrap()
+        .contains("\"status\":200"));
+}
+
+#[cfg(feature = "harness")]
+#[tokio::test]
+async fn test_regression_harness() {
+    use dify_client::harness::{BaselineMode, CaseStatus, TestRunner, TestSuite};
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat-messages"))
+        .respond_with(|req: &wiremock::Request| {
+            let body: serde_json::Value = req.body_json().unwrap();
+            let (answer, tokens) = match body["query"].as_str().unwrap_or_default() {
+                "boom" => {
+                    return ResponseTemplate::new(500).set_body_json(json!({
+                        "code": "internal_server_error", "message": "boom", "status": 500
+                    }))
+                }
+                "classify" => (r#"{"category": "billing", "score": [1, 2]}"#, 50),
+                _ => ("Hello! How can I help?", 900),
+            };
+            let mut resp = chat_messages_response("c1");
+            resp["answer"] = json!(answer);
+            resp["metadata"] = json!({"usage": {"total_tokens": tokens}});
+            ResponseTemplate::new(200).set_body_json(resp)
+        })
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let suite_path = dir.path().join("support.yaml");
+    std::fs::write(
+        &suite_path,
+        r#"
+app: chat
+baseline: support.baseline.json
+assert:
+  - max_latency_ms: 60000
+cases:
+  - name: greeting
+    query: hi
+    assert:
+      - contains: Hello
+      - regex: "(?i)how can i help"
+  - name: classify
+    query: classify
+    assert:
+      - json_path: { path: "$.category", equals: billing }
+      - json_path: { path: "score[1]", equals: 2 }
+      - max_tokens: 100
+  - name: french <&>
+    query: hi
+    assert:
+      - contains: Bonjour
+      - max_tokens: 100
+  - name: broken
+    query: boom
+  - name: later
+    query: hi
+    skip: true
+"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("support.baseline.json"),
+        r#"{"greeting": "Hello!\nHow can I help?"}"#,
+    )
+    .unwrap();
+
+    let client = Client::new(&server.uri(), "app-test");
+    let runner = TestRunner::new(client.api()).with_baseline_mode(BaselineMode::Fail);
+    let report = runner.run_file(&suite_path).await.unwrap();
+    assert_eq!(report.suite, "support");
+    assert_eq!(
+        (
+            report.tests,
+            report.passed,
+            report.failed,
+            report.errors,
+            report.skipped
+        ),
+        (5, 1, 2, 1, 1)
+    );
+    assert!(!report.is_success());
+    let greeting = &report.cases[0];
+    assert_eq!(greeting.status, CaseStatus::Failed);
+    assert!(greeting
+        .diff
+        .as_ref()
+        .unwrap()
+        .contains("+Hello! How can I help?"));
+    assert_eq!(report.cases[1].status, CaseStatus::Passed);
+    assert_eq!(report.cases[1].total_tokens, Some(50));
+    assert_eq!(report.cases[2].failures.len(), 2);
+    assert!(report.cases[3].error.as_ref().unwrap().contains("boom"));
+    assert_eq!(report.cases[4].status, CaseStatus::Skipped);
+
+    let junit = report.to_junit();
+    assert!(junit
+        .contains(r#"<testsuite name="support" tests="5" failures="2" errors="1" skipped="1""#));
+    assert!(junit.contains(r#"<testcase name="french &lt;&amp;&gt;" classname="support""#));
+    assert!(
+        junit.contains(r#"<failure message="expected the answer to contain &quot;Bonjour&quot;">"#)
+    );
+    assert!(junit.contains("<skipped/>"));
+    let json_path = dir.path().join("reports").join("support.json");
+    report.write_json(&json_path).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+    assert_eq!(json["cases"][3]["status"], "error");
+
+    // updating the baseline accepts the answers of the passed cases, later runs no longer differ
+    runner
+        .clone()
+        .with_baseline_mode(BaselineMode::Update)
+        .run_file(&suite_path)
+        .await
+        .unwrap();
+    let baseline: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(dir.path().join("support.baseline.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(baseline["greeting"], "Hello! How can I help?");
+    assert!(baseline.get("broken").is_none());
+    assert!(baseline.get("french <&>").is_none());
+    let report = runner.run_file(&suite_path).await.unwrap();
+    assert_eq!(report.cases[0].status, CaseStatus::Passed);
+    assert!(report.cases.iter().all(|case| case.diff.is_none()));
+
+    assert!(TestSuite::from_yaml("cases: [{name: a}, {name: a}]").is_err());
+    let e = TestSuite::from_yaml("cases: [{name: a, assert: [{regex: '('}]}]").unwrap_err();
+    assert!(e.to_string().contains("Assertion.Regex Illegal"));
+    assert!(TestSuite::from_yaml(
+        "cases: [{name: a, assert: [{json_path: {path: '$.', equals: 1}}]}]"
+    )
+    .is_err());
+}
+
+#[tokio::test]
+async fn test_cassette_record_replay() {
+    use dify_client::{
+        api::ApiPath,
+        cassette::{Cassette, CassetteMode, RecordedBody, REDACTED},
+    };
+
+    let dir = tempfile::tempdir().unwrap();
+    let cassette_path = dir.path().join("chat.json");
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat-messages"))
+        .and(body_partial_json(json!({"response_mode": "blocking"})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(chat_messages_response("c1")))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let sse = format!(
+        "data: {}\n\ndata: {}\n\n",
+        json!({"event": "message", "message_id": "m2", "conversation_id": "c1",
+            "created_at": 1705395332, "id": "m2", "task_id": "t1", "answer": "hi "}),
+        json!({"event": "message", "message_id": "m2", "conversation_id": "c1",
+            "created_at": 1705395332, "id": "m2", "task_id": "t1", "answer": "there"})
+    );
+    Mock::given(method("POST"))
+        .and(path("/v1/chat-messages"))
+        .and(body_partial_json(json!({"response_mode": "streaming"})))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(sse, "text/event-stream"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/files/upload"))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+            "id": "file-1", "name": "notes.md", "size": 7, "extension": "md",
+            "mime_type": "text/markdown", "created_by": "u1", "created_at": 1705395332
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat-messages/t1/stop"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"result": "success"})))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let chat = |query: &str| request::ChatMessagesRequest {
+        query: query.into(),
+        user: "afa".into(),
+        ..Default::default()
+    };
+    let upload = request::FilesUploadRequest {
+        file: "# notes".into(),
+        user: "afa".into(),
+    };
+    async fn stream_answer(
+        api: &dify_client::api::Api,
+        data: request::ChatMessagesRequest,
+    ) -> String {
+        let mut stream = api.chat_messages_stream(data).await.unwrap();
+        let mut answer = String::new();
+        while let Some(event) = stream.next().await {
+            if let response::SseMessageEvent::Message { answer: chunk, .. } = event.unwrap() {
+                answer.push_str(&chunk);
+            }
+        }
+        answer
+    }
+
+    // record
+    let cassette = Cassette::new(&cassette_path, CassetteMode::Auto).unwrap();
+    assert_eq!(cassette.mode(), CassetteMode::Record);
+    let api = Client::builder()
+        .base_url(server.uri())
+        .api_key("SECRET_KEY")
+        .cassette(cassette.clone())
+        .build()
+        .unwrap()
+        .api();
+    assert_eq!(
+        api.chat_messages(chat("hello")).await.unwrap().answer,
+        "hello"
+    );
+    assert_eq!(stream_answer(&api, chat("stream")).await, "hi there");
+    assert_eq!(
+        api.files_upload_with_name("notes.md", upload.clone())
+            .await
+            .unwrap()
+            .id,
+        "file-1"
+    );
+    let saved_interactions = |path: &std::path::Path| {
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        json["interactions"].as_array().unwrap().len()
+    };
+    assert!(!cassette_path.exists());
+    cassette.save().unwrap();
+    assert_eq!(saved_interactions(&cassette_path), 3);
+    let stop = request::StreamTaskStopRequest {
+        task_id: "t1".into(),
+        user: "afa".into(),
+    };
+    api.chat_messages_stop(stop).await.unwrap();
+    server.verify().await;
+    drop(server);
+
+    // the unsaved interactions are saved when the last clone is dropped
+    let interactions = cassette.interactions();
+    assert_eq!(interactions.len(), 4);
+    drop(api);
+    drop(cassette);
+    assert_eq!(saved_interactions(&cassette_path), 4);
+    let json = std::fs::read_to_string(&cassette_path).unwrap();
+    assert!(!json.contains("SECRET_KEY"));
+    assert_eq!(interactions[0].request.headers["authorization"], REDACTED);
+    assert!(!interactions[1].response.chunks.is_empty());
+    assert!(interactions[1].response.body.is_none());
+    let upload_request = &interactions[2].request;
+    assert_eq!(upload_request.api_path(), Some(ApiPath::FilesUpload));
+    assert!(upload_request.headers["content-type"].ends_with("boundary=dify-cassette-boundary"));
+    match &upload_request.body {
+        Some(RecordedBody::Text(body)) => {
+            assert!(body.contains("--dify-cassette-boundary"));
+            assert!(body.contains("# notes"));
+        }
+        body => panic!("unexpected upload body: {:?}", body),
+    }
+
+    // replay, with no server and a different task ID
+    let cassette = Cassette::new(&cassette_path, CassetteMode::Auto)
+        .unwrap()
+        .with_time_scale(0.0);
+    assert_eq!(cassette.mode(), CassetteMode::Replay);
+    let api = Client::builder()
+        .base_url("http://127.0.0.1:9/api")
+        .api_key("OTHER_KEY")
+        .cassette(cassette)
+        .build()
+        .unwrap()
+        .api();
+    assert_eq!(stream_answer(&api, chat("stream")).await, "hi there");
+    assert_eq!(
+        api.chat_messages(chat("hello")).await.unwrap().answer,
+        "hello"
+    );
+    // the last matching interaction is repeated
+    assert_eq!(
+        api.chat_messages(chat("hello")).await.unwrap().answer,
+        "hello"
+    );
+    assert_eq!(
+        api.files_upload_with_name("notes.md", upload)
+            .await
+            .unwrap()
+            .id,
+        "file-1"
+    );
+    let stop = request::StreamTaskStopRequest {
+        task_id: "t2".into(),
+        user: "afa".into(),
+    };
+    api.chat_messages_stop(stop).await.unwrap();
+    let error = api.chat_messages(chat("unknown")).await.unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("has no interaction for POST /api/v1/chat-messages"));
+
+    assert_eq!(
+        ApiPath::from_path("/v1/workflows/logs"),
+        Some(ApiPath::WorkflowsLogs)
+    );
+    assert_eq!(
+        ApiPath::from_path("/v1/workflows/wf-1/run"),
+        Some(ApiPath::WorkflowsRunById)
+    );
+    assert_eq!(ApiPath::from_path("/v2/other"), None);
+    assert_eq!(
+        ApiPath::from_path(ApiPath::ConversationVariableUpdate.as_str()),
+        Some(ApiPath::ConversationVariableUpdate)
+    );
+}
+
+#[tokio::test]
+async fn test_response_cache() {
+    use dify_client::{api::ApiPath, cache::ResponseCache};
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/parameters"))
+        .and(header_eq("if-none-match", "\"v1\""))
+        .respond_with(ResponseTemplate::new(304))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/parameters"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("etag", "\"v1\"")
+                .set_body_json(parameters_response()),
+        )
+        .expect(3)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/meta"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"tool_icons": {}})))
+        .expect(3)
+        .mount(&server)
+        .await;
+
+    let client = Client::new(&server.uri(), "API_KEY");
+    let params = |user: &str| request::ParametersRequest { user: user.into() };
+    let meta = |user: &str| request::MetaRequest { user: user.into() };
+
+    // fresh responses are shared by the users of an app, per API key
+    let cache = ResponseCache::new();
+    let api = client.api().with_cache(cache.clone());
+    api.meta(meta("alice")).await.unwrap();
+    api.meta(meta("bob")).await.unwrap();
+    assert_eq!(cache.len(), 1);
+    let other = api.clone().with_api_key("OTHER_KEY").unwrap();
+    other.meta(meta("alice")).await.unwrap();
+    assert_eq!(cache.len(), 2);
+    cache.invalidate(&other);
+    assert_eq!(cache.len(), 1);
+
+    // expired responses are revalidated with their ETag
+    let cache = ResponseCache::new().with_ttl(Duration::ZERO);
+    let api = client.api().with_cache(cache.clone());
+    let first = api.parameters(params("alice")).await.unwrap();
+    let second = api.parameters(params("bob")).await.unwrap();
+    assert_eq!(first.user_input_form.len(), 4);
+    assert_eq!(second.user_input_form.len(), 4);
+    cache.invalidate_path(&api, ApiPath::Parameters);
+    assert!(cache.is_empty());
+    api.parameters(params("alice")).await.unwrap();
+
+    // the least recently used response is evicted
+    let cache = ResponseCache::new().with_max_entries(1);
+    let api = client.api().with_cache(cache.clone());
+    api.parameters(params("alice")).await.unwrap();
+    api.meta(meta("alice")).await.unwrap();
+    assert_eq!(cache.len(), 1);
+    server.verify().await;
+}
@@ -1,5 +1,9 @@
 use anyhow::Result;
-use dify_client::{http::header, request, response, Client, Config};
+use dify_client::{
+    config::{ConfigFile, ConfigFormat},
+    http::header,
+    request, response, Client, ClientPool, Config,
+};
 use futures::StreamExt;
 use std::{collections::HashMap, env, time::Duration};
 
@@ -15,9 +19,57 @@ fn test_config() {
     assert_eq!(config.timeout, Duration::from_secs(30));
 }
 
+#[test]
+fn test_config_from_env() {
+    env::set_var("DIFY_TEST_ENV_API_KEY", "API_KEY");
+    env::set_var("DIFY_TEST_ENV_BASE_URL", "https://dify.example.com");
+    env::set_var("DIFY_TEST_ENV_TIMEOUT", "90");
+    let config = Config::from_env_with_prefix("DIFY_TEST_ENV").expect("load config failed");
+    assert_eq!(config.base_url, "https://dify.example.com");
+    assert_eq!(config.api_key, "API_KEY");
+    assert_eq!(config.timeout, Duration::from_secs(90));
+
+    let result = Config::from_env_with_prefix("DIFY_TEST_ENV_MISSING");
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_config_file() {
+    let text = r#"
+        base_url = "https://api.dify.ai"
+        timeout = 60
+        default_app = "support-bot"
+
+        [apps.support-bot]
+        api_key = "app-support"
+
+        [apps.summary-workflow]
+        api_key = "app-summary"
+        base_url = "https://dify.example.com"
+        timeout = 300
+    "#;
+    let file = ConfigFile::parse(text, ConfigFormat::Toml).expect("parse config failed");
+    let config = file.default_config().expect("default app failed");
+    assert_eq!(config.base_url, "https://api.dify.ai");
+    assert_eq!(config.api_key, "app-support");
+    assert_eq!(config.timeout, Duration::from_secs(60));
+
+    let config = file.app("summary-workflow").expect("named app failed");
+    assert_eq!(config.base_url, "https://dify.example.com");
+    assert_eq!(config.api_key, "app-summary");
+    assert_eq!(config.timeout, Duration::from_secs(300));
+    assert!(file.app("unknown").is_err());
+
+    let json = r#"{"apps": {"only": {"api_key": "app-only"}}}"#;
+    let file = ConfigFile::parse(json, ConfigFormat::Json).expect("parse config failed");
+    let config = file.default_config().expect("default app failed");
+    assert_eq!(config.api_key, "app-only");
+    assert_eq!(config.base_url, Config::default().base_url);
+}
+
 #[test]
 fn test_new_client() {
-    let client = Client::new("https://api.dify.ai".into(), "API_KEY".into());
+    let client = Client::new("https://api.dify.ai", "API_KEY");
     assert_eq!(client.config.base_url, "https://api.dify.ai");
     assert_eq!(client.config.api_key, "API_KEY");
     assert_eq!(client.config.timeout, Duration::from_secs(30));
@@ -36,6 +88,96 @@ fn test_new_client_with_config() {
     assert_eq!(client.config.timeout, Duration::from_secs(60));
 }
 
+#[test]
+fn test_client_builder() {
+    let client = Client::builder()
+        .base_url("https://api.dify.ai/")
+        .api_key("API_KEY")
+        .timeout(Duration::from_secs(60))
+        .connect_timeout(Duration::from_secs(5))
+        .user_agent("dify-client-test")
+        .default_header(
+            header::HeaderName::from_static("x-trace-id"),
+            header::HeaderValue::from_static("trace"),
+        )
+        .pool_max_idle_per_host(4)
+        .build()
+        .expect("build client failed");
+    assert_eq!(client.config.base_url, "https://api.dify.ai");
+    assert_eq!(client.config.api_key, "API_KEY");
+    assert_eq!(client.config.timeout, Duration::from_secs(60));
+}
+
+#[test]
+fn test_client_builder_illegal_api_key() {
+    let result = Client::builder().api_key("API_KEY\n").build();
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_client_builder_http_client() {
+    let http_client = reqwest::Client::new();
+    let client = Client::builder()
+        .api_key("API_KEY")
+        .http_client(http_client)
+        .build()
+        .expect("build client failed");
+    assert_eq!(client.config.api_key, "API_KEY");
+}
+
+#[test]
+fn test_client_pool() {
+    let template = Client::new("https://api.dify.ai", "API_KEY_DEFAULT");
+    let mut pool = ClientPool::new(template);
+    pool.register("support-bot", "API_KEY_SUPPORT", None)
+        .expect("register app failed");
+    pool.register(
+        "sales-bot",
+        "API_KEY_SALES",
+        Some("https://dify.example.com/"),
+    )
+    .expect("register app failed");
+    assert!(pool.register("illegal", "API_KEY\n", None).is_err());
+
+    let client = pool.client("support-bot").expect("app not found");
+    assert_eq!(client.config.base_url, "https://api.dify.ai");
+    assert_eq!(client.config.api_key, "API_KEY_SUPPORT");
+    assert_eq!(client.config.timeout, Duration::from_secs(30));
+    let client = pool.client("sales-bot").expect("app not found");
+    assert_eq!(client.config.base_url, "https://dify.example.com");
+    assert_eq!(client.config.api_key, "API_KEY_SALES");
+    assert!(pool.app("unknown").is_err());
+
+    let mut names = pool.names().collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, vec!["sales-bot", "support-bot"]);
+}
+
+#[test]
+fn test_owned_api() {
+    fn assert_static_send<T: Send + 'static>(_: &T) {}
+
+    let client = Client::new("https://api.dify.ai", "API_KEY");
+    let api = client.api().with_user("afa");
+    drop(client);
+    assert_static_send(&api);
+    assert_eq!(api.default_user(), Some("afa"));
+
+    let api = api
+        .with_api_key("API_KEY_OVERRIDE")
+        .expect("override failed");
+    assert_eq!(api.client().config.api_key, "API_KEY_OVERRIDE");
+    assert_eq!(api.client().config.base_url, "https://api.dify.ai");
+    assert!(api.with_api_key("API_KEY\n").is_err());
+
+    fn assert_send<T: Send>(_: &T) {}
+    fn assert_static_stream<T: futures::Stream + Send + 'static>() {}
+    let api = Client::new("https://api.dify.ai", "API_KEY").api();
+    let future = api.chat_messages_stream(request::ChatMessagesRequest::default());
+    assert_send(&future);
+    assert_static_stream::<response::SseMessageEventStream<dify_client::api::BytesStream>>();
+}
+
 fn get_client(api_key: Option<&str>) -> Client {
     let dify_base_url = env::var("DIFY_BASE_URL").expect("DIFY_BASE_URL is not set");
     let dify_api_key = env::var("DIFY_API_KEY").expect("DIFY_API_KEY is not set");
@@ -243,7 +385,7 @@ async fn test_conversations_get() {
     assert!(result.is_ok());
     let response = result.unwrap();
     println!("{:}", serde_json::to_string_pretty(&response).unwrap());
-    assert!(response.data.len() > 0);
+    assert!(!response.data.is_empty());
 }
 
 #[tokio::test]
@@ -258,7 +400,7 @@ async fn test_messages_get() {
     assert!(result.is_ok());
     let response = result.unwrap();
     println!("{:}", serde_json::to_string_pretty(&response).unwrap());
-    assert!(response.data.len() > 0);
+    assert!(!response.data.is_empty());
 }
 
 #[tokio::test]
@@ -269,7 +411,7 @@ async fn test_parameters() {
     assert!(result.is_ok());
     let response = result.unwrap();
     println!("{:}", serde_json::to_string_pretty(&response).unwrap());
-    assert!(response.system_parameters.image_file_size_limit.len() > 0);
+    assert!(!response.system_parameters.image_file_size_limit.is_empty());
 }
 
 #[tokio::test]
@@ -424,8 +566,7 @@ Chatflow is set to overtake "expert mode" in current Chatbot apps. You may choos
             response::SseMessageEvent::WorkflowFinished { data, .. } => {
                 let output = data
                     .outputs
-                    .map(|o| o["output"].as_str().map(|s| s.to_owned()))
-                    .flatten()
+                    .and_then(|o| o["output"].as_str().map(|s| s.to_owned()))
                     .unwrap_or_default();
                 println!("output: {:?}", output);
                 outputs.push(output);